@@ -86,6 +86,7 @@ fn main() {
             WallpaperDisplayMode::Wallpaper
         },
         linux_backend,
+        ..default()
     });
 
     app.add_systems(Startup, setup)
@@ -193,7 +194,7 @@ fn setup(
     commands.spawn((
         Camera3d::default(),
         Transform::from_xyz(0.0, 7., 14.0).looking_at(Vec3::new(0., 1., 0.), Vec3::Y),
-        LiveWallpaperCamera,
+        LiveWallpaperCamera::default(),
     ));
 }
 
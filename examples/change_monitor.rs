@@ -1,5 +1,6 @@
 //! Change the target monitor at runtime.
-//! Works on Windows and Wayland (with the `wayland` feature).
+//! Cycles between the first monitor and all monitors every 5 seconds.
+//! Works on Windows, Wayland and X11.
 
 use bevy::prelude::*;
 use bevy_live_wallpaper::{LiveWallpaperCamera, LiveWallpaperPlugin, WallpaperTargetMonitor};
@@ -33,7 +34,7 @@ fn main() {
 }
 
 fn setup_scene(mut commands: Commands) {
-    commands.spawn((Camera2d, LiveWallpaperCamera));
+    commands.spawn((Camera2d, LiveWallpaperCamera::default()));
 
     commands.spawn((
         Sprite::from_color(Color::srgb(0.15, 0.4, 0.85), Vec2::splat(1600.0)),
@@ -43,14 +44,19 @@ fn setup_scene(mut commands: Commands) {
 
 fn change_monitor(
     mut wallpaper_target: ResMut<WallpaperTargetMonitor>,
-    mut has_run: Local<bool>,
+    mut timer: Local<Option<Timer>>,
     time: Res<Time>,
 ) {
-    // Switch after 5 seconds once.
-    if *has_run || time.elapsed_secs() < 5.0 {
+    let timer = timer.get_or_insert_with(|| Timer::from_seconds(5.0, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
         return;
     }
-    *has_run = true;
 
-    *wallpaper_target = WallpaperTargetMonitor::All;
+    let next = if *wallpaper_target == WallpaperTargetMonitor::All {
+        WallpaperTargetMonitor::Index(0)
+    } else {
+        WallpaperTargetMonitor::All
+    };
+    info!("Switching wallpaper target to {next:?}");
+    *wallpaper_target = next;
 }
@@ -35,7 +35,7 @@ fn main() {
 }
 
 fn spawn_camera(mut commands: Commands) {
-    commands.spawn((Camera2d, LiveWallpaperCamera));
+    commands.spawn((Camera2d, LiveWallpaperCamera::default()));
 }
 
 fn handle_pointer_state(
@@ -32,7 +32,7 @@ fn main() {
 fn setup_scene(mut commands: Commands) {
     // Spawn a camera. On Wayland/X11 this component is required; on Windows
     // it is optional but harmless to keep for consistency.
-    commands.spawn((Camera2d, LiveWallpaperCamera));
+    commands.spawn((Camera2d, LiveWallpaperCamera::default()));
 
     // ... spawn your scene entities here ...
     commands.spawn((
@@ -8,24 +8,20 @@
 
 use bevy::{math::Isometry2d, prelude::*};
 use bevy_live_wallpaper::{
-    LiveWallpaperCamera, LiveWallpaperPlugin, WallpaperDisplayMode, WallpaperPointerState,
-    WallpaperSurfaceInfo,
+    LiveWallpaperCamera, LiveWallpaperPlugin, WallpaperPointerState, WallpaperSurfaceInfo,
 };
 
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .add_plugins(LiveWallpaperPlugin {
-            display_mode: WallpaperDisplayMode::Windowed,
-            ..default()
-        })
+        .add_plugins(LiveWallpaperPlugin::new().windowed())
         .add_systems(Startup, setup)
         .add_systems(Update, (debug_overlay, debug_cursor))
         .run();
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn((Camera2d, LiveWallpaperCamera));
+    commands.spawn((Camera2d, LiveWallpaperCamera::default()));
 
     commands.spawn((
         Sprite::from_color(Color::srgb(0.2, 0.6, 0.9), Vec2::splat(300.0)),
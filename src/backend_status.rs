@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+/// Connection state of the active wallpaper backend.
+///
+/// Backends that can lose their display server connection (currently
+/// Wayland) keep this up to date so scenes can pause work while offline.
+/// A backend that could not be initialized at all reports
+/// [`WallpaperBackendStatus::Failed`] instead of panicking.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub enum WallpaperBackendStatus {
+    /// The backend is connected and presenting.
+    #[default]
+    Connected,
+    /// The connection was lost; a reconnection attempt is scheduled.
+    Disconnected,
+    /// A reconnection attempt is in progress.
+    Reconnecting,
+    /// The backend could not be initialized; nothing is presented. Holds the
+    /// reason. The Wayland backend keeps retrying with backoff unless a
+    /// [`crate::WaylandFallback`] took over.
+    Failed(String),
+    /// The display server lacks a protocol the backend needs (e.g. a Wayland
+    /// compositor without `zwlr_layer_shell_v1`) and no fallback was used.
+    Unsupported,
+    /// The display server closed the wallpaper for good (all layer surfaces
+    /// closed, or the X11 connection broke).
+    Closed,
+}
+
+impl WallpaperBackendStatus {
+    pub fn is_connected(&self) -> bool {
+        matches!(self, Self::Connected)
+    }
+}
+
+/// Which backend draws the wallpaper.
+///
+/// Inserted by the wallpaper backend the plugin picked (after any fallback);
+/// missing when none could be added.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Resource)]
+pub enum WallpaperBackend {
+    Wayland,
+    X11,
+    Windows,
+    MacOs,
+    /// A normal Bevy window, i.e. [`crate::WallpaperDisplayMode::Windowed`].
+    Windowed,
+}
+
+/// Sent once when the backend shuts down for good and enters
+/// [`WallpaperBackendStatus::Closed`].
+///
+/// With [`crate::WallpaperShutdownBehavior::KeepRunning`] the app stays
+/// alive; read this message from your own system to decide what to do.
+#[derive(Message, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct WallpaperBackendClosed;
+
+/// (X11 only) What the X server offers for compositing the wallpaper.
+///
+/// Inserted by the X11 backend once connected. Without the Composite
+/// extension nothing composites the windows, so effects that rely on
+/// transparency or blending with the desktop won't be visible.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperX11Info {
+    /// Whether the server has the Composite extension.
+    pub composite_available: bool,
+}
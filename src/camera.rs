@@ -2,5 +2,24 @@ use bevy::prelude::*;
 
 /// Marks a camera whose output should be redirected to the wallpaper surface.
 /// This component is used by non-windowed backends such as Wayland and X11.
-#[derive(Component, Default)]
-pub struct LiveWallpaperCamera;
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct LiveWallpaperCamera {
+    /// Restricts the camera to a single output's region of the wallpaper.
+    ///
+    /// When set, the backend keeps the camera's viewport on the sub-rectangle
+    /// of the combined render target that belongs to this output (Wayland
+    /// output name, as reported in `PointerSample::output`). `None` renders to
+    /// the whole wallpaper area. Cameras sharing the wallpaper target still need
+    /// distinct `Camera::order` values. Currently honored by the Wayland backend.
+    pub output_id: Option<u32>,
+}
+
+impl LiveWallpaperCamera {
+    /// Camera that only renders the region of the given output.
+    pub fn for_output(output_id: u32) -> Self {
+        Self {
+            output_id: Some(output_id),
+        }
+    }
+}
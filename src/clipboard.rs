@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+/// Reads and writes the system clipboard, e.g. to paste an image into an
+/// interactive wallpaper.
+///
+/// Only implemented on Windows. Wayland and X11 aren't supported yet: on
+/// Wayland `wl_data_device` and primary selection hand the selection only to
+/// the client with keyboard focus, which wallpaper surfaces rarely have, and
+/// an X11 selection owner has to keep serving requests from its own event
+/// loop. There, and on the other backends, reads return `None` and writes an
+/// error.
+#[derive(Resource, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperClipboard;
+
+impl WallpaperClipboard {
+    /// Text on the clipboard, if any.
+    pub fn get_text(&self) -> Option<String> {
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows_backend::clipboard_text()
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            None
+        }
+    }
+
+    /// Replaces the clipboard content with `text`.
+    pub fn set_text(&self, text: &str) -> Result<(), String> {
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows_backend::set_clipboard_text(text)
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = text;
+            Err("Clipboard access is not supported on this platform".into())
+        }
+    }
+
+    /// Bitmap on the clipboard as an sRGB RGBA image, added to `images`.
+    pub fn get_image(&self, images: &mut Assets<Image>) -> Option<Handle<Image>> {
+        #[cfg(target_os = "windows")]
+        {
+            crate::windows_backend::clipboard_image().map(|image| images.add(image))
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            let _ = images;
+            None
+        }
+    }
+}
+
+/// Decodes an uncompressed 24/32-bit device-independent bitmap (`CF_DIB`:
+/// a `BITMAPINFOHEADER` followed by the pixels) into RGBA.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn decode_dib(bytes: &[u8]) -> Option<Image> {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+    /// `BI_RGB` and `BI_BITFIELDS` from `wingdi.h`.
+    const BI_RGB: u32 = 0;
+    const BI_BITFIELDS: u32 = 3;
+
+    let u32_at = |at: usize| Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let i32_at = |at: usize| Some(i32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let header_size = u32_at(0)? as usize;
+    let header_width = i32_at(4)?;
+    let header_height = i32_at(8)?;
+    let bits = usize::from(u16::from_le_bytes(bytes.get(14..16)?.try_into().ok()?));
+    let compression = u32_at(16)?;
+    let colors_used = u32_at(32)? as usize;
+    if !matches!(bits, 24 | 32)
+        || !(compression == BI_RGB || (compression == BI_BITFIELDS && bits == 32))
+    {
+        debug!("Unsupported clipboard bitmap: {bits} bpp, compression {compression}");
+        return None;
+    }
+
+    let width = usize::try_from(header_width).ok().filter(|w| *w > 0)?;
+    let height = header_height.unsigned_abs() as usize;
+    let bottom_up = header_height > 0;
+    // Channel masks follow a plain BITMAPINFOHEADER with BI_BITFIELDS.
+    let masks = if compression == BI_BITFIELDS && header_size == 40 {
+        12
+    } else {
+        0
+    };
+    let offset = header_size + masks + colors_used * 4;
+    let stride = (width * bits).div_ceil(32) * 4;
+    let pixels = bytes.get(offset..offset + stride * height)?;
+
+    let channels = bits / 8;
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        let row = if bottom_up { height - 1 - y } else { y };
+        for pixel in pixels[row * stride..][..width * channels].chunks_exact(channels) {
+            let alpha = if channels == 4 { pixel[3] } else { u8::MAX };
+            data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], alpha]);
+        }
+    }
+    // Most producers leave the alpha byte of 32-bit bitmaps zeroed.
+    if channels == 4 && data.chunks_exact(4).all(|pixel| pixel[3] == 0) {
+        data.chunks_exact_mut(4)
+            .for_each(|pixel| pixel[3] = u8::MAX);
+    }
+
+    Some(Image::new(
+        Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 40-byte `BITMAPINFOHEADER` followed by `pixels`.
+    fn dib(width: i32, height: i32, bits: u16, compression: u32, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&40u32.to_le_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&bits.to_le_bytes());
+        bytes.extend_from_slice(&compression.to_le_bytes());
+        bytes.extend_from_slice(&[0; 20]);
+        bytes.extend_from_slice(pixels);
+        bytes
+    }
+
+    #[test]
+    fn decodes_bottom_up_24_bit_rows_with_padding() {
+        // Two BGR pixels per row, padded to 8 bytes; the bottom row comes first.
+        let pixels = [
+            0, 0, 255, 0, 255, 0, 0, 0, // bottom: red, green
+            255, 0, 0, 255, 255, 255, 0, 0, // top: blue, white
+        ];
+        let image = decode_dib(&dib(2, 2, 24, 0, &pixels)).unwrap();
+
+        assert_eq!(image.width(), 2);
+        assert_eq!(image.height(), 2);
+        assert_eq!(
+            image.data.unwrap(),
+            [
+                0, 0, 255, 255, 255, 255, 255, 255, // top
+                255, 0, 0, 255, 0, 255, 0, 255, // bottom
+            ]
+        );
+    }
+
+    #[test]
+    fn decodes_top_down_32_bit_bitfields() {
+        // Masks after the header, then one pixel with a zeroed alpha byte.
+        let mut pixels = Vec::new();
+        for mask in [0x00ff_0000u32, 0x0000_ff00, 0x0000_00ff] {
+            pixels.extend_from_slice(&mask.to_le_bytes());
+        }
+        pixels.extend_from_slice(&[30, 20, 10, 0]);
+        let image = decode_dib(&dib(1, -1, 32, 3, &pixels)).unwrap();
+
+        assert_eq!(image.data.unwrap(), [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn rejects_unsupported_or_truncated_bitmaps() {
+        assert!(decode_dib(&dib(1, 1, 8, 0, &[0; 4])).is_none());
+        assert!(decode_dib(&dib(1, 1, 24, 1, &[0; 4])).is_none());
+        assert!(decode_dib(&dib(2, 2, 24, 0, &[0; 8])).is_none());
+        assert!(decode_dib(&[0; 8]).is_none());
+    }
+}
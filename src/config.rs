@@ -0,0 +1,59 @@
+//! Plugin settings stored in a file, behind the `serde` feature.
+//!
+//! ```toml
+//! target_monitor = { name = "DP-1" }
+//! display_mode = "wallpaper"
+//! max_fps = 30
+//!
+//! [idle_policy]
+//! timeout = { secs = 300, nanos = 0 }
+//! idle_fps = 1.0
+//! ```
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::LiveWallpaperPlugin;
+
+/// Every [`LiveWallpaperPlugin`] field, with missing keys left at their
+/// defaults. Enum values are `snake_case`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WallpaperConfig {
+    #[serde(flatten)]
+    pub plugin: LiveWallpaperPlugin,
+}
+
+impl WallpaperConfig {
+    /// Reads the config from a TOML file.
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        Self::from_toml(&contents)
+            .map_err(|err| format!("Invalid config {}: {err}", path.display()))
+    }
+
+    /// Parses the config from a TOML string.
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|err| err.to_string())
+    }
+
+    /// Formats the config as TOML, e.g. to write out the defaults.
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string_pretty(self).map_err(|err| err.to_string())
+    }
+}
+
+impl From<WallpaperConfig> for LiveWallpaperPlugin {
+    fn from(config: WallpaperConfig) -> Self {
+        config.plugin
+    }
+}
+
+impl From<LiveWallpaperPlugin> for WallpaperConfig {
+    fn from(plugin: LiveWallpaperPlugin) -> Self {
+        Self { plugin }
+    }
+}
@@ -0,0 +1,24 @@
+use bevy::prelude::*;
+
+/// (Wayland only) Kind of content the wallpaper shows, passed to the
+/// compositor through `wp_content_type_v1`.
+///
+/// Compositors use it to tune scheduling, e.g. KDE lowers latency and allows
+/// tearing for `Game`. Changing it at runtime re-issues the hint on every
+/// wallpaper surface. Compositors without the protocol and other backends
+/// ignore it.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperContentType {
+    /// No particular content; the compositor's defaults apply.
+    #[default]
+    None,
+    /// Still images, e.g. a slideshow.
+    Photo,
+    /// Video playback.
+    Video,
+    /// Interactive, latency-sensitive content.
+    Game,
+}
@@ -0,0 +1,23 @@
+use bevy::{prelude::*, window::SystemCursorIcon};
+
+/// Cursor shown while the pointer is over the wallpaper.
+///
+/// Applied whenever a pointer enters a wallpaper surface and again when this
+/// resource changes. On Wayland, shapes need `wp_cursor_shape_manager_v1`;
+/// without it `Default` and `Shape` leave the compositor's cursor alone and
+/// only `Hidden` takes effect. On X11 the cursor is set on the wallpaper
+/// windows, with shapes mapped to the closest core cursor font glyph. The
+/// Windows backend ignores it.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperCursor {
+    /// The desktop's regular arrow cursor.
+    #[default]
+    Default,
+    /// No cursor, e.g. when the wallpaper draws its own.
+    Hidden,
+    /// A named cursor shape.
+    Shape(SystemCursorIcon),
+}
@@ -0,0 +1,172 @@
+#[cfg(any(feature = "wayland", feature = "x11"))]
+use std::time::Instant;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[cfg(feature = "wayland")]
+use crate::wayland::render::{WaylandGpuSurfaceState, present_wayland_surface};
+#[cfg(feature = "x11")]
+use crate::x11::render::{X11GpuSurfaceState, present_x11_surface};
+#[cfg(any(feature = "wayland", feature = "x11"))]
+use bevy::render::{Render, RenderSystems};
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+    render::RenderApp,
+};
+
+use crate::{WallpaperFrameStats, WallpaperSurfaces};
+
+/// Feeds wallpaper metrics into Bevy's [`Diagnostics`], e.g. for
+/// `LogDiagnosticsPlugin`.
+///
+/// Opt-in; add it next to [`crate::LiveWallpaperPlugin`]. Present timing and
+/// surface counts are only measured by the Wayland and X11 backends.
+pub struct WallpaperDiagnosticsPlugin;
+
+impl WallpaperDiagnosticsPlugin {
+    /// CPU time spent presenting to the wallpaper surfaces per frame, in ms.
+    pub const WALLPAPER_FRAME_TIME: DiagnosticPath =
+        DiagnosticPath::const_new("wallpaper/frame_time");
+    /// Time between frames the compositor actually presented, in ms (Wayland
+    /// `wp_presentation`).
+    pub const WALLPAPER_PRESENT_LAT: DiagnosticPath =
+        DiagnosticPath::const_new("wallpaper/present_latency");
+    /// Number of configured GPU surfaces.
+    pub const WALLPAPER_SURFACE_COUNT: DiagnosticPath =
+        DiagnosticPath::const_new("wallpaper/surface_count");
+    /// Number of outputs showing the wallpaper.
+    pub const WALLPAPER_OUTPUT_COUNT: DiagnosticPath =
+        DiagnosticPath::const_new("wallpaper/output_count");
+}
+
+impl Plugin for WallpaperDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let metrics = RenderMetrics::default();
+        app.insert_resource(metrics.clone())
+            .register_diagnostic(Diagnostic::new(Self::WALLPAPER_FRAME_TIME).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::WALLPAPER_PRESENT_LAT).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::WALLPAPER_SURFACE_COUNT))
+            .register_diagnostic(Diagnostic::new(Self::WALLPAPER_OUTPUT_COUNT))
+            .add_systems(PostUpdate, record_wallpaper_diagnostics);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.insert_resource(metrics);
+
+        #[cfg(feature = "wayland")]
+        render_app.add_systems(
+            Render,
+            (
+                begin_present_timing.before(present_wayland_surface),
+                end_wayland_present_timing.after(present_wayland_surface),
+            )
+                .in_set(RenderSystems::Cleanup)
+                .run_if(resource_exists::<WaylandGpuSurfaceState>),
+        );
+
+        #[cfg(feature = "x11")]
+        render_app.add_systems(
+            Render,
+            (
+                begin_present_timing.before(present_x11_surface),
+                end_x11_present_timing.after(present_x11_surface),
+            )
+                .in_set(RenderSystems::Cleanup)
+                .run_if(resource_exists::<X11GpuSurfaceState>),
+        );
+    }
+}
+
+/// Measurements taken in the render world, shared with the main world.
+#[derive(Resource, Clone, Default)]
+struct RenderMetrics(Arc<Mutex<RenderMeasurements>>);
+
+#[derive(Clone, Copy, Default)]
+struct RenderMeasurements {
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    present_started: Option<Instant>,
+    present_time: Option<Duration>,
+    surfaces: Option<usize>,
+}
+
+impl RenderMetrics {
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    fn update(&self, f: impl FnOnce(&mut RenderMeasurements)) {
+        f(&mut self.0.lock().unwrap_or_else(|err| err.into_inner()));
+    }
+
+    /// Returns the latest measurements, clearing the present time so each
+    /// one is only recorded once.
+    fn take(&self) -> RenderMeasurements {
+        let mut measurements = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        let taken = *measurements;
+        measurements.present_time = None;
+        taken
+    }
+}
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+fn begin_present_timing(metrics: Res<RenderMetrics>) {
+    metrics.update(|m| m.present_started = Some(Instant::now()));
+}
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+fn end_present_timing(metrics: &RenderMetrics, surfaces: usize) {
+    metrics.update(|m| {
+        m.present_time = m.present_started.take().map(|start| start.elapsed());
+        m.surfaces = Some(surfaces);
+    });
+}
+
+#[cfg(feature = "wayland")]
+fn end_wayland_present_timing(metrics: Res<RenderMetrics>, state: Res<WaylandGpuSurfaceState>) {
+    let surfaces = state
+        .surfaces
+        .values()
+        .filter(|s| s.config.is_some())
+        .count();
+    end_present_timing(&metrics, surfaces);
+}
+
+#[cfg(feature = "x11")]
+fn end_x11_present_timing(metrics: Res<RenderMetrics>, state: Res<X11GpuSurfaceState>) {
+    let surfaces = state
+        .surfaces
+        .values()
+        .filter(|s| s.config.is_some())
+        .count();
+    end_present_timing(&metrics, surfaces);
+}
+
+fn record_wallpaper_diagnostics(
+    mut diagnostics: Diagnostics,
+    metrics: Res<RenderMetrics>,
+    frame_stats: Option<Res<WallpaperFrameStats>>,
+    surfaces: Option<Res<WallpaperSurfaces>>,
+) {
+    let measurements = metrics.take();
+    if let Some(time) = measurements.present_time {
+        diagnostics.add_measurement(&WallpaperDiagnosticsPlugin::WALLPAPER_FRAME_TIME, || {
+            time.as_secs_f64() * 1000.0
+        });
+    }
+    if let Some(count) = measurements.surfaces {
+        diagnostics.add_measurement(&WallpaperDiagnosticsPlugin::WALLPAPER_SURFACE_COUNT, || {
+            count as f64
+        });
+    }
+    if let Some(interval) = frame_stats.and_then(|stats| stats.average_frame_time()) {
+        diagnostics.add_measurement(&WallpaperDiagnosticsPlugin::WALLPAPER_PRESENT_LAT, || {
+            interval.as_secs_f64() * 1000.0
+        });
+    }
+    if let Some(surfaces) = surfaces {
+        diagnostics.add_measurement(&WallpaperDiagnosticsPlugin::WALLPAPER_OUTPUT_COUNT, || {
+            surfaces.surfaces.len() as f64
+        });
+    }
+}
@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+
+use crate::{LinuxBackend, LiveWallpaperPlugin, WallpaperDisplayMode, WallpaperTargetMonitor};
+
+/// Overrides read from the environment, e.g. to run windowed in CI without
+/// code changes.
+///
+/// | Variable                 | Values                          |
+/// |--------------------------|---------------------------------|
+/// | `BEVY_WALLPAPER_MODE`    | `wallpaper`, `windowed`         |
+/// | `BEVY_WALLPAPER_MONITOR` | `primary`, `all`, an index, or an output name such as `DP-1` |
+/// | `BEVY_WALLPAPER_BACKEND` | `wayland`, `x11`, `auto`        |
+/// | `BEVY_WALLPAPER_FPS`     | presents per second, `0` for no cap |
+/// | `BEVY_WALLPAPER_FORCE`   | `1` to skip the single-instance check |
+///
+/// [`LiveWallpaperPlugin`] reads these when it is built; they only replace
+/// fields the app left at their default. `BEVY_WALLPAPER_FORCE` is the
+/// exception: it turns off [`LiveWallpaperPlugin::single_instance`] even when
+/// the app enabled it. Keywords are case-insensitive, output names are not.
+/// Unrecognized values are logged and ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WallpaperEnvConfig {
+    pub display_mode: Option<WallpaperDisplayMode>,
+    pub target_monitor: Option<WallpaperTargetMonitor>,
+    pub linux_backend: Option<LinuxBackend>,
+    /// `Some(None)` when set to `0`.
+    pub max_fps: Option<Option<u32>>,
+    /// Skip the single-instance check.
+    pub force: bool,
+}
+
+impl WallpaperEnvConfig {
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Reads the variables through `lookup` instead of the process environment.
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            display_mode: read_var(&lookup, "BEVY_WALLPAPER_MODE", |value| {
+                match value.to_ascii_lowercase().as_str() {
+                    "wallpaper" => Some(WallpaperDisplayMode::Wallpaper),
+                    "windowed" => Some(WallpaperDisplayMode::Windowed),
+                    _ => None,
+                }
+            }),
+            target_monitor: read_var(&lookup, "BEVY_WALLPAPER_MONITOR", |value| {
+                match value.to_ascii_lowercase().as_str() {
+                    "" => None,
+                    "primary" => Some(WallpaperTargetMonitor::Primary),
+                    "all" => Some(WallpaperTargetMonitor::All),
+                    _ => Some(value.parse().map_or_else(
+                        |_| WallpaperTargetMonitor::Name(value.to_owned()),
+                        WallpaperTargetMonitor::Index,
+                    )),
+                }
+            }),
+            linux_backend: read_var(&lookup, "BEVY_WALLPAPER_BACKEND", |value| {
+                match value.to_ascii_lowercase().as_str() {
+                    "wayland" => Some(LinuxBackend::Wayland),
+                    "x11" => Some(LinuxBackend::X11),
+                    "auto" => Some(LinuxBackend::Auto),
+                    _ => None,
+                }
+            }),
+            max_fps: read_var(&lookup, "BEVY_WALLPAPER_FPS", |value| {
+                value.parse().ok().map(|fps| (fps > 0).then_some(fps))
+            }),
+            force: read_var(&lookup, "BEVY_WALLPAPER_FORCE", |value| {
+                match value.to_ascii_lowercase().as_str() {
+                    "1" | "true" => Some(true),
+                    "0" | "false" => Some(false),
+                    _ => None,
+                }
+            })
+            .unwrap_or_default(),
+        }
+    }
+
+    /// Returns `plugin` with the overrides applied to its default fields, and
+    /// the single-instance check off when forced.
+    pub fn apply(&self, plugin: &LiveWallpaperPlugin) -> LiveWallpaperPlugin {
+        let defaults = LiveWallpaperPlugin::default();
+        LiveWallpaperPlugin {
+            display_mode: or_override(
+                plugin.display_mode,
+                defaults.display_mode,
+                self.display_mode,
+            ),
+            target_monitor: or_override(
+                plugin.target_monitor.clone(),
+                defaults.target_monitor,
+                self.target_monitor.clone(),
+            ),
+            linux_backend: or_override(
+                plugin.linux_backend,
+                defaults.linux_backend,
+                self.linux_backend,
+            ),
+            max_fps: or_override(plugin.max_fps, defaults.max_fps, self.max_fps),
+            single_instance: plugin.single_instance && !self.force,
+            ..plugin.clone()
+        }
+    }
+}
+
+fn or_override<T: PartialEq>(field: T, default: T, env: Option<T>) -> T {
+    match env {
+        Some(value) if field == default => value,
+        _ => field,
+    }
+}
+
+fn read_var<T>(
+    lookup: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+    let value = lookup(name)?;
+    let parsed = parse(value.trim());
+    if parsed.is_none() {
+        warn!("Ignoring unrecognized {name}={value:?}");
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn from_vars(vars: &[(&str, &str)]) -> WallpaperEnvConfig {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect();
+        WallpaperEnvConfig::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    fn monitor(value: &str) -> Option<WallpaperTargetMonitor> {
+        from_vars(&[("BEVY_WALLPAPER_MONITOR", value)]).target_monitor
+    }
+
+    #[test]
+    fn monitor_accepts_keywords_indices_and_names() {
+        assert_eq!(monitor("Primary"), Some(WallpaperTargetMonitor::Primary));
+        assert_eq!(monitor(" all "), Some(WallpaperTargetMonitor::All));
+        assert_eq!(monitor("2"), Some(WallpaperTargetMonitor::Index(2)));
+        assert_eq!(
+            monitor("DP-1"),
+            Some(WallpaperTargetMonitor::Name("DP-1".into()))
+        );
+        assert_eq!(
+            monitor("HDMI-A-1 "),
+            Some(WallpaperTargetMonitor::Name("HDMI-A-1".into()))
+        );
+        assert_eq!(monitor(""), None);
+    }
+
+    #[test]
+    fn overrides_only_default_fields() {
+        let env = from_vars(&[
+            ("BEVY_WALLPAPER_MODE", "WINDOWED"),
+            ("BEVY_WALLPAPER_MONITOR", "DP-1"),
+            ("BEVY_WALLPAPER_FPS", "30"),
+        ]);
+        let plugin = LiveWallpaperPlugin::default().target(WallpaperTargetMonitor::Index(1));
+
+        let applied = env.apply(&plugin);
+        assert_eq!(applied.display_mode, WallpaperDisplayMode::Windowed);
+        assert_eq!(applied.target_monitor, WallpaperTargetMonitor::Index(1));
+        assert_eq!(applied.max_fps, Some(30));
+    }
+
+    #[test]
+    fn force_overrides_explicit_single_instance() {
+        let plugin = LiveWallpaperPlugin::default().single_instance(true);
+        let forced = from_vars(&[("BEVY_WALLPAPER_FORCE", "1")]);
+        assert!(!forced.apply(&plugin).single_instance);
+
+        let unforced = from_vars(&[("BEVY_WALLPAPER_FORCE", "0")]);
+        assert!(unforced.apply(&plugin).single_instance);
+        let plugin = LiveWallpaperPlugin::default().single_instance(false);
+        assert!(!unforced.apply(&plugin).single_instance);
+    }
+}
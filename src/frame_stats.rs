@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+/// Number of frame intervals kept for [`WallpaperFrameStats::average_frame_time`].
+#[cfg(feature = "wayland")]
+const FRAME_TIME_WINDOW: usize = 60;
+
+/// Presentation timing reported by the compositor.
+///
+/// On Wayland this is fed by `wp_presentation` feedback when the compositor
+/// supports it. Other backends leave it at the default value.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperFrameStats {
+    /// Time of the last presented frame, on the compositor's presentation clock.
+    pub presented_at: Duration,
+    /// Refresh interval of the output the last frame was shown on (0 if unknown).
+    pub refresh_interval_ns: u32,
+    /// `wp_presentation_feedback` kind flags of the last presented frame.
+    pub flags: u32,
+    /// Number of frames the compositor discarded without showing them.
+    pub discarded: u64,
+    intervals: VecDeque<Duration>,
+}
+
+impl WallpaperFrameStats {
+    /// Records a presented frame. `interval` is the time since the previous
+    /// presented frame on the same output, if known.
+    #[cfg(feature = "wayland")]
+    pub(crate) fn record(
+        &mut self,
+        presented_at: Duration,
+        interval: Option<Duration>,
+        refresh_interval_ns: u32,
+        flags: u32,
+    ) {
+        self.presented_at = presented_at;
+        self.refresh_interval_ns = refresh_interval_ns;
+        self.flags = flags;
+
+        if let Some(interval) = interval {
+            if self.intervals.len() == FRAME_TIME_WINDOW {
+                self.intervals.pop_front();
+            }
+            self.intervals.push_back(interval);
+        }
+    }
+
+    /// Rolling average of the time between presented frames.
+    pub fn average_frame_time(&self) -> Option<Duration> {
+        if self.intervals.is_empty() {
+            return None;
+        }
+        let total: Duration = self.intervals.iter().sum();
+        Some(total / self.intervals.len() as u32)
+    }
+
+    /// Refresh interval of the output, if the compositor reported one.
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        (self.refresh_interval_ns > 0)
+            .then(|| Duration::from_nanos(u64::from(self.refresh_interval_ns)))
+    }
+}
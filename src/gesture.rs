@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+/// A touchpad gesture over the wallpaper, in the order it happened within the
+/// frame.
+///
+/// Fed by `zwp_pointer_gestures_v1` on Wayland. Other backends never send it.
+/// `output` is the output under the pointer, as in
+/// [`crate::PointerSample::output`].
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum WallpaperGestureEvent {
+    SwipeBegin {
+        output: Option<u32>,
+        fingers: u32,
+    },
+    SwipeUpdate {
+        output: Option<u32>,
+        /// Motion since the previous update, in logical pixels.
+        delta: Vec2,
+    },
+    SwipeEnd {
+        output: Option<u32>,
+        /// The compositor took the gesture over (e.g. for workspace switching).
+        cancelled: bool,
+    },
+    PinchBegin {
+        output: Option<u32>,
+        fingers: u32,
+    },
+    PinchUpdate {
+        output: Option<u32>,
+        /// Motion of the fingers' center since the previous update, in
+        /// logical pixels.
+        delta: Vec2,
+        /// Distance between the fingers relative to the start of the pinch.
+        scale: f32,
+        /// Clockwise rotation since the previous update, in degrees.
+        rotation: f32,
+    },
+    PinchEnd {
+        output: Option<u32>,
+        cancelled: bool,
+    },
+}
+
+impl WallpaperGestureEvent {
+    pub fn output(&self) -> Option<u32> {
+        match *self {
+            Self::SwipeBegin { output, .. }
+            | Self::SwipeUpdate { output, .. }
+            | Self::SwipeEnd { output, .. }
+            | Self::PinchBegin { output, .. }
+            | Self::PinchUpdate { output, .. }
+            | Self::PinchEnd { output, .. } => output,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::{
+    prelude::*,
+    render::{
+        renderer::RenderAdapterInfo,
+        settings::{PowerPreference, WgpuSettings},
+    },
+};
+
+/// GPU the wallpaper should render on.
+///
+/// Bevy picks the adapter while `RenderPlugin` is built, before
+/// [`crate::LiveWallpaperPlugin`] runs, so the preference has to be passed to
+/// `RenderPlugin` as well:
+///
+/// ```no_run
+/// # use bevy::{prelude::*, render::RenderPlugin};
+/// # use bevy_live_wallpaper::{LiveWallpaperPlugin, WallpaperGpuPreference};
+/// let gpu = WallpaperGpuPreference::LowPower;
+/// App::new()
+///     .add_plugins(DefaultPlugins.set(RenderPlugin {
+///         render_creation: gpu.wgpu_settings().into(),
+///         ..default()
+///     }))
+///     .add_plugins(LiveWallpaperPlugin {
+///         gpu_preference: gpu,
+///         ..default()
+///     });
+/// ```
+///
+/// The plugin logs the adapter actually selected at startup and warns when it
+/// doesn't match a requested [`WallpaperGpuPreference::Adapter`]. The
+/// `WGPU_ADAPTER_NAME` and `WGPU_POWER_PREF` environment variables still take
+/// precedence.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperGpuPreference {
+    /// Bevy's default (high performance unless `WGPU_POWER_PREF` says otherwise).
+    #[default]
+    Default,
+    /// Prefer a discrete GPU.
+    HighPerformance,
+    /// Prefer an integrated GPU.
+    LowPower,
+    /// Adapter whose name contains this string (case-insensitive).
+    Adapter(String),
+}
+
+impl WallpaperGpuPreference {
+    /// Applies the preference to `settings`.
+    pub fn apply(&self, settings: &mut WgpuSettings) {
+        match self {
+            Self::Default => {}
+            Self::HighPerformance => settings.power_preference = PowerPreference::HighPerformance,
+            Self::LowPower => settings.power_preference = PowerPreference::LowPower,
+            Self::Adapter(name) => settings.adapter_name = Some(name.to_lowercase()),
+        }
+    }
+
+    /// Default [`WgpuSettings`] with the preference applied.
+    pub fn wgpu_settings(&self) -> WgpuSettings {
+        let mut settings = WgpuSettings::default();
+        self.apply(&mut settings);
+        settings
+    }
+}
+
+/// Logs the adapter Bevy selected and whether it matches the preference.
+pub(crate) fn log_wallpaper_adapter(
+    preference: Res<WallpaperGpuPreference>,
+    adapter_info: Option<Res<RenderAdapterInfo>>,
+) {
+    let Some(adapter_info) = adapter_info else {
+        return;
+    };
+    info!(
+        "Wallpaper renders on adapter \"{}\" ({:?}, {:?})",
+        adapter_info.name, adapter_info.device_type, adapter_info.backend
+    );
+    if let WallpaperGpuPreference::Adapter(name) = &*preference
+        && !adapter_info
+            .name
+            .to_lowercase()
+            .contains(&name.to_lowercase())
+    {
+        warn!(
+            "Requested adapter \"{name}\" was not selected; pass \
+             `WallpaperGpuPreference::wgpu_settings()` to `RenderPlugin`"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{WallpaperPointerState, redraw::WallpaperDamage};
+
+/// Throttles presenting while the user is idle.
+///
+/// On Wayland, idleness comes from `ext_idle_notifier_v1` when the compositor
+/// supports it, which also accounts for keyboard and other input. Elsewhere
+/// the plugin falls back to pointer inactivity from [`WallpaperPointerState`].
+/// Like [`crate::WallpaperRedrawMode`], throttling only affects the Wayland
+/// and X11 backends.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WallpaperIdlePolicy {
+    /// Inactivity before the user counts as idle; `None` disables throttling.
+    pub timeout: Option<Duration>,
+    /// Frames presented per second while idle; `0.0` stops presenting.
+    pub idle_fps: f32,
+}
+
+impl Default for WallpaperIdlePolicy {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            idle_fps: 1.0,
+        }
+    }
+}
+
+/// Whether the user is currently idle according to [`WallpaperIdlePolicy`].
+///
+/// Read this to pause animations; presenting is throttled automatically.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperIdleState {
+    pub idle: bool,
+}
+
+/// Set by backends that report idleness themselves, which disables the
+/// pointer-inactivity fallback.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub(crate) struct CompositorIdleNotify(pub bool);
+
+/// Whether the latest pointer sample moved, clicked or holds a button.
+pub(crate) fn pointer_active(pointer_state: &WallpaperPointerState) -> bool {
+    pointer_state.last.as_ref().is_some_and(|sample| {
+        sample.delta != Vec2::ZERO || sample.last_button.is_some() || !sample.pressed.is_empty()
+    })
+}
+
+/// Marks the user idle once the pointer has been still for the policy timeout.
+pub(crate) fn pointer_idle_fallback_system(
+    policy: Res<WallpaperIdlePolicy>,
+    compositor: Res<CompositorIdleNotify>,
+    pointer_state: Res<WallpaperPointerState>,
+    time: Res<Time<Real>>,
+    mut last_activity: Local<Duration>,
+    mut state: ResMut<WallpaperIdleState>,
+) {
+    if compositor.0 {
+        return;
+    }
+    let now = time.elapsed();
+    let Some(timeout) = policy.timeout else {
+        *last_activity = now;
+        state.set_if_neq(WallpaperIdleState { idle: false });
+        return;
+    };
+
+    if pointer_active(&pointer_state) {
+        *last_activity = now;
+    }
+
+    let idle = now.saturating_sub(*last_activity) >= timeout;
+    state.set_if_neq(WallpaperIdleState { idle });
+}
+
+/// Drops damage between idle frames so at most `idle_fps` frames are presented.
+pub(crate) fn throttle_idle_presents_system(
+    policy: Res<WallpaperIdlePolicy>,
+    state: Res<WallpaperIdleState>,
+    time: Res<Time<Real>>,
+    mut last_present: Local<Option<Duration>>,
+    mut damage: ResMut<WallpaperDamage>,
+) {
+    if !state.idle || !damage.dirty {
+        if !state.idle {
+            *last_present = None;
+        }
+        return;
+    }
+    if policy.idle_fps <= 0.0 {
+        damage.dirty = false;
+        return;
+    }
+
+    let now = time.elapsed();
+    let interval = Duration::from_secs_f32(1.0 / policy.idle_fps);
+    if last_present.is_some_and(|last| now.saturating_sub(last) < interval) {
+        damage.dirty = false;
+    } else {
+        *last_present = Some(now);
+    }
+}
@@ -1,29 +1,683 @@
 use bevy::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+
+use crate::WallpaperSurfaceInfo;
 
 /// Pointer state snapshot, updated every Wayland dispatch tick.
-#[derive(Resource, Clone, Debug, Default)]
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
 pub struct WallpaperPointerState {
-    /// Last observed pointer sample across all outputs.
+    /// Last observed pointer sample across all outputs (most recently active seat).
     pub last: Option<PointerSample>,
+    /// Latest sample per seat, for backends that distinguish seats (Wayland).
+    pub by_seat: HashMap<u32, PointerSample>,
+}
+
+impl WallpaperPointerState {
+    /// Sample of the most recently active seat, for single-pointer code.
+    pub fn primary_sample(&self) -> Option<&PointerSample> {
+        self.last.as_ref()
+    }
+
+    /// Latest sample of every seat, or just [`Self::last`] on backends
+    /// without seats.
+    pub fn samples(&self) -> impl Iterator<Item = &PointerSample> {
+        let seatless = self
+            .by_seat
+            .is_empty()
+            .then_some(self.last.as_ref())
+            .flatten();
+        self.by_seat.values().chain(seatless)
+    }
+
+    /// Whether the latest pointer sample is over the wallpaper area.
+    pub fn pointer_in_surface(&self, surface: &WallpaperSurfaceInfo) -> bool {
+        self.pointer_surface_local(surface).is_some()
+    }
+
+    /// Latest pointer position relative to the top-left of the wallpaper area,
+    /// or `None` when the pointer is outside it.
+    ///
+    /// On Wayland the pointer must also still be on a wallpaper surface; X11
+    /// and Windows only test against the bounds of `surface`.
+    pub fn pointer_surface_local(&self, surface: &WallpaperSurfaceInfo) -> Option<Vec2> {
+        let sample = self.last.as_ref()?;
+        // Seat-aware backends (Wayland) clear the output once the pointer leaves.
+        if sample.seat.is_some() && sample.output.is_none() {
+            return None;
+        }
+        let local = sample.position - surface.offset_position;
+        (local.cmpge(Vec2::ZERO).all() && local.cmplt(surface.size).all()).then_some(local)
+    }
+
+    /// Latest pointer position scaled to the wallpaper area, with `(0, 0)` at
+    /// its top-left and `(1, 1)` at its bottom-right, e.g. for shader
+    /// uniforms. `None` whenever [`Self::pointer_surface_local`] is.
+    pub fn pointer_normalized(&self, surface: &WallpaperSurfaceInfo) -> Option<Vec2> {
+        self.pointer_surface_local(surface)
+            .map(|local| local / surface.size)
+    }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Reflect)]
 pub struct PointerSample {
+    /// Seat that produced this sample; `None` on backends without seats.
+    pub seat: Option<u32>,
     /// Backend-specific output/monitor identifier (per backend, best-effort).
-    /// `None` when the pointer is not over any known output.
+    /// `None` when the pointer is not over any known output; on Wayland this
+    /// includes after it left the wallpaper surfaces.
     pub output: Option<u32>,
     /// Global logical position (surface local + output offset).
     pub position: Vec2,
     /// Delta from the previous sample in global logical coordinates.
     pub delta: Vec2,
+    /// Set on the first sample after the pointer entered an output (or came
+    /// back from none). `delta` is zero then rather than the jump from the
+    /// previous position.
+    pub entered_output: bool,
     pub last_button: Option<PointerButton>,
     /// Buttons currently held down.
     pub pressed: HashSet<MouseButton>,
+    /// Wheel clicks since the previous frame; positive `y` scrolls down and
+    /// positive `x` scrolls right.
+    pub scroll_discrete: Vec2,
+    /// Pixel-precise scroll distance since the previous frame, e.g. from a
+    /// touchpad. Only Wayland and windowed mode report it.
+    pub scroll_smooth: Vec2,
+}
+
+impl PointerSample {
+    /// Flags the sample as entering an output when it differs from `prev`'s
+    /// and drops the jump between outputs from `delta`. For polling backends.
+    #[cfg(any(
+        feature = "x11",
+        target_os = "windows",
+        all(target_os = "macos", feature = "macos")
+    ))]
+    pub(crate) fn reset_on_output_change(&mut self, prev: Option<&PointerSample>) {
+        self.entered_output =
+            self.output.is_some() && prev.is_none_or(|prev| prev.output != self.output);
+        if self.entered_output {
+            self.delta = Vec2::ZERO;
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
 pub struct PointerButton {
     pub button: Option<MouseButton>,
     pub pressed: bool,
 }
+
+/// A single pointer action, in the order it happened within the frame.
+///
+/// [`WallpaperPointerState`] only keeps the latest sample; read these messages
+/// when intermediate motion or quick click/release pairs matter.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct WallpaperPointerEvent {
+    /// Backend-specific output/monitor identifier, as in [`PointerSample::output`].
+    pub output: Option<u32>,
+    /// Global logical position at the time of the event.
+    pub position: Vec2,
+    pub kind: WallpaperPointerEventKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum WallpaperPointerEventKind {
+    /// The pointer moved by `delta` (global logical coordinates).
+    Moved { delta: Vec2 },
+    /// A button changed state.
+    Button {
+        button: Option<MouseButton>,
+        pressed: bool,
+    },
+    /// The pointer entered an output.
+    Entered,
+    /// The pointer left an output.
+    Left,
+}
+
+/// Scrolling of one pointer during a frame, sent once per frame it scrolled.
+///
+/// The totals are also in [`PointerSample::scroll_discrete`] and
+/// [`PointerSample::scroll_smooth`]. The Windows wallpaper backend doesn't
+/// receive wheel input and never sends these.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct WallpaperScrollEvent {
+    /// Backend-specific output/monitor identifier, as in [`PointerSample::output`].
+    pub output: Option<u32>,
+    /// Global logical position of the pointer.
+    pub position: Vec2,
+    /// Wheel clicks, as in [`PointerSample::scroll_discrete`].
+    pub discrete: Vec2,
+    /// Pixel distance, as in [`PointerSample::scroll_smooth`].
+    pub smooth: Vec2,
+}
+
+impl WallpaperScrollEvent {
+    /// The scroll recorded in `sample`, if it scrolled.
+    pub(crate) fn from_sample(sample: &PointerSample) -> Option<Self> {
+        (sample.scroll_discrete != Vec2::ZERO || sample.scroll_smooth != Vec2::ZERO).then_some(
+            Self {
+                output: sample.output,
+                position: sample.position,
+                discrete: sample.scroll_discrete,
+                smooth: sample.scroll_smooth,
+            },
+        )
+    }
+}
+
+/// Builds the events implied by going from `prev` to `next`, for backends that
+/// only observe snapshots (polling).
+#[cfg(any(
+    feature = "x11",
+    target_os = "windows",
+    all(target_os = "macos", feature = "macos")
+))]
+pub(crate) fn pointer_events_between(
+    prev: Option<&PointerSample>,
+    next: &PointerSample,
+) -> Vec<WallpaperPointerEvent> {
+    let mut events = Vec::new();
+
+    let prev_output = prev.and_then(|p| p.output);
+    if prev.is_none() || prev_output != next.output {
+        if let Some(prev) = prev
+            && prev.output.is_some()
+        {
+            events.push(WallpaperPointerEvent {
+                output: prev.output,
+                position: prev.position,
+                kind: WallpaperPointerEventKind::Left,
+            });
+        }
+        if next.output.is_some() {
+            events.push(WallpaperPointerEvent {
+                output: next.output,
+                position: next.position,
+                kind: WallpaperPointerEventKind::Entered,
+            });
+        }
+    }
+
+    if next.delta != Vec2::ZERO {
+        events.push(WallpaperPointerEvent {
+            output: next.output,
+            position: next.position,
+            kind: WallpaperPointerEventKind::Moved { delta: next.delta },
+        });
+    }
+
+    if let Some(btn) = next.last_button {
+        events.push(WallpaperPointerEvent {
+            output: next.output,
+            position: next.position,
+            kind: WallpaperPointerEventKind::Button {
+                button: btn.button,
+                pressed: btn.pressed,
+            },
+        });
+    }
+
+    events
+}
+
+/// The button change between two polled sets of held buttons, for backends
+/// that only observe snapshots. When several changed at once, presses win over
+/// releases and left, right, middle win over the rest.
+#[cfg(any(
+    feature = "x11",
+    target_os = "windows",
+    all(target_os = "macos", feature = "macos")
+))]
+pub(crate) fn detect_last_button(
+    prev: Option<&HashSet<MouseButton>>,
+    current: &HashSet<MouseButton>,
+) -> Option<PointerButton> {
+    let empty = HashSet::new();
+    let prev = prev.unwrap_or(&empty);
+
+    let mut newly_pressed: Vec<MouseButton> = current.difference(prev).copied().collect();
+    if let Some(btn) = prioritize_button(&mut newly_pressed) {
+        return Some(PointerButton {
+            button: Some(btn),
+            pressed: true,
+        });
+    }
+
+    let mut released: Vec<MouseButton> = prev.difference(current).copied().collect();
+    if let Some(btn) = prioritize_button(&mut released) {
+        return Some(PointerButton {
+            button: Some(btn),
+            pressed: false,
+        });
+    }
+
+    None
+}
+
+#[cfg(any(
+    feature = "x11",
+    target_os = "windows",
+    all(target_os = "macos", feature = "macos")
+))]
+fn prioritize_button(buttons: &mut Vec<MouseButton>) -> Option<MouseButton> {
+    // Deterministic priority similar to common UX expectations.
+    let priority = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+    for p in priority {
+        if let Some(pos) = buttons.iter().position(|b| *b == p) {
+            return Some(buttons.swap_remove(pos));
+        }
+    }
+
+    buttons.pop()
+}
+
+/// Whether backends read the pointer at all; set from
+/// [`crate::LiveWallpaperPlugin::capture_input`] at build time.
+#[cfg(any(feature = "x11", target_os = "windows"))]
+#[derive(Resource, Clone, Copy, Debug)]
+pub(crate) struct CaptureInput(pub bool);
+
+/// Tuning for the gestures derived from [`WallpaperPointerEvent`]s.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WallpaperInputConfig {
+    /// Maximum time in seconds between two presses of a double click.
+    pub double_click_interval: f64,
+    /// Maximum pointer travel in logical pixels for double clicks and long presses.
+    pub double_click_threshold: f32,
+    /// Time in seconds a button must be held still to count as a long press.
+    pub long_press_duration: f64,
+    /// Pointer travel in logical pixels before a held button starts a drag.
+    pub drag_threshold: f32,
+}
+
+impl Default for WallpaperInputConfig {
+    fn default() -> Self {
+        Self {
+            double_click_interval: 0.4,
+            double_click_threshold: 4.0,
+            long_press_duration: 0.6,
+            drag_threshold: 4.0,
+        }
+    }
+}
+
+/// Sent when the same button is pressed twice in quick succession.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct WallpaperDoubleClick {
+    pub button: MouseButton,
+    /// Global logical position of the second press.
+    pub position: Vec2,
+}
+
+/// Sent once when a button has been held without moving for
+/// [`WallpaperInputConfig::long_press_duration`].
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct WallpaperLongPress {
+    pub button: MouseButton,
+    /// Global logical position where the press started.
+    pub position: Vec2,
+}
+
+/// Stage of a [`WallpaperDragEvent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum WallpaperDragPhase {
+    /// The pointer moved past [`WallpaperInputConfig::drag_threshold`].
+    Started,
+    /// The pointer moved during the drag.
+    Updated,
+    /// The button was released or the pointer left the output.
+    Ended,
+}
+
+/// A button held down while moving the pointer, built from every
+/// [`WallpaperPointerEvent`] so no intermediate motion is lost.
+///
+/// Only one drag is tracked at a time, started by the first button pressed.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct WallpaperDragEvent {
+    pub button: MouseButton,
+    /// Backend-specific output/monitor identifier, as in [`PointerSample::output`].
+    pub output: Option<u32>,
+    /// Global logical position where the button was pressed.
+    pub start: Vec2,
+    /// Global logical position of the pointer now.
+    pub current: Vec2,
+    /// Movement since the previous drag event (since `start` for `Started`).
+    pub delta: Vec2,
+    pub phase: WallpaperDragPhase,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct HeldButton {
+    started_at: f64,
+    position: Vec2,
+    long_press_sent: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct ClickTracker {
+    held: HashMap<MouseButton, HeldButton>,
+    last_press: HashMap<MouseButton, (f64, Vec2)>,
+}
+
+/// Derives double clicks and long presses from [`WallpaperPointerEvent`]s, so
+/// a press and release within one frame still count.
+///
+/// Intervals use real time, which keeps running while
+/// [`crate::WallpaperPlayState`] pauses the animation.
+pub(crate) fn detect_click_gestures_system(
+    mut pointer_events: MessageReader<WallpaperPointerEvent>,
+    config: Res<WallpaperInputConfig>,
+    time: Res<Time<Real>>,
+    mut tracker: Local<ClickTracker>,
+    mut double_clicks: MessageWriter<WallpaperDoubleClick>,
+    mut long_presses: MessageWriter<WallpaperLongPress>,
+) {
+    let now = time.elapsed_secs_f64();
+    for evt in pointer_events.read() {
+        match evt.kind {
+            WallpaperPointerEventKind::Button {
+                button: Some(button),
+                pressed: true,
+            } => {
+                tracker.held.insert(
+                    button,
+                    HeldButton {
+                        started_at: now,
+                        position: evt.position,
+                        long_press_sent: false,
+                    },
+                );
+
+                let is_double = tracker
+                    .last_press
+                    .get(&button)
+                    .is_some_and(|(at, position)| {
+                        now - at <= config.double_click_interval
+                            && position.distance(evt.position) <= config.double_click_threshold
+                    });
+                if is_double {
+                    tracker.last_press.remove(&button);
+                    double_clicks.write(WallpaperDoubleClick {
+                        button,
+                        position: evt.position,
+                    });
+                } else {
+                    tracker.last_press.insert(button, (now, evt.position));
+                }
+            }
+            WallpaperPointerEventKind::Button {
+                button: Some(button),
+                pressed: false,
+            } => {
+                tracker.held.remove(&button);
+            }
+            WallpaperPointerEventKind::Moved { .. } => {
+                for held in tracker.held.values_mut() {
+                    if held.position.distance(evt.position) > config.double_click_threshold {
+                        // Moved too far; this press is a drag, not a long press.
+                        held.long_press_sent = true;
+                    }
+                }
+            }
+            // The release may happen off the wallpaper, where we can't see it.
+            WallpaperPointerEventKind::Left => tracker.held.clear(),
+            _ => {}
+        }
+    }
+
+    for (&button, held) in tracker.held.iter_mut() {
+        if held.long_press_sent {
+            continue;
+        }
+        if now - held.started_at >= config.long_press_duration {
+            held.long_press_sent = true;
+            long_presses.write(WallpaperLongPress {
+                button,
+                position: held.position,
+            });
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ActiveDrag {
+    button: MouseButton,
+    output: Option<u32>,
+    start: Vec2,
+    last: Vec2,
+    /// Whether the threshold was crossed and `Started` sent.
+    started: bool,
+}
+
+impl ActiveDrag {
+    fn event(&self, current: Vec2, phase: WallpaperDragPhase) -> WallpaperDragEvent {
+        WallpaperDragEvent {
+            button: self.button,
+            output: self.output,
+            start: self.start,
+            current,
+            delta: current - self.last,
+            phase,
+        }
+    }
+}
+
+/// Turns the pointer event stream into drag events.
+pub(crate) fn detect_drag_gestures_system(
+    mut pointer_events: MessageReader<WallpaperPointerEvent>,
+    config: Res<WallpaperInputConfig>,
+    mut drag: Local<Option<ActiveDrag>>,
+    mut drag_events: MessageWriter<WallpaperDragEvent>,
+) {
+    for evt in pointer_events.read() {
+        match evt.kind {
+            WallpaperPointerEventKind::Button {
+                button: Some(button),
+                pressed: true,
+            } if drag.is_none() => {
+                *drag = Some(ActiveDrag {
+                    button,
+                    output: evt.output,
+                    start: evt.position,
+                    last: evt.position,
+                    started: false,
+                });
+            }
+            WallpaperPointerEventKind::Moved { .. } => {
+                let Some(active) = drag.as_mut() else {
+                    continue;
+                };
+                if !active.started {
+                    if active.start.distance(evt.position) < config.drag_threshold {
+                        continue;
+                    }
+                    active.started = true;
+                    drag_events.write(active.event(evt.position, WallpaperDragPhase::Started));
+                } else {
+                    drag_events.write(active.event(evt.position, WallpaperDragPhase::Updated));
+                }
+                active.output = evt.output;
+                active.last = evt.position;
+            }
+            WallpaperPointerEventKind::Button {
+                button: Some(button),
+                pressed: false,
+            } if drag.is_some_and(|active| active.button == button) => {
+                if let Some(active) = drag.take()
+                    && active.started
+                {
+                    drag_events.write(active.event(evt.position, WallpaperDragPhase::Ended));
+                }
+            }
+            WallpaperPointerEventKind::Left => {
+                if let Some(active) = drag.take()
+                    && active.started
+                {
+                    drag_events.write(active.event(active.last, WallpaperDragPhase::Ended));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::{TimePlugin, TimeUpdateStrategy};
+
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn click_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .init_resource::<WallpaperInputConfig>()
+            .add_message::<WallpaperPointerEvent>()
+            .add_message::<WallpaperDoubleClick>()
+            .add_message::<WallpaperLongPress>()
+            .init_resource::<Gestures>()
+            .add_systems(
+                PostUpdate,
+                (detect_click_gestures_system, count_gestures).chain(),
+            );
+        app.update();
+        app
+    }
+
+    #[derive(Resource, Default)]
+    struct Gestures {
+        double_clicks: usize,
+        long_presses: usize,
+    }
+
+    fn count_gestures(
+        mut double_clicks: MessageReader<WallpaperDoubleClick>,
+        mut long_presses: MessageReader<WallpaperLongPress>,
+        mut gestures: ResMut<Gestures>,
+    ) {
+        gestures.double_clicks += double_clicks.read().count();
+        gestures.long_presses += long_presses.read().count();
+    }
+
+    fn button(pressed: bool) -> WallpaperPointerEvent {
+        WallpaperPointerEvent {
+            output: Some(0),
+            position: Vec2::new(10.0, 10.0),
+            kind: WallpaperPointerEventKind::Button {
+                button: Some(MouseButton::Left),
+                pressed,
+            },
+        }
+    }
+
+    fn moved(x: f32) -> WallpaperPointerEvent {
+        WallpaperPointerEvent {
+            output: Some(0),
+            position: Vec2::new(x, 10.0),
+            kind: WallpaperPointerEventKind::Moved {
+                delta: Vec2::new(x - 10.0, 0.0),
+            },
+        }
+    }
+
+    fn frame(app: &mut App, events: impl IntoIterator<Item = WallpaperPointerEvent>) {
+        app.world_mut()
+            .write_message_batch(events)
+            .expect("pointer events registered");
+        app.update();
+    }
+
+    fn gestures(app: &App) -> &Gestures {
+        app.world().resource::<Gestures>()
+    }
+
+    #[test]
+    fn double_click_within_one_frame() {
+        let mut app = click_app();
+        frame(
+            &mut app,
+            [button(true), button(false), button(true), button(false)],
+        );
+
+        assert_eq!(gestures(&app).double_clicks, 1);
+    }
+
+    #[test]
+    fn double_click_across_frames() {
+        let mut app = click_app();
+        frame(&mut app, [button(true), button(false)]);
+        frame(&mut app, [button(true), button(false)]);
+
+        assert_eq!(gestures(&app).double_clicks, 1);
+    }
+
+    #[test]
+    fn slow_clicks_while_paused_are_not_double() {
+        let mut app = click_app();
+        app.world_mut().resource_mut::<Time<Virtual>>().pause();
+        frame(&mut app, [button(true), button(false)]);
+        for _ in 0..5 {
+            app.update();
+        }
+        frame(&mut app, [button(true), button(false)]);
+
+        assert_eq!(gestures(&app).double_clicks, 0);
+    }
+
+    #[test]
+    fn long_press_needs_a_still_pointer() {
+        let mut app = click_app();
+        frame(&mut app, [button(true)]);
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(gestures(&app).long_presses, 1);
+
+        let mut app = click_app();
+        frame(&mut app, [button(true), moved(30.0)]);
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(gestures(&app).long_presses, 0);
+    }
+
+    #[cfg(feature = "x11")]
+    #[test]
+    fn last_button_prefers_presses_and_primary_buttons() {
+        let held = |buttons: &[MouseButton]| buttons.iter().copied().collect::<HashSet<_>>();
+
+        let change = detect_last_button(
+            Some(&held(&[MouseButton::Middle])),
+            &held(&[MouseButton::Back, MouseButton::Left]),
+        );
+        assert_eq!(
+            change,
+            Some(PointerButton {
+                button: Some(MouseButton::Left),
+                pressed: true,
+            })
+        );
+
+        let change = detect_last_button(Some(&held(&[MouseButton::Right])), &held(&[]));
+        assert_eq!(
+            change,
+            Some(PointerButton {
+                button: Some(MouseButton::Right),
+                pressed: false,
+            })
+        );
+        assert_eq!(detect_last_button(None, &held(&[])), None);
+    }
+}
@@ -0,0 +1,280 @@
+//! Keeps two wallpaper apps from claiming the same monitor(s).
+//!
+//! Unix-like systems use a PID file in `$XDG_RUNTIME_DIR` (or the temp dir);
+//! Windows uses a named mutex. The lock is released when the app is dropped.
+//!
+//! Targets are compared as written, not resolved to outputs:
+//! [`WallpaperTargetMonitor::All`] conflicts with every other target, but
+//! `Primary`, `Index(0)` and `Name("DP-1")` don't conflict with each other
+//! even when they pick the same output.
+
+use bevy::prelude::*;
+
+use crate::WallpaperTargetMonitor;
+
+/// Held for the lifetime of the app.
+#[derive(Resource)]
+pub(crate) struct InstanceLock {
+    _guard: platform::Guard,
+}
+
+/// Takes the lock for `target`, or returns why another instance holds it.
+pub(crate) fn acquire(target: &WallpaperTargetMonitor) -> Result<InstanceLock, String> {
+    platform::acquire(&monitor_key(target)).map(|guard| InstanceLock { _guard: guard })
+}
+
+/// Key of [`WallpaperTargetMonitor::All`], which overlaps every other key.
+const ALL_KEY: &str = "all";
+
+fn monitor_key(target: &WallpaperTargetMonitor) -> String {
+    match target {
+        WallpaperTargetMonitor::Primary => "primary".into(),
+        WallpaperTargetMonitor::Index(index) => index.to_string(),
+        WallpaperTargetMonitor::All => ALL_KEY.into(),
+        // Keep it usable as a file and mutex name, and apart from the others.
+        WallpaperTargetMonitor::Name(name) => {
+            let name: String = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            format!("name_{name}")
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::{
+        fs::{self, OpenOptions},
+        io::{ErrorKind, Write},
+        path::{Path, PathBuf},
+    };
+
+    use super::ALL_KEY;
+
+    /// Path of the PID file, if one was written.
+    pub(crate) struct Guard(Option<PathBuf>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            if let Some(path) = &self.0 {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    const PREFIX: &str = "bevy_live_wallpaper_";
+    const SUFFIX: &str = ".lock";
+
+    pub(super) fn acquire(key: &str) -> Result<Guard, String> {
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        acquire_in(&dir, key)
+    }
+
+    fn acquire_in(dir: &Path, key: &str) -> Result<Guard, String> {
+        let path = dir.join(format!("{PREFIX}{key}{SUFFIX}"));
+        check_overlapping(dir, key)?;
+
+        // A second attempt follows removing a stale file.
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())
+                        .map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+                    return Ok(Guard(Some(path)));
+                }
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if let Some(pid) = live_owner(&path) {
+                        return Err(format!(
+                            "Another instance (PID {pid}) holds {}",
+                            path.display()
+                        ));
+                    }
+                    let _ = fs::remove_file(&path);
+                }
+                Err(err) => {
+                    // Not worth refusing to start over, e.g. a read-only dir.
+                    bevy::log::warn!("Skipping the instance lock {}: {err}", path.display());
+                    return Ok(Guard(None));
+                }
+            }
+        }
+        Err(format!(
+            "Failed to take the instance lock {}",
+            path.display()
+        ))
+    }
+
+    /// Fails if a live instance holds a lock that overlaps `key` without being
+    /// the same one: any lock for `all`, and `all` for any other key.
+    fn check_overlapping(dir: &Path, key: &str) -> Result<(), String> {
+        let overlapping: Vec<PathBuf> = if key == ALL_KEY {
+            let Ok(entries) = fs::read_dir(dir) else {
+                return Ok(());
+            };
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| {
+                    let name = entry.file_name();
+                    let name = name.to_string_lossy();
+                    name.strip_prefix(PREFIX)
+                        .and_then(|name| name.strip_suffix(SUFFIX))
+                        .is_some_and(|other| other != ALL_KEY)
+                })
+                .map(|entry| entry.path())
+                .collect()
+        } else {
+            vec![dir.join(format!("{PREFIX}{ALL_KEY}{SUFFIX}"))]
+        };
+        for path in overlapping {
+            if let Some(pid) = live_owner(&path) {
+                return Err(format!(
+                    "Another instance (PID {pid}) holds the overlapping {}",
+                    path.display()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// PID in the lock file at `path`, if it belongs to another live process.
+    fn live_owner(path: &Path) -> Option<u32> {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok())
+            .filter(|pid| *pid != std::process::id() && is_alive(*pid))
+    }
+
+    /// Probes `pid` with signal 0. A process owned by another user still
+    /// counts as alive.
+    fn is_alive(pid: u32) -> bool {
+        let Some(pid) = i32::try_from(pid)
+            .ok()
+            .and_then(rustix::process::Pid::from_raw)
+        else {
+            return false;
+        };
+        match rustix::process::test_kill_process(pid) {
+            Ok(()) => true,
+            Err(err) => err != rustix::io::Errno::SRCH,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn exited_process_is_not_alive() {
+            assert!(is_alive(std::process::id()));
+
+            let mut child = std::process::Command::new("true").spawn().unwrap();
+            let pid = child.id();
+            child.wait().unwrap();
+            assert!(!is_alive(pid));
+        }
+
+        #[test]
+        fn all_overlaps_every_other_key() {
+            let dir = std::env::temp_dir()
+                .join(format!("bevy_live_wallpaper_test_{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            // PID 1 is always alive, standing in for another instance.
+            let held_by_other = |key: &str| {
+                fs::write(dir.join(format!("{PREFIX}{key}{SUFFIX}")), "1").unwrap();
+            };
+
+            held_by_other("primary");
+            assert!(acquire_in(&dir, "primary").is_err());
+            assert!(acquire_in(&dir, ALL_KEY).is_err());
+            assert!(acquire_in(&dir, "name_DP_1").is_ok());
+
+            fs::remove_file(dir.join(format!("{PREFIX}primary{SUFFIX}"))).unwrap();
+            held_by_other(ALL_KEY);
+            assert!(acquire_in(&dir, "1").is_err());
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::{CloseHandle, ERROR_ALREADY_EXISTS, GetLastError, HANDLE};
+    use windows::Win32::System::Threading::{
+        CreateMutexW, OpenMutexW, SYNCHRONIZATION_SYNCHRONIZE,
+    };
+    use windows::core::HSTRING;
+
+    use super::ALL_KEY;
+
+    /// Held by every instance whose target isn't `All`, so that one can tell
+    /// whether any is running.
+    const SINGLE_KEY: &str = "single";
+
+    /// Raw mutex handles; kept as integers so the guard is `Send`.
+    pub(crate) struct Guard(Vec<isize>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            for handle in &self.0 {
+                unsafe {
+                    let _ = CloseHandle(HANDLE(*handle as _));
+                }
+            }
+        }
+    }
+
+    fn mutex_name(key: &str) -> String {
+        format!("Global\\BevyLiveWallpaper_{key}")
+    }
+
+    /// Whether another instance has a mutex for `key` open.
+    fn exists(key: &str) -> bool {
+        let name = HSTRING::from(mutex_name(key).as_str());
+        match unsafe { OpenMutexW(SYNCHRONIZATION_SYNCHRONIZE, false, &name) } {
+            Ok(handle) => {
+                unsafe {
+                    let _ = CloseHandle(handle);
+                }
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Opens (or creates) the mutex for `key`; `Ok(false)` if it already existed.
+    fn create(guard: &mut Guard, key: &str, owned: bool) -> Result<bool, String> {
+        let name = mutex_name(key);
+        let handle = unsafe { CreateMutexW(None, owned, &HSTRING::from(name.as_str())) }
+            .map_err(|err| format!("Failed to create the mutex {name}: {err}"))?;
+        guard.0.push(handle.0 as isize);
+        Ok(unsafe { GetLastError() } != ERROR_ALREADY_EXISTS)
+    }
+
+    pub(super) fn acquire(key: &str) -> Result<Guard, String> {
+        let mut guard = Guard(Vec::new());
+        if !create(&mut guard, key, true)? {
+            return Err(format!(
+                "Another instance holds the mutex {}",
+                mutex_name(key)
+            ));
+        }
+        // `All` overlaps every other target.
+        let overlapping = if key == ALL_KEY {
+            SINGLE_KEY
+        } else {
+            create(&mut guard, SINGLE_KEY, false)?;
+            ALL_KEY
+        };
+        if exists(overlapping) {
+            return Err(format!(
+                "Another instance holds the overlapping mutex {}",
+                mutex_name(overlapping)
+            ));
+        }
+        Ok(guard)
+    }
+}
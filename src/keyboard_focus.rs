@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+/// Whether a wallpaper surface has keyboard focus.
+///
+/// Fed by `wl_keyboard` enter/leave on Wayland, whatever
+/// [`crate::WaylandKeyboardInteractivity`] is set to (with `None` the
+/// compositor never focuses the wallpaper). Other backends leave it at the
+/// default value.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperKeyboardFocus {
+    pub has_focus: bool,
+    /// Output whose surface has focus, as in [`crate::PointerSample::output`].
+    pub focused_output: Option<u32>,
+}
+
+/// Sent when a wallpaper surface gains or loses keyboard focus.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub struct WallpaperFocusEvent {
+    pub gained: bool,
+    /// Output whose surface gained or lost focus, if still known.
+    pub output: Option<u32>,
+}
@@ -1,23 +1,54 @@
 //! Bevy Live Wallpaper
 //!
 //! A Bevy plugin that renders your scene as the desktop wallpaper on Wayland,
-//! X11, and Windows. Pick the matching backend feature (`wayland` or `x11`) on
-//! Linux/BSD; Windows works with defaults.
+//! X11, Windows, and macOS. Pick the matching backend feature (`wayland` or
+//! `x11`) on Linux/BSD and `macos` on macOS; Windows works with defaults.
 
 #[cfg(all(
     not(feature = "wayland"),
     not(feature = "x11"),
-    not(target_os = "windows")
+    not(target_os = "windows"),
+    not(all(target_os = "macos", feature = "macos"))
 ))]
 compile_error!(
-    "On non-Windows platforms, at least one of the 'wayland' or 'x11' features must be enabled."
+    "Enable the 'wayland' or 'x11' feature (or 'macos' on macOS); Windows needs no feature."
 );
 
+pub mod backend_status;
 pub mod camera;
+pub mod clipboard;
+#[cfg(feature = "serde")]
+pub mod config;
+pub mod content_type;
+pub mod cursor;
+pub mod diagnostics;
+pub mod env_config;
+pub mod frame_stats;
+pub mod gesture;
+pub mod gpu_preference;
+pub mod idle;
 pub mod input;
+mod instance_lock;
+pub mod keyboard_focus;
+pub mod lock_screen;
+pub mod memory_budget;
+pub mod monitor_events;
+pub mod play_state;
 pub mod plugin;
+pub mod pointer_constraint;
+pub mod present_mode;
+pub mod redraw;
+pub mod screen_lock;
+pub mod stylus;
+pub mod surface_format;
 pub mod surface_info;
+pub mod surface_recovery;
+pub mod surfaces;
+pub mod system_set;
 pub mod target_monitor;
+pub mod tearing;
+pub mod visibility;
+pub mod wayland_diagnostics;
 mod windowed_backend;
 
 #[cfg(feature = "wayland")]
@@ -29,12 +60,59 @@ mod x11;
 #[cfg(target_os = "windows")]
 mod windows_backend;
 
-pub use plugin::{LinuxBackend, LiveWallpaperPlugin, WallpaperDisplayMode};
+#[cfg(all(target_os = "macos", feature = "macos"))]
+mod macos_backend;
 
+pub use plugin::{
+    LinuxBackend, LiveWallpaperPlugin, WallpaperAlphaMode, WallpaperDisplayMode,
+    WallpaperShutdownBehavior, WallpaperX11Config, WaylandExclusiveZone, WaylandFallback,
+    WaylandKeyboardInteractivity, WaylandLayer, WaylandMargins, WaylandOutputOrder,
+    WaylandPrimaryOutput,
+};
+
+pub use backend_status::{
+    WallpaperBackend, WallpaperBackendClosed, WallpaperBackendStatus, WallpaperX11Info,
+};
 pub use camera::LiveWallpaperCamera;
-pub use input::{PointerButton, PointerSample, WallpaperPointerState};
-pub use surface_info::WallpaperSurfaceInfo;
+pub use clipboard::WallpaperClipboard;
+#[cfg(feature = "serde")]
+pub use config::WallpaperConfig;
+pub use content_type::WallpaperContentType;
+pub use cursor::WallpaperCursor;
+pub use diagnostics::WallpaperDiagnosticsPlugin;
+pub use env_config::WallpaperEnvConfig;
+pub use frame_stats::WallpaperFrameStats;
+pub use gesture::WallpaperGestureEvent;
+pub use gpu_preference::WallpaperGpuPreference;
+pub use idle::{WallpaperIdlePolicy, WallpaperIdleState};
+pub use input::{
+    PointerButton, PointerSample, WallpaperDoubleClick, WallpaperDragEvent, WallpaperDragPhase,
+    WallpaperInputConfig, WallpaperLongPress, WallpaperPointerEvent, WallpaperPointerEventKind,
+    WallpaperPointerState, WallpaperScrollEvent,
+};
+pub use keyboard_focus::{WallpaperFocusEvent, WallpaperKeyboardFocus};
+pub use lock_screen::{WallpaperLockEvent, WallpaperLockScreen};
+pub use memory_budget::{
+    MemoryPressure, WallpaperMemoryBudget, WallpaperMemoryPolicy, WallpaperMemoryPressure,
+};
+pub use monitor_events::{WallpaperMonitorConnected, WallpaperMonitorDisconnected};
+pub use play_state::{PlayState, WallpaperAutoPlay, WallpaperPlayState};
+pub use pointer_constraint::{PointerConstraintKind, WallpaperPointerConstraint};
+pub use present_mode::{WallpaperFifoPacing, WallpaperFrameLatency, WallpaperPresentMode};
+pub use redraw::{WallpaperForceRedraw, WallpaperFrameRateLimit, WallpaperRedrawMode};
+pub use screen_lock::{WallpaperPaused, WallpaperScreenLockPlugin};
+pub use stylus::{StylusTool, WallpaperStylusEvent, WallpaperStylusPhase, WallpaperStylusState};
+pub use surface_format::{WallpaperColorSpace, WallpaperPreferredFormats, WallpaperSurfaceFormat};
+pub use surface_info::{WallpaperSurfaceChanged, WallpaperSurfaceInfo};
+pub use surface_recovery::{SurfaceRecoveryInfo, WallpaperSurfaceRecovery};
+pub use surfaces::{WallpaperSurface, WallpaperSurfaceHandles, WallpaperSurfaces};
+pub use system_set::WallpaperSystemSet;
 pub use target_monitor::WallpaperTargetMonitor;
+pub use tearing::{WallpaperAllowTearing, WallpaperTearingState};
+pub use visibility::{OutputVisibility, WallpaperVisibility};
+pub use wayland_diagnostics::{
+    WallpaperWaylandDiagnostics, WaylandGlobalInfo, WaylandOutputDiagnostics,
+};
 
 #[cfg(feature = "wayland")]
 pub use wayland::surface::WaylandSurfaceHandles;
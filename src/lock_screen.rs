@@ -0,0 +1,28 @@
+use bevy::prelude::*;
+
+/// (Wayland only) Shows the wallpaper scene as the lock screen.
+///
+/// Setting `locked` asks the compositor to lock the session through
+/// `ext_session_lock_v1`; while locked, every output shows the scene on a
+/// lock surface instead of the layer surface. The app is then the screen
+/// locker: the session stays locked until `locked` is cleared, so authenticate
+/// the user before doing so. If the compositor refuses the lock (or ends it
+/// itself), the plugin clears `locked` and sends
+/// [`WallpaperLockEvent::Denied`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperLockScreen {
+    pub locked: bool,
+}
+
+/// Sent when the session lock requested through [`WallpaperLockScreen`]
+/// changes state, e.g. to switch the scene to a dimmed variant.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum WallpaperLockEvent {
+    /// The compositor locked the session and shows the lock surfaces.
+    Locked,
+    /// The session was unlocked and the layer surfaces are used again.
+    Unlocked,
+    /// The compositor refused or ended the lock.
+    Denied,
+}
@@ -0,0 +1,241 @@
+//! macOS backend: Bevy's window is kept and moved down to the desktop window
+//! level, below the Finder icons, so wgpu keeps presenting to its
+//! `CAMetalLayer` as usual.
+
+use crate::{
+    PointerSample, WallpaperBackend, WallpaperDisplayMode, WallpaperPointerEvent,
+    WallpaperPointerState, WallpaperSurfaceInfo, WallpaperTargetMonitor,
+    input::{detect_last_button, pointer_events_between},
+    plugin::wallpaper_mode_active,
+};
+use bevy::prelude::*;
+use bevy::window::{RawHandleWrapper, WindowPosition};
+use objc2::{MainThreadMarker, rc::Retained};
+use objc2_app_kit::{
+    NSEvent, NSNormalWindowLevel, NSScreen, NSView, NSWindow, NSWindowCollectionBehavior,
+    NSWindowLevel,
+};
+use objc2_foundation::{NSPoint, NSRect, NSSize};
+use raw_window_handle::RawWindowHandle;
+use std::collections::HashSet;
+
+/// `kCGDesktopWindowLevel` (`kCGMinimumWindowLevel + 20`) from `CGWindowLevel.h`.
+const DESKTOP_WINDOW_LEVEL: NSWindowLevel = i32::MIN as NSWindowLevel + 20;
+
+#[derive(Default)]
+pub(crate) struct WallpaperMacosPlugin;
+
+impl Plugin for WallpaperMacosPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WallpaperBackend::MacOs)
+            .add_systems(
+                Startup,
+                attach_wallpaper_windows_system.run_if(wallpaper_mode_active),
+            )
+            .add_systems(
+                Update,
+                (
+                    switch_display_mode_system.run_if(resource_changed::<WallpaperDisplayMode>),
+                    update_window_frame_system.run_if(
+                        resource_changed::<WallpaperTargetMonitor>
+                            .or_else(resource_changed::<WallpaperDisplayMode>),
+                    ),
+                    update_pointer_and_surface_info_system,
+                )
+                    .chain()
+                    .run_if(
+                        wallpaper_mode_active.or_else(resource_changed::<WallpaperDisplayMode>),
+                    ),
+            );
+    }
+}
+
+fn attach_wallpaper_windows_system(handle_wrappers: Query<&RawHandleWrapper, With<Window>>) {
+    for handle_wrapper in &handle_wrappers {
+        if let Some(window) = ns_window(handle_wrapper) {
+            attach_to_desktop(&window);
+        }
+    }
+}
+
+/// Moves the window between the desktop level and a normal window when
+/// [`WallpaperDisplayMode`] changes at runtime.
+fn switch_display_mode_system(
+    mode: Res<WallpaperDisplayMode>,
+    mut windows: Query<(&RawHandleWrapper, &mut Window)>,
+) {
+    if mode.is_added() {
+        return;
+    }
+
+    for (handle_wrapper, mut window) in &mut windows {
+        let Some(ns_window) = ns_window(handle_wrapper) else {
+            continue;
+        };
+        match *mode {
+            WallpaperDisplayMode::Wallpaper => attach_to_desktop(&ns_window),
+            WallpaperDisplayMode::Windowed => {
+                detach_from_desktop(&ns_window);
+                window.position = WindowPosition::Centered(MonitorSelection::Primary);
+                window.resolution.set(1280.0, 720.0);
+            }
+        }
+    }
+}
+
+fn ns_window(handle_wrapper: &RawHandleWrapper) -> Option<Retained<NSWindow>> {
+    match handle_wrapper.get_window_handle() {
+        RawWindowHandle::AppKit(handle) => {
+            // SAFETY: the handle stays valid while the window entity exists.
+            let view: &NSView = unsafe { handle.ns_view.cast().as_ref() };
+            view.window()
+        }
+        _ => None,
+    }
+}
+
+fn attach_to_desktop(window: &NSWindow) {
+    window.setLevel(DESKTOP_WINDOW_LEVEL);
+    window.setCollectionBehavior(
+        NSWindowCollectionBehavior::CanJoinAllSpaces
+            | NSWindowCollectionBehavior::Stationary
+            | NSWindowCollectionBehavior::IgnoresCycle,
+    );
+    window.setIgnoresMouseEvents(true);
+    window.setHasShadow(false);
+    window.orderBack(None);
+}
+
+/// Undoes [`attach_to_desktop`].
+fn detach_from_desktop(window: &NSWindow) {
+    window.setLevel(NSNormalWindowLevel);
+    window.setCollectionBehavior(NSWindowCollectionBehavior::Default);
+    window.setIgnoresMouseEvents(false);
+    window.setHasShadow(true);
+}
+
+/// Screen frames in points, with the primary (menu bar) screen first.
+///
+/// Cocoa's global coordinates grow upwards from the bottom-left corner of the
+/// primary screen.
+fn screen_frames() -> Vec<NSRect> {
+    let Some(mtm) = MainThreadMarker::new() else {
+        return Vec::new();
+    };
+    NSScreen::screens(mtm)
+        .iter()
+        .map(|screen| screen.frame())
+        .collect()
+}
+
+fn union_frame(frames: &[NSRect]) -> Option<NSRect> {
+    frames.iter().copied().reduce(|a, b| {
+        let min_x = a.origin.x.min(b.origin.x);
+        let min_y = a.origin.y.min(b.origin.y);
+        let max_x = (a.origin.x + a.size.width).max(b.origin.x + b.size.width);
+        let max_y = (a.origin.y + a.size.height).max(b.origin.y + b.size.height);
+        NSRect::new(
+            NSPoint::new(min_x, min_y),
+            NSSize::new(max_x - min_x, max_y - min_y),
+        )
+    })
+}
+
+fn target_frame(target_monitor: &WallpaperTargetMonitor, frames: &[NSRect]) -> Option<NSRect> {
+    match target_monitor {
+        WallpaperTargetMonitor::Primary => frames.first().copied(),
+        WallpaperTargetMonitor::Index(n) => frames.get(*n).copied(),
+        WallpaperTargetMonitor::All => union_frame(frames),
+        // Screens aren't matched by name here.
+        WallpaperTargetMonitor::Name(_) => None,
+    }
+}
+
+fn update_window_frame_system(
+    target_monitor: Res<WallpaperTargetMonitor>,
+    handle_wrappers: Query<&RawHandleWrapper, With<Window>>,
+) {
+    let frames = screen_frames();
+    let Some(frame) = target_frame(&target_monitor, &frames) else {
+        return;
+    };
+    for handle_wrapper in &handle_wrappers {
+        if let Some(window) = ns_window(handle_wrapper) {
+            window.setFrame_display(frame, true);
+        }
+    }
+}
+
+fn update_pointer_and_surface_info_system(
+    target_monitor: Res<WallpaperTargetMonitor>,
+    mut pointer_state: ResMut<WallpaperPointerState>,
+    mut pointer_events: MessageWriter<WallpaperPointerEvent>,
+    mut surface_info: ResMut<WallpaperSurfaceInfo>,
+) {
+    let frames = screen_frames();
+    let Some(bounds) = union_frame(&frames) else {
+        return;
+    };
+    let Some(target) = target_frame(&target_monitor, &frames) else {
+        return;
+    };
+    let top = bounds.origin.y + bounds.size.height;
+
+    surface_info.set(
+        (target.origin.x - bounds.origin.x).floor() as i32,
+        (top - (target.origin.y + target.size.height)).floor() as i32,
+        target.size.width.ceil().max(1.0) as u32,
+        target.size.height.ceil().max(1.0) as u32,
+    );
+
+    let cursor = NSEvent::mouseLocation();
+    let logical_position = Vec2::new((cursor.x - bounds.origin.x) as f32, (top - cursor.y) as f32);
+
+    let pressed = pressed_buttons();
+    let last_button = detect_last_button(pointer_state.last.as_ref().map(|s| &s.pressed), &pressed);
+    let prev_position = pointer_state
+        .last
+        .as_ref()
+        .map(|s| s.position)
+        .unwrap_or(logical_position);
+
+    let mut sample = PointerSample {
+        seat: None,
+        output: output_for_position(&frames, cursor),
+        position: logical_position,
+        delta: logical_position - prev_position,
+        entered_output: false,
+        last_button,
+        pressed,
+        scroll_discrete: Vec2::ZERO,
+        scroll_smooth: Vec2::ZERO,
+    };
+    sample.reset_on_output_change(pointer_state.last.as_ref());
+    pointer_events.write_batch(pointer_events_between(pointer_state.last.as_ref(), &sample));
+    pointer_state.last = Some(sample);
+}
+
+fn output_for_position(frames: &[NSRect], point: NSPoint) -> Option<u32> {
+    frames
+        .iter()
+        .position(|frame| {
+            point.x >= frame.origin.x
+                && point.x < frame.origin.x + frame.size.width
+                && point.y >= frame.origin.y
+                && point.y < frame.origin.y + frame.size.height
+        })
+        .map(|idx| idx as u32)
+}
+
+fn pressed_buttons() -> HashSet<MouseButton> {
+    let mask = NSEvent::pressedMouseButtons();
+    [
+        (1 << 0, MouseButton::Left),
+        (1 << 1, MouseButton::Right),
+        (1 << 2, MouseButton::Middle),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| mask & bit != 0)
+    .map(|(_, button)| button)
+    .collect()
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, render::renderer::RenderDevice};
+
+/// How often the allocator report is refreshed; building it walks every
+/// allocation, so it isn't done every frame.
+const MEMORY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// GPU memory the wallpaper may use and how to react when it runs short.
+///
+/// wgpu doesn't expose the driver's memory budget, so the budget is set here
+/// and compared against what wgpu's allocator reports for the device.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WallpaperMemoryPolicy {
+    /// Bytes the wallpaper may allocate; `None` disables pressure tracking.
+    pub budget_bytes: Option<u64>,
+    /// Fraction of the budget that has to stay free before pressure counts
+    /// as [`MemoryPressure::High`].
+    pub headroom: f32,
+    /// Halve [`crate::WallpaperSurfaceInfo::scale_factor`] while pressure is
+    /// [`MemoryPressure::Critical`], so apps sizing their render targets from
+    /// it render at a lower resolution. Honored on Wayland, X11 and in
+    /// windowed mode; the Windows and macOS backends leave `scale_factor`
+    /// unset, so it has no effect there.
+    pub auto_reduce_resolution: bool,
+}
+
+impl Default for WallpaperMemoryPolicy {
+    fn default() -> Self {
+        Self {
+            budget_bytes: None,
+            headroom: 0.2,
+            auto_reduce_resolution: false,
+        }
+    }
+}
+
+/// How close the allocations are to [`WallpaperMemoryPolicy::budget_bytes`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum MemoryPressure {
+    #[default]
+    None,
+    /// Less than the policy's headroom is left.
+    High,
+    /// The budget is used up.
+    Critical,
+}
+
+/// GPU memory of the render device, refreshed about once per second.
+///
+/// Allocation counts come from wgpu's allocator report, available on Vulkan,
+/// DX12 and Metal; on other backends they stay `None` and pressure stays
+/// [`MemoryPressure::None`].
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperMemoryBudget {
+    /// Largest buffer the device can create.
+    pub max_buffer_size: u64,
+    /// Bytes used by live allocations.
+    pub allocated_bytes: Option<u64>,
+    /// Bytes reserved in memory blocks, including their unused space.
+    pub reserved_bytes: Option<u64>,
+    pub pressure: MemoryPressure,
+    /// Factor applied to the reported scale factor; `0.5` while resolution
+    /// is reduced, `1.0` otherwise.
+    pub resolution_scale: f32,
+}
+
+impl Default for WallpaperMemoryBudget {
+    fn default() -> Self {
+        Self {
+            max_buffer_size: 0,
+            allocated_bytes: None,
+            reserved_bytes: None,
+            pressure: MemoryPressure::None,
+            resolution_scale: 1.0,
+        }
+    }
+}
+
+/// Sent when [`WallpaperMemoryBudget::pressure`] changes, including when it
+/// drops back to [`MemoryPressure::None`].
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub struct WallpaperMemoryPressure {
+    pub pressure: MemoryPressure,
+    pub allocated_bytes: u64,
+    pub budget_bytes: u64,
+}
+
+fn pressure_level(policy: &WallpaperMemoryPolicy, allocated: Option<u64>) -> MemoryPressure {
+    let (Some(budget), Some(allocated)) = (policy.budget_bytes, allocated) else {
+        return MemoryPressure::None;
+    };
+    let free = budget.saturating_sub(allocated);
+    if allocated >= budget {
+        MemoryPressure::Critical
+    } else if (free as f64) < budget as f64 * f64::from(policy.headroom) {
+        MemoryPressure::High
+    } else {
+        MemoryPressure::None
+    }
+}
+
+/// Refreshes [`WallpaperMemoryBudget`] and reports pressure changes.
+pub(crate) fn update_memory_budget_system(
+    policy: Res<WallpaperMemoryPolicy>,
+    render_device: Option<Res<RenderDevice>>,
+    time: Res<Time<Real>>,
+    mut last_poll: Local<Option<Duration>>,
+    mut budget: ResMut<WallpaperMemoryBudget>,
+    mut pressure_events: MessageWriter<WallpaperMemoryPressure>,
+) {
+    let Some(render_device) = render_device else {
+        return;
+    };
+    let now = time.elapsed();
+    if last_poll.is_some_and(|last| now.saturating_sub(last) < MEMORY_POLL_INTERVAL)
+        && !policy.is_changed()
+    {
+        return;
+    }
+    *last_poll = Some(now);
+
+    let report = render_device.wgpu_device().generate_allocator_report();
+    let allocated_bytes = report.as_ref().map(|report| report.total_allocated_bytes);
+    let reserved_bytes = report.as_ref().map(|report| report.total_reserved_bytes);
+    let pressure = pressure_level(&policy, allocated_bytes);
+    let resolution_scale = if policy.auto_reduce_resolution && pressure == MemoryPressure::Critical
+    {
+        0.5
+    } else {
+        1.0
+    };
+
+    if pressure != budget.pressure {
+        debug!("GPU memory pressure {:?} -> {pressure:?}", budget.pressure);
+        pressure_events.write(WallpaperMemoryPressure {
+            pressure,
+            allocated_bytes: allocated_bytes.unwrap_or_default(),
+            budget_bytes: policy.budget_bytes.unwrap_or_default(),
+        });
+    }
+    budget.set_if_neq(WallpaperMemoryBudget {
+        max_buffer_size: render_device.limits().max_buffer_size,
+        allocated_bytes,
+        reserved_bytes,
+        pressure,
+        resolution_scale,
+    });
+}
@@ -0,0 +1,60 @@
+#[cfg(any(feature = "wayland", feature = "x11", target_os = "windows"))]
+use std::collections::HashSet;
+
+#[cfg(any(feature = "wayland", feature = "x11", target_os = "windows"))]
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Sent when a monitor appears, including once per monitor found at startup.
+///
+/// Wayland reports a monitor once its size and position are known; X11 after
+/// a RandR refresh; Windows when Bevy spawns its [`bevy::window::Monitor`].
+#[derive(Message, Clone, Debug, PartialEq, Eq, Reflect)]
+pub struct WallpaperMonitorConnected {
+    /// The `wl_output` global name on Wayland (as in
+    /// [`crate::PointerSample::output`]), the RandR output on X11 and the
+    /// monitor entity's index on Windows.
+    pub output: u32,
+    /// Connector name such as `DP-1`, when the backend knows it.
+    pub name: Option<String>,
+    /// Size in logical pixels on Wayland, physical pixels elsewhere.
+    pub size: UVec2,
+    /// Top-left corner in the desktop layout.
+    pub position: IVec2,
+}
+
+/// Sent when a monitor announced by [`WallpaperMonitorConnected`] goes away.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub struct WallpaperMonitorDisconnected {
+    pub output: u32,
+}
+
+/// Writers for both monitor messages and the monitors announced so far.
+#[cfg(any(feature = "wayland", feature = "x11", target_os = "windows"))]
+#[derive(SystemParam)]
+pub(crate) struct MonitorMessages<'w, 's> {
+    known: Local<'s, HashSet<u32>>,
+    connected: MessageWriter<'w, WallpaperMonitorConnected>,
+    disconnected: MessageWriter<'w, WallpaperMonitorDisconnected>,
+}
+
+#[cfg(any(feature = "wayland", feature = "x11", target_os = "windows"))]
+impl MonitorMessages<'_, '_> {
+    /// Announces the monitors in `current` that weren't there last time and
+    /// the ones that are gone since.
+    pub(crate) fn update(&mut self, current: Vec<WallpaperMonitorConnected>) {
+        let ids: HashSet<u32> = current.iter().map(|monitor| monitor.output).collect();
+        for output in self.known.difference(&ids) {
+            debug!("Monitor {output} disconnected");
+            self.disconnected
+                .write(WallpaperMonitorDisconnected { output: *output });
+        }
+        for monitor in current {
+            if !self.known.contains(&monitor.output) {
+                debug!("Monitor {} connected: {:?}", monitor.output, monitor.name);
+                self.connected.write(monitor);
+            }
+        }
+        *self.known = ids;
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    WallpaperFocusEvent, WallpaperPaused, WallpaperPointerState, idle::pointer_active,
+    redraw::WallpaperDamage,
+};
+
+/// Whether the wallpaper animation runs.
+///
+/// Pausing pauses `Time<Virtual>` (and with it `Time` in `Update` and fixed
+/// timesteps) and stops the Wayland/X11 backends from presenting; other
+/// backends keep presenting the paused scene. Can be changed at runtime.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperPlayState {
+    pub state: PlayState,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum PlayState {
+    #[default]
+    Playing,
+    Paused,
+    /// Advance exactly one frame, then switch to `Paused`.
+    Stepping,
+}
+
+/// Rules that pause the wallpaper automatically and resume it once they no
+/// longer apply. A pause set by hand through [`WallpaperPlayState`] is left
+/// alone.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WallpaperAutoPlay {
+    /// Pause once the pointer has been still for this long.
+    pub pause_after_pointer_idle: Option<Duration>,
+    /// Pause after the wallpaper lost keyboard focus (see
+    /// [`WallpaperFocusEvent`]) until it regains it.
+    pub pause_on_focus_lost: bool,
+}
+
+/// Set once a frame started with [`PlayState::Stepping`], so the step ends
+/// after that frame rather than after the one that requested it.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub(crate) struct PlayStepStarted(pub bool);
+
+/// Follows [`WallpaperPlayState`] with virtual time, before time advances.
+pub(crate) fn apply_play_state_system(
+    play_state: Res<WallpaperPlayState>,
+    mut step_started: ResMut<PlayStepStarted>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    match play_state.state {
+        PlayState::Playing => time.unpause(),
+        PlayState::Stepping => {
+            time.unpause();
+            step_started.0 = true;
+        }
+        PlayState::Paused => time.pause(),
+    }
+}
+
+/// Drops damage while paused (or while [`WallpaperPaused`] exists) so the
+/// last frame stays on screen.
+pub(crate) fn skip_paused_presents_system(
+    play_state: Res<WallpaperPlayState>,
+    screen_paused: Option<Res<WallpaperPaused>>,
+    mut damage: ResMut<WallpaperDamage>,
+) {
+    if play_state.state == PlayState::Paused || screen_paused.is_some() {
+        damage.dirty = false;
+    }
+}
+
+/// Ends a single-frame step once the stepped frame has run.
+pub(crate) fn finish_play_step_system(
+    mut play_state: ResMut<WallpaperPlayState>,
+    mut step_started: ResMut<PlayStepStarted>,
+) {
+    if !std::mem::take(&mut step_started.0) {
+        return;
+    }
+    if play_state.state == PlayState::Stepping {
+        play_state.state = PlayState::Paused;
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct AutoPlayTracker {
+    last_activity: Duration,
+    focus_lost: bool,
+    /// Whether the current pause came from the rules.
+    auto_paused: bool,
+}
+
+/// Pauses and resumes according to [`WallpaperAutoPlay`].
+pub(crate) fn auto_play_system(
+    rules: Res<WallpaperAutoPlay>,
+    pointer_state: Res<WallpaperPointerState>,
+    mut focus_events: MessageReader<WallpaperFocusEvent>,
+    time: Res<Time<Real>>,
+    mut tracker: Local<AutoPlayTracker>,
+    mut play_state: ResMut<WallpaperPlayState>,
+) {
+    let now = time.elapsed();
+    if pointer_active(&pointer_state) {
+        tracker.last_activity = now;
+    }
+    if let Some(event) = focus_events.read().last() {
+        tracker.focus_lost = !event.gained;
+    }
+
+    let pointer_idle = rules
+        .pause_after_pointer_idle
+        .is_some_and(|timeout| now.saturating_sub(tracker.last_activity) >= timeout);
+    let pause = pointer_idle || (rules.pause_on_focus_lost && tracker.focus_lost);
+
+    if pause && play_state.state == PlayState::Playing {
+        play_state.state = PlayState::Paused;
+        tracker.auto_paused = true;
+    } else if !pause && tracker.auto_paused {
+        if play_state.state == PlayState::Paused {
+            play_state.state = PlayState::Playing;
+        }
+        tracker.auto_paused = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::time::{TimePlugin, TimeSystems, TimeUpdateStrategy};
+
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    #[derive(Resource, Default)]
+    struct RequestStep(bool);
+
+    fn request_step_system(
+        mut request: ResMut<RequestStep>,
+        mut play_state: ResMut<WallpaperPlayState>,
+    ) {
+        if std::mem::take(&mut request.0) {
+            play_state.state = PlayState::Stepping;
+        }
+    }
+
+    fn paused_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(TimePlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .insert_resource(WallpaperPlayState {
+                state: PlayState::Paused,
+            })
+            .init_resource::<PlayStepStarted>()
+            .init_resource::<RequestStep>()
+            .add_systems(
+                First,
+                apply_play_state_system
+                    .before(TimeSystems)
+                    .run_if(resource_changed::<WallpaperPlayState>),
+            )
+            .add_systems(Update, request_step_system)
+            .add_systems(Last, finish_play_step_system);
+        app
+    }
+
+    fn virtual_elapsed(app: &App) -> Duration {
+        app.world().resource::<Time<Virtual>>().elapsed()
+    }
+
+    #[test]
+    fn step_from_update_advances_one_frame() {
+        let mut app = paused_app();
+        for _ in 0..3 {
+            app.update();
+        }
+        let before = virtual_elapsed(&app);
+
+        app.world_mut().resource_mut::<RequestStep>().0 = true;
+        for _ in 0..4 {
+            app.update();
+        }
+
+        assert_eq!(virtual_elapsed(&app) - before, FRAME);
+        assert_eq!(
+            app.world().resource::<WallpaperPlayState>().state,
+            PlayState::Paused
+        );
+    }
+
+    #[test]
+    fn paused_time_stays_still() {
+        let mut app = paused_app();
+        for _ in 0..3 {
+            app.update();
+        }
+        let before = virtual_elapsed(&app);
+        for _ in 0..3 {
+            app.update();
+        }
+
+        assert_eq!(virtual_elapsed(&app), before);
+    }
+}
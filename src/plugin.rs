@@ -1,9 +1,56 @@
-use bevy::prelude::*;
+use std::time::Duration;
 
-use crate::{WallpaperPointerState, WallpaperSurfaceInfo, WallpaperTargetMonitor};
+#[cfg(not(any(target_os = "windows", all(target_os = "macos", feature = "macos"))))]
+use bevy::window::PrimaryWindow;
+use bevy::{
+    camera::RenderTarget,
+    prelude::*,
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_resource::TextureFormat,
+    },
+    time::TimeSystems,
+};
+
+use crate::{
+    LiveWallpaperCamera, WallpaperAllowTearing, WallpaperAutoPlay, WallpaperBackend,
+    WallpaperBackendClosed, WallpaperBackendStatus, WallpaperClipboard, WallpaperColorSpace,
+    WallpaperContentType, WallpaperCursor, WallpaperDoubleClick, WallpaperDragEvent,
+    WallpaperEnvConfig, WallpaperFifoPacing, WallpaperFocusEvent, WallpaperForceRedraw,
+    WallpaperFrameLatency, WallpaperFrameRateLimit, WallpaperFrameStats, WallpaperGestureEvent,
+    WallpaperGpuPreference, WallpaperIdlePolicy, WallpaperIdleState, WallpaperInputConfig,
+    WallpaperKeyboardFocus, WallpaperLockEvent, WallpaperLockScreen, WallpaperLongPress,
+    WallpaperMemoryBudget, WallpaperMemoryPolicy, WallpaperMemoryPressure,
+    WallpaperMonitorConnected, WallpaperMonitorDisconnected, WallpaperPaused, WallpaperPlayState,
+    WallpaperPointerConstraint, WallpaperPointerEvent, WallpaperPointerState,
+    WallpaperPreferredFormats, WallpaperPresentMode, WallpaperRedrawMode, WallpaperScrollEvent,
+    WallpaperStylusEvent, WallpaperStylusState, WallpaperSurfaceChanged, WallpaperSurfaceFormat,
+    WallpaperSurfaceInfo, WallpaperSurfaceRecovery, WallpaperSurfaces, WallpaperSystemSet,
+    WallpaperTargetMonitor, WallpaperTearingState, WallpaperVisibility,
+    WallpaperWaylandDiagnostics, WallpaperX11Info,
+    gpu_preference::log_wallpaper_adapter,
+    idle::{CompositorIdleNotify, pointer_idle_fallback_system, throttle_idle_presents_system},
+    input::{detect_click_gestures_system, detect_drag_gestures_system},
+    instance_lock,
+    memory_budget::update_memory_budget_system,
+    play_state::{
+        PlayStepStarted, apply_play_state_system, auto_play_system, finish_play_step_system,
+        skip_paused_presents_system,
+    },
+    redraw::{WallpaperDamage, limit_frame_rate_system, track_wallpaper_damage_system},
+    surface_info::detect_surface_changes_system,
+};
 
 /// Main plugin to run the live wallpaper.
-#[derive(Default)]
+///
+/// Build it with [`LiveWallpaperPlugin::new`] and the chained setters, e.g.
+/// `LiveWallpaperPlugin::new().target(WallpaperTargetMonitor::All).frame_limit(60)`;
+/// they keep compiling as fields are added. The fields stay public for struct
+/// literals. Fields left at their default can be overridden from the
+/// environment; see [`WallpaperEnvConfig`].
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct LiveWallpaperPlugin {
     /// Selects which monitor(s) to render to (primary, index, or all).
     pub target_monitor: WallpaperTargetMonitor,
@@ -11,10 +58,175 @@ pub struct LiveWallpaperPlugin {
     pub display_mode: WallpaperDisplayMode,
     /// (Linux only) Selects the backend to use for rendering.
     pub linux_backend: LinuxBackend,
+    /// (Wayland only) What to do when the compositor lacks layer-shell support.
+    pub wayland_fallback: WaylandFallback,
+    /// (Wayland only) Layer-shell layer the wallpaper surfaces are placed on.
+    pub wayland_layer: WaylandLayer,
+    /// (Wayland only) Whether the layer surfaces can take keyboard focus.
+    pub keyboard_interactivity: WaylandKeyboardInteractivity,
+    /// (Wayland only) Exclusive zone of the layer surfaces; `None` uses
+    /// [`WaylandLayer::exclusive_zone`].
+    pub wayland_exclusive_zone: Option<i32>,
+    /// (Wayland only) Distance of the layer surfaces from the output edges.
+    pub wayland_margins: WaylandMargins,
+    /// (Wayland only) How outputs are ordered for [`WallpaperTargetMonitor`].
+    pub wayland_output_order: WaylandOutputOrder,
+    /// (Wayland only) Connector name (e.g. `DP-1`) of the output used for
+    /// [`WallpaperTargetMonitor::Primary`]; see [`WaylandPrimaryOutput`].
+    pub wayland_primary_output: Option<String>,
+    /// (Wayland only) Layer-shell namespace, e.g. for compositor layer rules.
+    /// Empty uses `egl_background_<output>`.
+    pub wayland_namespace: String,
+    /// (Wayland only) Color shown on new surfaces until the first frame is
+    /// presented; `None` leaves them empty. Needs `wp_viewporter`.
+    pub placeholder_color: Option<Color>,
+    /// (Wayland only) How long to wait for the compositor to answer the
+    /// initial roundtrip before the connection counts as failed (and
+    /// [`WaylandFallback`] applies). `None` waits forever.
+    pub wayland_connect_timeout: Option<Duration>,
+    /// (Wayland only) Socket to connect to, e.g. `wayland-2` (relative to
+    /// `XDG_RUNTIME_DIR`) or an absolute path. `None` follows
+    /// `WAYLAND_DISPLAY`; setting it also makes [`LinuxBackend::Auto`] pick
+    /// Wayland.
+    pub wayland_socket: Option<String>,
+    /// Cursor shown over the wallpaper. Defaults to
+    /// [`WallpaperCursor::Hidden`]; use `Default` for interactive wallpapers.
+    pub cursor: WallpaperCursor,
+    /// (X11 only) Backend tuning such as the RandR debounce.
+    pub x11_config: WallpaperX11Config,
+    /// (X11 only) Display to connect to, e.g. `:1`. `None` follows `DISPLAY`.
+    pub x11_display: Option<String>,
+    /// (X11 only) Reset the screen saver every 30 s while the wallpaper
+    /// plays, so the screen doesn't blank or power down.
+    pub inhibit_dpms: bool,
+    /// (X11 only) Turn DPMS off while the app runs and back on when it exits.
+    pub disable_dpms: bool,
+    /// (X11 only) `WM_NAME` / `_NET_WM_NAME` of the wallpaper windows, e.g.
+    /// to find them with `xdotool`.
+    pub window_title: String,
+    /// (X11 only) `WM_CLASS` of the wallpaper windows, e.g. for compositor
+    /// window rules.
+    pub window_class: String,
+    /// (X11 only) Let the window manager manage the wallpaper windows as
+    /// `_NET_WM_WINDOW_TYPE_DESKTOP` windows instead of creating them
+    /// override-redirect. Some window managers stack and composite them
+    /// better that way.
+    pub x11_managed_window: bool,
+    /// What happens to the app once the display server closes the wallpaper.
+    pub shutdown_behavior: WallpaperShutdownBehavior,
+    /// Refuse to start in wallpaper mode while another instance targets the
+    /// same [`WallpaperTargetMonitor`], or either one targets
+    /// [`WallpaperTargetMonitor::All`]. Targets aren't resolved to outputs, so
+    /// `Primary` and `Index(0)` don't conflict. Off by default; bypassed by
+    /// `BEVY_WALLPAPER_FORCE=1`. When the lock is taken, no wallpaper
+    /// backend is added and [`WallpaperBackendStatus`] is
+    /// [`WallpaperBackendStatus::Failed`]; the app keeps running.
+    pub single_instance: bool,
+    /// Read pointer input. When `false` the Wayland backend binds no seats,
+    /// X11 doesn't query the pointer and Windows doesn't poll the cursor, so
+    /// [`WallpaperPointerState`] stays empty. Only read at build time.
+    pub capture_input: bool,
+    /// Timing and distance thresholds for double-click / long-press detection.
+    pub input_config: WallpaperInputConfig,
+    /// Whether the wallpaper is composited opaquely or with alpha.
+    pub alpha_mode: WallpaperAlphaMode,
+    /// Whether unchanged frames are presented (Wayland/X11).
+    pub redraw_mode: WallpaperRedrawMode,
+    /// Frame rate throttling while the user is idle (Wayland/X11).
+    pub idle_policy: WallpaperIdlePolicy,
+    /// Rules that pause the animation automatically; see
+    /// [`WallpaperPlayState`].
+    pub auto_play: WallpaperAutoPlay,
+    /// (Wayland/X11) Upper bound on presented frames per second.
+    pub max_fps: Option<u32>,
+    /// (Wayland/X11) Vsync behavior of the wallpaper surfaces.
+    pub present_mode: WallpaperPresentMode,
+    /// (Wayland/X11) Frames queued ahead of the display on the wallpaper
+    /// surfaces.
+    pub frame_latency: WallpaperFrameLatency,
+    /// (Wayland/X11) Color precision of the wallpaper surfaces.
+    pub surface_format: WallpaperSurfaceFormat,
+    /// (Wayland/X11) Exact surface formats to try first, e.g. `Rgb10a2Unorm`
+    /// for 10-bit output.
+    pub preferred_formats: Vec<TextureFormat>,
+    /// (Wayland only) Color space the wallpaper surfaces are tagged with.
+    pub color_space: WallpaperColorSpace,
+    /// (Wayland only) Ask the compositor for tearing, low-latency presentation.
+    pub allow_tearing: bool,
+    /// (Wayland only) Pace `Mailbox` presents with `wp_fifo_v1`; see
+    /// [`WallpaperFifoPacing`].
+    pub fifo_pacing: bool,
+    /// (Wayland only) Content type hint for the wallpaper surfaces.
+    pub content_type: WallpaperContentType,
+    /// Skip presenting to outputs whose surface is occluded, see
+    /// [`WallpaperVisibility`]. Powered off outputs are always skipped.
+    pub skip_occluded_outputs: bool,
+    /// GPU to render on; see [`WallpaperGpuPreference`] for how to apply it.
+    pub gpu_preference: WallpaperGpuPreference,
+    /// GPU memory budget and the reaction to running short, see
+    /// [`WallpaperMemoryPolicy`].
+    pub memory_policy: WallpaperMemoryPolicy,
+}
+
+impl Default for LiveWallpaperPlugin {
+    fn default() -> Self {
+        Self {
+            target_monitor: default(),
+            display_mode: default(),
+            linux_backend: default(),
+            wayland_fallback: default(),
+            wayland_layer: default(),
+            keyboard_interactivity: default(),
+            wayland_exclusive_zone: default(),
+            wayland_margins: default(),
+            wayland_output_order: default(),
+            wayland_primary_output: default(),
+            wayland_namespace: default(),
+            placeholder_color: Some(Color::BLACK),
+            wayland_connect_timeout: Some(Duration::from_secs(5)),
+            wayland_socket: None,
+            cursor: WallpaperCursor::Hidden,
+            x11_config: default(),
+            x11_display: None,
+            inhibit_dpms: false,
+            disable_dpms: false,
+            window_title: "bevy_live_wallpaper".into(),
+            window_class: "bevy_live_wallpaper".into(),
+            x11_managed_window: false,
+            shutdown_behavior: default(),
+            single_instance: false,
+            capture_input: true,
+            input_config: default(),
+            alpha_mode: default(),
+            redraw_mode: default(),
+            idle_policy: default(),
+            auto_play: default(),
+            max_fps: default(),
+            present_mode: default(),
+            frame_latency: default(),
+            surface_format: default(),
+            preferred_formats: default(),
+            color_space: default(),
+            allow_tearing: default(),
+            fifo_pacing: default(),
+            content_type: default(),
+            skip_occluded_outputs: default(),
+            gpu_preference: default(),
+            memory_policy: default(),
+        }
+    }
 }
 
 /// Selects wallpaper presentation mode.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+///
+/// The plugin inserts this as a resource; changing it at runtime moves the
+/// scene between the desktop background and a normal window. When switching
+/// back to `Wallpaper` on Linux the primary window is despawned, so apps that
+/// start windowed should use `ExitCondition::DontExit`.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum WallpaperDisplayMode {
     /// Render directly to desktop surfaces (Wayland layer-shell, X11 root, Windows WorkerW).
     #[default]
@@ -23,8 +235,29 @@ pub enum WallpaperDisplayMode {
     Windowed,
 }
 
+/// Selects how the compositor blends the wallpaper with what lies beneath it.
+///
+/// `Transparent` is supported on Wayland, and on X11 when the screen has a
+/// 32-bit ARGB visual and a compositor (e.g. picom) is running. For it to
+/// have any effect the cameras should clear to a transparent color (e.g.
+/// `ClearColorConfig::Custom(Color::NONE)`). Other backends stay opaque.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperAlphaMode {
+    /// Ignore alpha and let the compositor skip blending.
+    #[default]
+    Opaque,
+    /// Blend the rendered alpha channel (pre- or post-multiplied, whichever
+    /// the surface supports) over the layers below.
+    Transparent,
+}
+
 /// Selects the Linux backend to use for rendering.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum LinuxBackend {
     /// Automatically select the backend based on the environment (prefers Wayland).
     #[default]
@@ -35,18 +268,595 @@ pub enum LinuxBackend {
     X11,
 }
 
+/// Reaction to the backend closing for good (see [`WallpaperBackendClosed`]).
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperShutdownBehavior {
+    /// Send `AppExit::Success` so no orphaned wallpaper process is left behind.
+    #[default]
+    ExitApp,
+    /// Keep the app running; handle [`WallpaperBackendClosed`] yourself.
+    KeepRunning,
+}
+
+/// Fallback used when the Wayland compositor doesn't offer `zwlr_layer_shell_v1`
+/// (e.g. GNOME/Mutter).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WaylandFallback {
+    /// Report [`WallpaperBackendStatus::Unsupported`] and render nothing.
+    #[default]
+    None,
+    /// Use the X11 backend through XWayland (requires the `x11` feature).
+    X11,
+    /// Switch to [`WallpaperDisplayMode::Windowed`] and open a window.
+    Windowed,
+}
+
+/// Layer-shell layer of the Wayland wallpaper surfaces, from bottom to top.
+///
+/// Inserted as a resource; changing it at runtime recreates the surfaces on
+/// the new layer. Surfaces on every layer but `Background` ignore other
+/// surfaces' exclusive zones (`exclusive_zone(-1)`) and stretch under panels.
+/// Compositors such as Sway don't allow that on `Background`, so surfaces
+/// there use an exclusive zone of 0 and leave room for panels instead.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WaylandLayer {
+    Background,
+    /// Below windows but above `Background`, where desktop icons usually sit.
+    #[default]
+    Bottom,
+    /// Above normal windows; the wallpaper covers them.
+    Top,
+    /// Above everything, including fullscreen windows.
+    Overlay,
+}
+
+impl WaylandLayer {
+    /// Exclusive zone requested for surfaces on this layer.
+    pub fn exclusive_zone(self) -> i32 {
+        match self {
+            Self::Background => 0,
+            Self::Bottom | Self::Top | Self::Overlay => -1,
+        }
+    }
+}
+
+/// Exclusive zone of the Wayland layer surfaces, in logical pixels.
+///
+/// `None` uses [`WaylandLayer::exclusive_zone`]. A positive value reserves
+/// that much space at the anchored edge, `0` leaves room for other surfaces'
+/// exclusive zones and `-1` stretches under them. Inserted as a resource;
+/// changes at runtime are applied to the existing surfaces.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WaylandExclusiveZone(pub Option<i32>);
+
+impl WaylandExclusiveZone {
+    /// Exclusive zone requested for surfaces on `layer`.
+    pub fn resolve(self, layer: WaylandLayer) -> i32 {
+        self.0.unwrap_or_else(|| layer.exclusive_zone())
+    }
+}
+
+/// Margins of the Wayland layer surfaces from the output edges, in logical
+/// pixels.
+///
+/// Margins shrink the wallpaper, e.g. to a strip behind a transparent top
+/// bar; [`crate::WallpaperSurfaceInfo`] and pointer positions account for
+/// them. Inserted as a resource; changes at runtime are applied to the
+/// existing surfaces.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WaylandMargins {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+/// Keyboard focus policy of the Wayland layer surfaces.
+///
+/// Inserted as a resource; changes at runtime are applied to the existing
+/// surfaces.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WaylandKeyboardInteractivity {
+    /// Never take keyboard focus; the desktop keeps its usual behavior.
+    #[default]
+    None,
+    /// Take focus when the user clicks the wallpaper (layer-shell v4+;
+    /// older compositors fall back to `None`).
+    OnDemand,
+    /// Grab the keyboard while the wallpaper is on the `Top` or `Overlay`
+    /// layer. Use with care: other windows stop receiving key events.
+    Exclusive,
+}
+
+/// Order of Wayland outputs for [`WallpaperTargetMonitor::Index`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WaylandOutputOrder {
+    /// Sorted by connector name (`wl_output` v4); outputs without one come
+    /// after, sorted by logical position. Stable across restarts.
+    #[default]
+    Name,
+    /// The order the compositor announced the outputs in, which can change
+    /// between boots. Kept for setups that relied on it.
+    Registry,
+}
+
+/// Output used for [`WallpaperTargetMonitor::Primary`] on Wayland, which has
+/// no primary output of its own.
+///
+/// Holds a connector name (`wl_output` v4, e.g. `DP-1`). Without one, or when
+/// no output has that name, the output at the logical origin is used, else the
+/// largest one (the earliest in [`WaylandOutputOrder`] on ties). Can be
+/// changed at runtime.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WaylandPrimaryOutput(pub Option<String>);
+
+/// X11 backend tuning.
+///
+/// Inserted as a resource; changes apply on the next frame.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct WallpaperX11Config {
+    /// How long to wait after the first RandR change before re-reading the
+    /// monitors, so a mode switch's burst of events reconfigures only once.
+    pub randr_debounce: Duration,
+}
+
+impl Default for WallpaperX11Config {
+    fn default() -> Self {
+        Self {
+            randr_debounce: Duration::from_millis(100),
+        }
+    }
+}
+
 impl Plugin for LiveWallpaperPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(self.target_monitor)
+        WallpaperEnvConfig::from_env().apply(self).build_app(app);
+    }
+}
+
+impl LiveWallpaperPlugin {
+    /// Same as [`LiveWallpaperPlugin::default`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Monitor(s) to render to.
+    pub fn target(mut self, target_monitor: WallpaperTargetMonitor) -> Self {
+        self.target_monitor = target_monitor;
+        self
+    }
+
+    pub fn display_mode(mut self, display_mode: WallpaperDisplayMode) -> Self {
+        self.display_mode = display_mode;
+        self
+    }
+
+    /// Starts in a normal window instead of on the desktop.
+    pub fn windowed(self) -> Self {
+        self.display_mode(WallpaperDisplayMode::Windowed)
+    }
+
+    pub fn linux_backend(mut self, linux_backend: LinuxBackend) -> Self {
+        self.linux_backend = linux_backend;
+        self
+    }
+
+    pub fn wayland_fallback(mut self, wayland_fallback: WaylandFallback) -> Self {
+        self.wayland_fallback = wayland_fallback;
+        self
+    }
+
+    pub fn wayland_layer(mut self, wayland_layer: WaylandLayer) -> Self {
+        self.wayland_layer = wayland_layer;
+        self
+    }
+
+    /// Overrides the layer's exclusive zone; see [`WaylandExclusiveZone`].
+    pub fn wayland_exclusive_zone(mut self, zone: i32) -> Self {
+        self.wayland_exclusive_zone = Some(zone);
+        self
+    }
+
+    pub fn wayland_socket(mut self, socket: impl Into<String>) -> Self {
+        self.wayland_socket = Some(socket.into());
+        self
+    }
+
+    pub fn x11_display(mut self, display: impl Into<String>) -> Self {
+        self.x11_display = Some(display.into());
+        self
+    }
+
+    pub fn x11_managed_window(mut self, managed: bool) -> Self {
+        self.x11_managed_window = managed;
+        self
+    }
+
+    pub fn inhibit_dpms(mut self, inhibit: bool) -> Self {
+        self.inhibit_dpms = inhibit;
+        self
+    }
+
+    pub fn disable_dpms(mut self, disable: bool) -> Self {
+        self.disable_dpms = disable;
+        self
+    }
+
+    pub fn cursor(mut self, cursor: WallpaperCursor) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Hides the cursor over the wallpaper, or shows the default one.
+    pub fn hide_cursor(self, hide: bool) -> Self {
+        self.cursor(if hide {
+            WallpaperCursor::Hidden
+        } else {
+            WallpaperCursor::default()
+        })
+    }
+
+    pub fn capture_input(mut self, capture_input: bool) -> Self {
+        self.capture_input = capture_input;
+        self
+    }
+
+    pub fn alpha_mode(mut self, alpha_mode: WallpaperAlphaMode) -> Self {
+        self.alpha_mode = alpha_mode;
+        self
+    }
+
+    pub fn redraw_mode(mut self, redraw_mode: WallpaperRedrawMode) -> Self {
+        self.redraw_mode = redraw_mode;
+        self
+    }
+
+    pub fn idle_policy(mut self, idle_policy: WallpaperIdlePolicy) -> Self {
+        self.idle_policy = idle_policy;
+        self
+    }
+
+    /// Caps presented frames per second; `0` removes the cap.
+    pub fn frame_limit(mut self, fps: u32) -> Self {
+        self.max_fps = (fps > 0).then_some(fps);
+        self
+    }
+
+    pub fn present_mode(mut self, present_mode: WallpaperPresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    pub fn surface_format(mut self, surface_format: WallpaperSurfaceFormat) -> Self {
+        self.surface_format = surface_format;
+        self
+    }
+
+    pub fn shutdown_behavior(mut self, shutdown_behavior: WallpaperShutdownBehavior) -> Self {
+        self.shutdown_behavior = shutdown_behavior;
+        self
+    }
+
+    pub fn single_instance(mut self, single_instance: bool) -> Self {
+        self.single_instance = single_instance;
+        self
+    }
+
+    pub fn placeholder_color(mut self, color: Option<Color>) -> Self {
+        self.placeholder_color = color;
+        self
+    }
+
+    pub fn gpu_preference(mut self, gpu_preference: WallpaperGpuPreference) -> Self {
+        self.gpu_preference = gpu_preference;
+        self
+    }
+
+    pub fn memory_policy(mut self, memory_policy: WallpaperMemoryPolicy) -> Self {
+        self.memory_policy = memory_policy;
+        self
+    }
+
+    /// Inserts the resources configured through the plugin fields.
+    fn insert_settings(&self, app: &mut App) {
+        app.insert_resource(self.target_monitor.clone())
+            .insert_resource(self.display_mode)
+            .insert_resource(self.alpha_mode)
+            .insert_resource(self.redraw_mode)
+            .insert_resource(self.shutdown_behavior)
+            .insert_resource(self.wayland_layer)
+            .insert_resource(self.wayland_output_order)
+            .insert_resource(WaylandPrimaryOutput(self.wayland_primary_output.clone()))
+            .insert_resource(self.keyboard_interactivity)
+            .insert_resource(WaylandExclusiveZone(self.wayland_exclusive_zone))
+            .insert_resource(self.wayland_margins)
+            .insert_resource(self.cursor)
+            .insert_resource(self.x11_config)
+            .insert_resource(self.idle_policy)
+            .insert_resource(self.auto_play)
+            .insert_resource(WallpaperFrameRateLimit(self.max_fps))
+            .insert_resource(self.present_mode)
+            .insert_resource(self.frame_latency)
+            .insert_resource(self.surface_format)
+            .insert_resource(WallpaperPreferredFormats(self.preferred_formats.clone()))
+            .insert_resource(self.color_space)
+            .insert_resource(WallpaperAllowTearing(self.allow_tearing))
+            .insert_resource(WallpaperFifoPacing(self.fifo_pacing))
+            .insert_resource(self.content_type)
+            .insert_resource(self.gpu_preference.clone())
+            .insert_resource(self.memory_policy);
+    }
+
+    fn build_app(&self, app: &mut App) {
+        let lock = if self.single_instance && self.display_mode == WallpaperDisplayMode::Wallpaper {
+            instance_lock::acquire(&self.target_monitor).map(Some)
+        } else {
+            Ok(None)
+        };
+
+        self.insert_settings(app);
+        app.init_resource::<WallpaperPlayState>()
+            .init_resource::<PlayStepStarted>()
+            .init_resource::<WallpaperMemoryBudget>()
+            .init_resource::<WallpaperClipboard>()
+            .init_resource::<WallpaperIdleState>()
+            .init_resource::<CompositorIdleNotify>()
+            .add_message::<WallpaperBackendClosed>()
+            .init_resource::<WallpaperForceRedraw>()
+            .init_resource::<WallpaperDamage>()
+            .add_plugins(ExtractResourcePlugin::<WallpaperDamage>::default())
+            .init_resource::<WallpaperBackendStatus>()
             .init_resource::<WallpaperPointerState>()
-            .init_resource::<WallpaperSurfaceInfo>();
+            .init_resource::<WallpaperPointerConstraint>()
+            .init_resource::<WallpaperSurfaceInfo>()
+            .init_resource::<WallpaperSurfaces>()
+            .init_resource::<WallpaperSurfaceRecovery>()
+            .init_resource::<WallpaperFrameStats>()
+            .init_resource::<WallpaperStylusState>()
+            .init_resource::<WallpaperKeyboardFocus>()
+            .insert_resource(WallpaperVisibility {
+                skip_occluded: self.skip_occluded_outputs,
+                ..default()
+            })
+            .init_resource::<WallpaperTearingState>()
+            .init_resource::<WallpaperLockScreen>()
+            .insert_resource(self.input_config)
+            .add_message::<WallpaperPointerEvent>()
+            .add_message::<WallpaperScrollEvent>()
+            .add_message::<WallpaperDoubleClick>()
+            .add_message::<WallpaperLongPress>()
+            .add_message::<WallpaperDragEvent>()
+            .add_message::<WallpaperStylusEvent>()
+            .add_message::<WallpaperFocusEvent>()
+            .add_message::<WallpaperGestureEvent>()
+            .add_message::<WallpaperLockEvent>()
+            .add_message::<WallpaperMemoryPressure>()
+            .add_message::<WallpaperMonitorConnected>()
+            .add_message::<WallpaperMonitorDisconnected>()
+            .add_message::<WallpaperSurfaceChanged>()
+            .configure_sets(
+                PostUpdate,
+                (
+                    WallpaperSystemSet::BackendEvents,
+                    WallpaperSystemSet::SyncRenderTarget,
+                    WallpaperSystemSet::AssignCamera,
+                )
+                    .chain(),
+            )
+            .add_systems(Startup, log_wallpaper_adapter)
+            .add_systems(
+                PostUpdate,
+                (
+                    display_mode_change_system.before(WallpaperSystemSet::BackendEvents),
+                    update_memory_budget_system.before(WallpaperSystemSet::BackendEvents),
+                    detect_click_gestures_system.after(WallpaperSystemSet::BackendEvents),
+                    detect_drag_gestures_system.after(WallpaperSystemSet::BackendEvents),
+                    detect_surface_changes_system.after(WallpaperSystemSet::BackendEvents),
+                    track_wallpaper_damage_system.after(TransformSystems::Propagate),
+                    exit_on_backend_closed_system.after(WallpaperSystemSet::BackendEvents),
+                    pointer_idle_fallback_system.after(WallpaperSystemSet::BackendEvents),
+                    throttle_idle_presents_system
+                        .after(track_wallpaper_damage_system)
+                        .after(pointer_idle_fallback_system),
+                    limit_frame_rate_system.after(throttle_idle_presents_system),
+                    auto_play_system.after(WallpaperSystemSet::BackendEvents),
+                    skip_paused_presents_system
+                        .after(limit_frame_rate_system)
+                        .after(auto_play_system),
+                ),
+            )
+            .add_systems(
+                First,
+                apply_play_state_system
+                    .before(TimeSystems)
+                    .run_if(resource_changed::<WallpaperPlayState>),
+            )
+            .add_systems(Last, finish_play_step_system);
+        // Wayland takes it through its connect options.
+        #[cfg(any(feature = "x11", target_os = "windows"))]
+        app.insert_resource(crate::input::CaptureInput(self.capture_input));
 
-        match self.display_mode {
-            WallpaperDisplayMode::Wallpaper => {
+        register_reflect_types(app);
+
+        // Both backends are installed so the mode can be switched at runtime;
+        // each one idles while the other mode is active.
+        app.add_plugins(crate::windowed_backend::WindowedBackendPlugin);
+        match lock {
+            Ok(lock) => {
+                if let Some(lock) = lock {
+                    app.insert_resource(lock);
+                }
                 self.build_wallpaper_backend(app);
             }
-            WallpaperDisplayMode::Windowed => {
-                app.add_plugins(crate::windowed_backend::WindowedBackendPlugin);
+            Err(err) => {
+                // Leave it to the app to exit or carry on without a wallpaper.
+                error!(
+                    "{err}; not starting the wallpaper backend (set BEVY_WALLPAPER_FORCE=1 \
+                     to override)"
+                );
+                app.insert_resource(WallpaperBackendStatus::Failed(err));
+            }
+        }
+    }
+}
+
+/// Registers the public resources, components and messages so they show up in
+/// inspectors and scenes. Nested types are registered through these.
+fn register_reflect_types(app: &mut App) {
+    app.register_type::<LiveWallpaperCamera>()
+        .register_type::<WallpaperAllowTearing>()
+        .register_type::<WallpaperAlphaMode>()
+        .register_type::<WallpaperAutoPlay>()
+        .register_type::<WallpaperBackend>()
+        .register_type::<WallpaperBackendClosed>()
+        .register_type::<WallpaperBackendStatus>()
+        .register_type::<WallpaperClipboard>()
+        .register_type::<WallpaperColorSpace>()
+        .register_type::<WallpaperContentType>()
+        .register_type::<WallpaperCursor>()
+        .register_type::<WallpaperDisplayMode>()
+        .register_type::<WallpaperDoubleClick>()
+        .register_type::<WallpaperDragEvent>()
+        .register_type::<WallpaperFifoPacing>()
+        .register_type::<WallpaperFocusEvent>()
+        .register_type::<WallpaperForceRedraw>()
+        .register_type::<WallpaperFrameLatency>()
+        .register_type::<WallpaperFrameRateLimit>()
+        .register_type::<WallpaperFrameStats>()
+        .register_type::<WallpaperGestureEvent>()
+        .register_type::<WallpaperGpuPreference>()
+        .register_type::<WallpaperIdlePolicy>()
+        .register_type::<WallpaperIdleState>()
+        .register_type::<WallpaperInputConfig>()
+        .register_type::<WallpaperKeyboardFocus>()
+        .register_type::<WallpaperLockEvent>()
+        .register_type::<WallpaperLockScreen>()
+        .register_type::<WallpaperLongPress>()
+        .register_type::<WallpaperMemoryBudget>()
+        .register_type::<WallpaperMemoryPolicy>()
+        .register_type::<WallpaperMemoryPressure>()
+        .register_type::<WallpaperMonitorConnected>()
+        .register_type::<WallpaperMonitorDisconnected>()
+        .register_type::<WallpaperPaused>()
+        .register_type::<WallpaperPlayState>()
+        .register_type::<WallpaperPointerConstraint>()
+        .register_type::<WallpaperPointerEvent>()
+        .register_type::<WallpaperPointerState>()
+        .register_type::<WallpaperPreferredFormats>()
+        .register_type::<WallpaperPresentMode>()
+        .register_type::<WallpaperRedrawMode>()
+        .register_type::<WallpaperScrollEvent>()
+        .register_type::<WallpaperShutdownBehavior>()
+        .register_type::<WallpaperStylusEvent>()
+        .register_type::<WallpaperStylusState>()
+        .register_type::<WallpaperSurfaceChanged>()
+        .register_type::<WallpaperSurfaceFormat>()
+        .register_type::<WallpaperSurfaceInfo>()
+        .register_type::<WallpaperSurfaceRecovery>()
+        .register_type::<WallpaperTargetMonitor>()
+        .register_type::<WallpaperTearingState>()
+        .register_type::<WallpaperVisibility>()
+        .register_type::<WallpaperWaylandDiagnostics>()
+        .register_type::<WallpaperX11Config>()
+        .register_type::<WallpaperX11Info>()
+        .register_type::<WaylandKeyboardInteractivity>()
+        .register_type::<WaylandExclusiveZone>()
+        .register_type::<WaylandLayer>()
+        .register_type::<WaylandMargins>()
+        .register_type::<WaylandOutputOrder>()
+        .register_type::<WaylandPrimaryOutput>()
+        .register_type::<LinuxBackend>()
+        .register_type::<WaylandFallback>();
+}
+
+/// Run condition: the scene is rendered to the desktop background.
+pub(crate) fn wallpaper_mode_active(mode: Res<WallpaperDisplayMode>) -> bool {
+    *mode == WallpaperDisplayMode::Wallpaper
+}
+
+/// Run condition: the scene is rendered into a normal window.
+pub(crate) fn windowed_mode_active(mode: Res<WallpaperDisplayMode>) -> bool {
+    *mode == WallpaperDisplayMode::Windowed
+}
+
+fn exit_on_backend_closed_system(
+    behavior: Res<WallpaperShutdownBehavior>,
+    mut closed: MessageReader<WallpaperBackendClosed>,
+    mut app_exit: MessageWriter<AppExit>,
+) {
+    if closed.read().count() == 0 {
+        return;
+    }
+    match *behavior {
+        WallpaperShutdownBehavior::ExitApp => {
+            info!("Wallpaper backend closed; exiting");
+            app_exit.write(AppExit::Success);
+        }
+        WallpaperShutdownBehavior::KeepRunning => {
+            info!("Wallpaper backend closed; keeping the app running");
+        }
+    }
+}
+
+/// Handles the backend-independent part of a display mode switch.
+///
+/// Backends tear down or recreate their own surfaces when they observe the
+/// change; this system moves cameras back to the window, resets pointer state
+/// and (on Linux) spawns or despawns the primary window.
+fn display_mode_change_system(
+    mode: Res<WallpaperDisplayMode>,
+    mut commands: Commands,
+    mut cameras: Query<(Entity, &mut Camera), With<LiveWallpaperCamera>>,
+    mut pointer_state: ResMut<WallpaperPointerState>,
+    #[cfg(not(any(target_os = "windows", all(target_os = "macos", feature = "macos"))))]
+    primary_windows: Query<Entity, With<PrimaryWindow>>,
+) {
+    if !mode.is_changed() || mode.is_added() {
+        return;
+    }
+
+    info!("Switching wallpaper display mode to {:?}", *mode);
+    *pointer_state = WallpaperPointerState::default();
+
+    if *mode == WallpaperDisplayMode::Windowed {
+        for (entity, mut camera) in &mut cameras {
+            commands.entity(entity).insert(RenderTarget::default());
+            camera.viewport = None;
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", all(target_os = "macos", feature = "macos"))))]
+    match *mode {
+        WallpaperDisplayMode::Windowed => {
+            if primary_windows.is_empty() {
+                commands.spawn((Window::default(), PrimaryWindow));
+            }
+        }
+        WallpaperDisplayMode::Wallpaper => {
+            for entity in &primary_windows {
+                commands.entity(entity).despawn();
             }
         }
     }
@@ -57,24 +867,49 @@ impl LiveWallpaperPlugin {
         #[cfg(target_os = "windows")]
         app.add_plugins(crate::windows_backend::WallpaperWindowsPlugin);
 
-        #[cfg(all(not(target_os = "windows"), any(feature = "wayland", feature = "x11")))]
+        #[cfg(all(target_os = "macos", feature = "macos"))]
+        app.add_plugins(crate::macos_backend::WallpaperMacosPlugin);
+
+        #[cfg(all(
+            not(any(target_os = "windows", all(target_os = "macos", feature = "macos"))),
+            any(feature = "wayland", feature = "x11")
+        ))]
         self.build_linux_backend(app);
     }
 
-    #[cfg(all(not(target_os = "windows"), any(feature = "wayland", feature = "x11")))]
+    #[cfg(all(
+        not(any(target_os = "windows", all(target_os = "macos", feature = "macos"))),
+        any(feature = "wayland", feature = "x11")
+    ))]
     fn build_linux_backend(&self, app: &mut App) {
         const ONLY_WAYLAND: bool = cfg!(all(feature = "wayland", not(feature = "x11")));
         const ONLY_X11: bool = cfg!(all(feature = "x11", not(feature = "wayland")));
 
         let mut chosen_backend = self.linux_backend;
 
+        // Also read by the X11 backend when Wayland falls back to it.
+        #[cfg(feature = "x11")]
+        app.insert_resource(crate::x11::X11WindowIdentity {
+            title: self.window_title.clone(),
+            class: self.window_class.clone(),
+            managed: self.x11_managed_window,
+        });
+        #[cfg(feature = "x11")]
+        app.insert_resource(crate::x11::X11Display(self.x11_display.clone()));
+        #[cfg(feature = "x11")]
+        app.insert_resource(crate::x11::X11DpmsConfig {
+            inhibit: self.inhibit_dpms,
+            disable: self.disable_dpms,
+        });
+
         if chosen_backend == LinuxBackend::Auto {
             if ONLY_WAYLAND {
                 chosen_backend = LinuxBackend::Wayland;
             } else if ONLY_X11 {
                 chosen_backend = LinuxBackend::X11;
             } else {
-                let wayland_found = std::env::var("WAYLAND_DISPLAY").is_ok();
+                let wayland_found =
+                    self.wayland_socket.is_some() || std::env::var("WAYLAND_DISPLAY").is_ok();
                 if wayland_found {
                     chosen_backend = LinuxBackend::Wayland;
                 } else {
@@ -88,7 +923,16 @@ impl LiveWallpaperPlugin {
                 #[cfg(feature = "wayland")]
                 {
                     info!("Using Wayland backend.");
-                    app.add_plugins(crate::wayland::backend::WaylandBackendPlugin);
+                    app.add_plugins(crate::wayland::backend::WaylandBackendPlugin {
+                        fallback: self.wayland_fallback,
+                        namespace: self.wayland_namespace.clone(),
+                        placeholder_color: self.placeholder_color,
+                        connect: crate::wayland::backend::WaylandConnectOptions {
+                            socket: self.wayland_socket.clone(),
+                            timeout: self.wayland_connect_timeout,
+                            capture_input: self.capture_input,
+                        },
+                    });
                 }
                 #[cfg(not(feature = "wayland"))]
                 panic!(
@@ -110,3 +954,23 @@ impl LiveWallpaperPlugin {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_type_field_becomes_resource() {
+        let mut app = App::new();
+        LiveWallpaperPlugin {
+            content_type: WallpaperContentType::Game,
+            ..default()
+        }
+        .insert_settings(&mut app);
+
+        assert_eq!(
+            *app.world().resource::<WallpaperContentType>(),
+            WallpaperContentType::Game
+        );
+    }
+}
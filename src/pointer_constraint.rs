@@ -0,0 +1,25 @@
+use bevy::prelude::*;
+
+/// Keeps the pointer locked in place or confined while it is over the
+/// wallpaper, e.g. for FPS-style camera control.
+///
+/// Applied through `zwp_pointer_constraints_v1` on Wayland to the wallpaper
+/// surface the pointer is on, or the next one it enters. The compositor may
+/// refuse or lift the constraint at any time (e.g. on focus changes). While
+/// locked the compositor stops sending pointer motion. Other backends ignore
+/// it.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperPointerConstraint {
+    /// `None` releases the pointer.
+    pub constraint: Option<PointerConstraintKind>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+pub enum PointerConstraintKind {
+    /// The pointer stays where it is and the cursor is hidden.
+    Locked,
+    /// The pointer can't leave `region`, in surface-local logical pixels;
+    /// `None` confines it to the whole surface.
+    Confined { region: Option<Rect> },
+}
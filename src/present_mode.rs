@@ -0,0 +1,82 @@
+use bevy::{prelude::*, render::extract_resource::ExtractResource};
+
+/// Present mode of the wallpaper surfaces (Wayland/X11).
+///
+/// Can be changed at runtime, e.g. to switch between power saving and low
+/// latency; the surfaces are reconfigured on the next frame. Modes the surface
+/// doesn't support fall back to `Auto` with a warning. The Windows backend
+/// follows the primary window's `present_mode` instead.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperPresentMode {
+    /// Backend default: `Fifo` on Wayland, `Mailbox` or `Immediate` on X11.
+    #[default]
+    Auto,
+    /// Wait for vertical blank; never tears and saves the most power.
+    Fifo,
+    /// Replace the queued frame with the newest one; low latency without tearing.
+    Mailbox,
+    /// Present right away; lowest latency, may tear.
+    Immediate,
+}
+
+/// How many frames wgpu may queue ahead of the display on the wallpaper
+/// surfaces (Wayland/X11), i.e. `desired_maximum_frame_latency`.
+///
+/// `1` gives the lowest latency; `2` avoids artifacts some drivers show at `1`
+/// (e.g. X11 with EGL). Values below `1` are treated as `1`. Changing it at
+/// runtime reconfigures the surfaces. The Windows backend follows the primary
+/// window's `desired_maximum_frame_latency` instead.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WallpaperFrameLatency(pub u32);
+
+impl Default for WallpaperFrameLatency {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+/// (Wayland only) Paces [`WallpaperPresentMode::Mailbox`] presents with
+/// `wp_fifo_v1` and `wp_commit_timing_v1`, so the compositor takes at most one
+/// frame per refresh, aimed at the refresh after the last presented one.
+///
+/// Off by default: Vulkan drivers that use `wp_fifo_v1` themselves (e.g. Mesa
+/// 25) make a second fifo object on the surface a protocol error. Compositors
+/// without the protocols present as usual.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperFifoPacing(pub bool);
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+impl WallpaperPresentMode {
+    /// Picks the present mode to configure, trying `auto` in order when this
+    /// is `Auto` or the requested mode isn't in `supported`.
+    pub(crate) fn select(
+        self,
+        supported: &[wgpu::PresentMode],
+        auto: &[wgpu::PresentMode],
+    ) -> wgpu::PresentMode {
+        let requested = match self {
+            Self::Auto => None,
+            Self::Fifo => Some(wgpu::PresentMode::Fifo),
+            Self::Mailbox => Some(wgpu::PresentMode::Mailbox),
+            Self::Immediate => Some(wgpu::PresentMode::Immediate),
+        };
+        if let Some(mode) = requested {
+            if supported.contains(&mode) {
+                return mode;
+            }
+            warn!("Present mode {mode:?} is not supported by the wallpaper surface; using Auto");
+        }
+
+        auto.iter()
+            .copied()
+            .find(|mode| supported.contains(mode))
+            // Fifo is the one mode every surface has to support.
+            .unwrap_or(wgpu::PresentMode::Fifo)
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*, render::extract_resource::ExtractResource};
+
+/// Controls when the Wayland/X11 backends present a new frame.
+///
+/// The Windows and windowed backends present through Bevy's own window
+/// surface and always follow `Always`.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperRedrawMode {
+    /// Present every frame.
+    #[default]
+    Always,
+    /// Present only when the scene changed since the last present.
+    ///
+    /// Changes are detected from transforms, visibility, cameras, projections
+    /// and image assets. Anything else that affects the output (text, material
+    /// parameters, time-driven shaders) should request a frame through
+    /// [`WallpaperForceRedraw`]. Pair this with a reactive
+    /// `bevy::winit::WinitSettings` update mode so the app itself idles too.
+    OnChange,
+}
+
+/// Set to `true` to present the next frame regardless of detected changes.
+///
+/// Reset by the plugin once the frame has been handed to the renderer.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperForceRedraw(pub bool);
+
+/// (Wayland/X11) Caps presented frames per second; `None` presents as often as
+/// the redraw mode allows.
+///
+/// Frames in between still run `Update`, they just aren't presented. Set from
+/// [`crate::LiveWallpaperPlugin::max_fps`].
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperFrameRateLimit(pub Option<u32>);
+
+/// Whether the current frame should be presented, extracted to the render world.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug)]
+pub(crate) struct WallpaperDamage {
+    pub dirty: bool,
+}
+
+impl Default for WallpaperDamage {
+    fn default() -> Self {
+        Self { dirty: true }
+    }
+}
+
+/// Scene changes that count as damage in [`WallpaperRedrawMode::OnChange`].
+#[derive(SystemParam)]
+pub(crate) struct SceneChanges<'w, 's> {
+    transforms: Query<'w, 's, (), Changed<GlobalTransform>>,
+    visibility: Query<'w, 's, (), Changed<Visibility>>,
+    cameras: Query<'w, 's, (), Changed<Camera>>,
+    projections: Query<'w, 's, (), Changed<Projection>>,
+    image_events: MessageReader<'w, 's, AssetEvent<Image>>,
+}
+
+impl SceneChanges<'_, '_> {
+    fn any(&mut self) -> bool {
+        let images_changed = !self.image_events.is_empty();
+        self.image_events.clear();
+
+        images_changed
+            || !self.transforms.is_empty()
+            || !self.visibility.is_empty()
+            || !self.cameras.is_empty()
+            || !self.projections.is_empty()
+    }
+}
+
+pub(crate) fn track_wallpaper_damage_system(
+    mode: Res<WallpaperRedrawMode>,
+    mut force: ResMut<WallpaperForceRedraw>,
+    mut damage: ResMut<WallpaperDamage>,
+    mut changes: SceneChanges,
+) {
+    // Always drain the change readers so stale events don't leak into the
+    // next frame after switching modes.
+    let changed = changes.any();
+    damage.dirty = *mode == WallpaperRedrawMode::Always || std::mem::take(&mut force.0) || changed;
+}
+
+/// Drops damage for frames that would exceed [`WallpaperFrameRateLimit`].
+pub(crate) fn limit_frame_rate_system(
+    limit: Res<WallpaperFrameRateLimit>,
+    time: Res<Time<Real>>,
+    mut last_present: Local<Option<Duration>>,
+    mut damage: ResMut<WallpaperDamage>,
+) {
+    let Some(fps) = limit.0.filter(|fps| *fps > 0) else {
+        return;
+    };
+    if !damage.dirty {
+        return;
+    }
+
+    let now = time.elapsed();
+    let interval = Duration::from_secs_f64(1.0 / f64::from(fps));
+    if last_present.is_some_and(|last| now.saturating_sub(last) < interval) {
+        damage.dirty = false;
+    } else {
+        *last_present = Some(now);
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::HashSet,
+    sync::{Mutex, mpsc},
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+
+/// Present while the screen is locked or the system is about to sleep.
+///
+/// Inserted and removed by [`WallpaperScreenLockPlugin`]. While it exists the
+/// Wayland and X11 backends stop presenting and `Update` runs at the plugin's
+/// `paused_fps` on every backend.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperPaused;
+
+/// Pauses the wallpaper while the screen is locked.
+///
+/// Opt-in; add it next to [`crate::LiveWallpaperPlugin`]. On Linux it needs
+/// the `dbus` feature and listens for `PrepareForSleep` from
+/// `org.freedesktop.login1` and `ActiveChanged` from
+/// `org.freedesktop.ScreenSaver`; on Windows it uses session lock
+/// notifications. Elsewhere it does nothing.
+pub struct WallpaperScreenLockPlugin {
+    /// `Update` runs per second while paused; `0.0` keeps the normal rate.
+    pub paused_fps: f32,
+}
+
+impl Default for WallpaperScreenLockPlugin {
+    fn default() -> Self {
+        Self { paused_fps: 2.0 }
+    }
+}
+
+impl Plugin for WallpaperScreenLockPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        spawn_lock_watchers(sender);
+
+        app.insert_resource(ScreenLockSignals(Mutex::new(receiver)))
+            .insert_resource(PausedUpdateRate(self.paused_fps))
+            .add_systems(First, apply_screen_lock_system)
+            .add_systems(
+                Last,
+                throttle_paused_updates_system.run_if(resource_exists::<WallpaperPaused>),
+            );
+    }
+}
+
+/// A lock source, named after the notification it comes from, turned on or
+/// off.
+pub(crate) type LockSignal = (&'static str, bool);
+
+#[derive(Resource)]
+struct ScreenLockSignals(Mutex<mpsc::Receiver<LockSignal>>);
+
+#[derive(Resource, Clone, Copy)]
+struct PausedUpdateRate(f32);
+
+#[cfg(all(target_os = "linux", feature = "dbus"))]
+fn spawn_lock_watchers(sender: mpsc::Sender<LockSignal>) {
+    dbus::spawn_watcher(sender.clone(), dbus::PREPARE_FOR_SLEEP);
+    dbus::spawn_watcher(sender, dbus::SCREEN_SAVER_ACTIVE);
+}
+
+#[cfg(target_os = "windows")]
+fn spawn_lock_watchers(sender: mpsc::Sender<LockSignal>) {
+    crate::windows_backend::spawn_session_lock_watcher(sender);
+}
+
+#[cfg(not(any(all(target_os = "linux", feature = "dbus"), target_os = "windows")))]
+fn spawn_lock_watchers(_sender: mpsc::Sender<LockSignal>) {
+    warn!(
+        "WallpaperScreenLockPlugin needs the 'dbus' feature on Linux and is unsupported on this platform"
+    );
+}
+
+/// Inserts [`WallpaperPaused`] while any lock source is active.
+fn apply_screen_lock_system(
+    mut commands: Commands,
+    signals: Res<ScreenLockSignals>,
+    paused: Option<Res<WallpaperPaused>>,
+    mut active: Local<HashSet<&'static str>>,
+) {
+    let Ok(receiver) = signals.0.lock() else {
+        return;
+    };
+    for (source, on) in receiver.try_iter() {
+        if on {
+            active.insert(source);
+        } else {
+            active.remove(source);
+        }
+    }
+
+    match (active.is_empty(), paused.is_some()) {
+        (false, false) => {
+            debug!("Screen locked; pausing the wallpaper");
+            commands.insert_resource(WallpaperPaused);
+        }
+        (true, true) => {
+            debug!("Screen unlocked; resuming the wallpaper");
+            commands.remove_resource::<WallpaperPaused>();
+        }
+        _ => {}
+    }
+}
+
+/// Sleeps at the end of the frame so `Update` runs at most `paused_fps` times
+/// per second while paused.
+fn throttle_paused_updates_system(
+    rate: Res<PausedUpdateRate>,
+    mut last_update: Local<Option<Instant>>,
+) {
+    if rate.0 <= 0.0 {
+        return;
+    }
+    let interval = Duration::from_secs_f32(1.0 / rate.0);
+    if let Some(remaining) = last_update.and_then(|last| interval.checked_sub(last.elapsed())) {
+        std::thread::sleep(remaining);
+    }
+    *last_update = Some(Instant::now());
+}
+
+#[cfg(all(target_os = "linux", feature = "dbus"))]
+mod dbus {
+    use std::sync::mpsc;
+
+    use bevy::prelude::*;
+    use zbus::blocking::{Connection, Proxy};
+
+    use super::LockSignal;
+
+    /// A boolean D-Bus signal that reports a lock source.
+    pub(super) struct SignalWatch {
+        system_bus: bool,
+        destination: &'static str,
+        path: &'static str,
+        interface: &'static str,
+        member: &'static str,
+    }
+
+    pub(super) const PREPARE_FOR_SLEEP: SignalWatch = SignalWatch {
+        system_bus: true,
+        destination: "org.freedesktop.login1",
+        path: "/org/freedesktop/login1",
+        interface: "org.freedesktop.login1.Manager",
+        member: "PrepareForSleep",
+    };
+
+    pub(super) const SCREEN_SAVER_ACTIVE: SignalWatch = SignalWatch {
+        system_bus: false,
+        destination: "org.freedesktop.ScreenSaver",
+        path: "/org/freedesktop/ScreenSaver",
+        interface: "org.freedesktop.ScreenSaver",
+        member: "ActiveChanged",
+    };
+
+    pub(super) fn spawn_watcher(sender: mpsc::Sender<LockSignal>, watch: SignalWatch) {
+        let spawned = std::thread::Builder::new()
+            .name(format!("wallpaper-dbus-{}", watch.member))
+            .spawn(move || {
+                if let Err(err) = watch_signal(&sender, &watch) {
+                    warn!("Not watching {}.{}: {err}", watch.interface, watch.member);
+                }
+            });
+        if let Err(err) = spawned {
+            warn!("Failed to spawn the D-Bus watcher thread: {err}");
+        }
+    }
+
+    /// Forwards every signal until the app drops the receiver.
+    fn watch_signal(sender: &mpsc::Sender<LockSignal>, watch: &SignalWatch) -> zbus::Result<()> {
+        let connection = if watch.system_bus {
+            Connection::system()?
+        } else {
+            Connection::session()?
+        };
+        let proxy = Proxy::new(&connection, watch.destination, watch.path, watch.interface)?;
+        for message in proxy.receive_signal(watch.member)? {
+            let active: bool = message.body().deserialize()?;
+            if sender.send((watch.member, active)).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+/// Latest state of a drawing tablet tool (pen, eraser, ...).
+///
+/// Fed by `zwp_tablet_manager_v2` on Wayland. Other backends leave it at the
+/// default value.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperStylusState {
+    /// Global logical position, `None` while no tool is over a wallpaper surface
+    /// and until its first motion after coming into proximity.
+    pub position: Option<Vec2>,
+    /// Normalized tip pressure in `0.0..=1.0`.
+    pub pressure: f32,
+    /// Tilt in degrees along the X and Y axes.
+    pub tilt: Vec2,
+    /// Whether a tool is in proximity of the tablet over a wallpaper surface.
+    pub in_proximity: bool,
+    /// Whether the tip is touching the tablet. Also reported as
+    /// [`MouseButton::Left`] in [`crate::PointerSample::pressed`].
+    pub down: bool,
+    /// Kind of the tool in proximity, if the compositor reported it.
+    pub tool: Option<StylusTool>,
+    /// Tool buttons currently held (Linux input event codes, e.g. `BTN_STYLUS`).
+    pub buttons: HashSet<u32>,
+}
+
+/// A single tablet tool action, in the order it happened within the frame.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct WallpaperStylusEvent {
+    /// Backend-specific output identifier, as in [`crate::PointerSample::output`].
+    pub output: Option<u32>,
+    /// Global logical position at the time of the event, `None` until the
+    /// tool's first [`WallpaperStylusPhase::Motion`] after proximity in.
+    pub position: Option<Vec2>,
+    pub phase: WallpaperStylusPhase,
+}
+
+/// Physical tool type, as reported by the tablet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum StylusTool {
+    Pen,
+    Eraser,
+    Brush,
+    Pencil,
+    Airbrush,
+    Finger,
+    Mouse,
+    Lens,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Reflect)]
+pub enum WallpaperStylusPhase {
+    /// The tool came into proximity over a wallpaper surface.
+    ProximityIn,
+    /// The tool left proximity (or the surface).
+    ProximityOut,
+    /// The tool moved.
+    Motion,
+    /// The tip touched the tablet.
+    Down,
+    /// The tip was lifted.
+    Up,
+    /// A tool button changed state.
+    Button { button: u32, pressed: bool },
+    /// End of a group of events that happened at the same time; axis values
+    /// in [`WallpaperStylusState`] are consistent at this point.
+    Frame,
+}
@@ -0,0 +1,198 @@
+#[cfg(any(feature = "wayland", feature = "x11"))]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "wayland")]
+use crate::WallpaperAllowTearing;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+use crate::{WallpaperFrameLatency, WallpaperPresentMode};
+#[cfg(any(feature = "wayland", feature = "x11"))]
+use bevy::ecs::system::SystemParam;
+use bevy::{
+    prelude::*,
+    render::{extract_resource::ExtractResource, render_resource::TextureFormat},
+};
+
+/// Color precision of the wallpaper surfaces (Wayland/X11).
+///
+/// The format actually used is reported in [`crate::WallpaperSurfaceInfo::format`].
+/// `Rgba16Float` is a linear format, so pair it with an HDR camera and pick
+/// tonemapping accordingly. The Windows backend presents through Bevy's own
+/// window surface and ignores this.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperSurfaceFormat {
+    /// 8-bit sRGB (`Bgra8UnormSrgb`).
+    #[default]
+    Srgb8,
+    /// Half-float `Rgba16Float`; falls back to 8-bit with a warning if the
+    /// surface doesn't offer it.
+    Hdr16Float,
+    /// `Rgba16Float` when the surface offers it, 8-bit sRGB otherwise.
+    ///
+    /// There's no portable way to query a monitor's HDR capability yet, so
+    /// the surface advertising a float format is taken as the signal.
+    Auto,
+}
+
+impl WallpaperSurfaceFormat {
+    #[cfg(any(feature = "wayland", feature = "x11"))]
+    fn candidates(self) -> &'static [TextureFormat] {
+        match self {
+            Self::Srgb8 => &[],
+            Self::Hdr16Float | Self::Auto => &[TextureFormat::Rgba16Float],
+        }
+    }
+}
+
+/// (Wayland only) Color space the wallpaper surfaces are tagged with through
+/// the `wp_color_manager_v1` color management protocol, so HDR compositors
+/// don't assume sRGB.
+///
+/// The surface format follows it (`Rgb10a2Unorm` for Display P3,
+/// `Rgba16Float` for PQ) unless [`WallpaperPreferredFormats`] says otherwise.
+/// The scene has to be rendered in the chosen encoding; Bevy's own output is
+/// sRGB. Compositors without the protocol treat the surfaces as sRGB. Changing
+/// it at runtime recreates the wallpaper surfaces.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum WallpaperColorSpace {
+    #[default]
+    Srgb,
+    /// Display P3 primaries with the sRGB transfer function.
+    DisplayP3,
+    /// BT.2020 primaries with the SMPTE ST 2084 (PQ) transfer function.
+    Rec2020Pq,
+}
+
+impl WallpaperColorSpace {
+    #[cfg(feature = "wayland")]
+    fn candidates(self) -> &'static [TextureFormat] {
+        match self {
+            Self::Srgb => &[],
+            Self::DisplayP3 => &[TextureFormat::Rgb10a2Unorm, TextureFormat::Rgba16Float],
+            Self::Rec2020Pq => &[TextureFormat::Rgba16Float, TextureFormat::Rgb10a2Unorm],
+        }
+    }
+}
+
+/// Surface formats to try, in order, before the default `Bgra8UnormSrgb`.
+///
+/// Higher precision formats such as `Rgb10a2Unorm` or `Rgba16Float` reduce
+/// banding in gradients. Formats the compositor doesn't offer are skipped; the
+/// one actually used is reported in [`crate::WallpaperSurfaceInfo::format`].
+/// Non-sRGB formats receive linear values, so adjust tonemapping accordingly.
+///
+/// These take precedence over [`WallpaperSurfaceFormat`]. Changing either at
+/// runtime recreates the wallpaper surfaces.
+#[derive(Resource, ExtractResource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperPreferredFormats(pub Vec<TextureFormat>);
+
+/// Format picked by the render world, shared with the main world so the render
+/// target image can be switched to match.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+#[derive(Resource, Clone, Default)]
+pub(crate) struct NegotiatedSurfaceFormat(Arc<Mutex<Option<TextureFormat>>>);
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+impl NegotiatedSurfaceFormat {
+    pub(crate) fn get(&self) -> Option<TextureFormat> {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub(crate) fn set(&self, format: Option<TextureFormat>) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = format;
+    }
+}
+
+/// Render world view of the user's surface preferences.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+#[derive(SystemParam)]
+pub(crate) struct SurfacePreferences<'w> {
+    preferred_formats: Option<Res<'w, WallpaperPreferredFormats>>,
+    surface_format: Option<Res<'w, WallpaperSurfaceFormat>>,
+    present_mode: Option<Res<'w, WallpaperPresentMode>>,
+    frame_latency: Option<Res<'w, WallpaperFrameLatency>>,
+    #[cfg(feature = "wayland")]
+    allow_tearing: Option<Res<'w, WallpaperAllowTearing>>,
+    #[cfg(feature = "wayland")]
+    color_space: Option<Res<'w, WallpaperColorSpace>>,
+    negotiated: Res<'w, NegotiatedSurfaceFormat>,
+}
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+impl SurfacePreferences<'_> {
+    /// Picks the surface format: the one already negotiated (so every surface
+    /// shares the render target's format), then the user's preferences and
+    /// color space, then `fallback`, then whatever the surface offers first.
+    pub(crate) fn choose_format(
+        &self,
+        supported: &[TextureFormat],
+        fallback: TextureFormat,
+    ) -> Option<TextureFormat> {
+        let negotiated = self.negotiated.get();
+        let preferred = self
+            .preferred_formats
+            .as_deref()
+            .map(|formats| formats.0.as_slice())
+            .unwrap_or_default();
+        let mode = self.surface_format.as_deref().copied().unwrap_or_default();
+        #[cfg(feature = "wayland")]
+        let color_space = self
+            .color_space
+            .as_deref()
+            .copied()
+            .unwrap_or_default()
+            .candidates();
+        #[cfg(not(feature = "wayland"))]
+        let color_space: &[TextureFormat] = &[];
+
+        let format = negotiated
+            .into_iter()
+            .chain(preferred.iter().copied())
+            .chain(color_space.iter().copied())
+            .chain(mode.candidates().iter().copied())
+            .chain(std::iter::once(fallback))
+            .find(|format| supported.contains(format))
+            .or_else(|| supported.first().copied())?;
+
+        if negotiated.is_none() {
+            if mode == WallpaperSurfaceFormat::Hdr16Float && format != TextureFormat::Rgba16Float {
+                warn!("Rgba16Float is not supported by the wallpaper surface; using {format:?}");
+            }
+            debug!("Negotiated wallpaper surface format {:?}", format);
+            self.negotiated.set(Some(format));
+        }
+        Some(format)
+    }
+
+    /// See [`WallpaperPresentMode::select`].
+    pub(crate) fn present_mode(
+        &self,
+        supported: &[wgpu::PresentMode],
+        auto: &[wgpu::PresentMode],
+    ) -> wgpu::PresentMode {
+        let mode = self.present_mode.as_deref().copied().unwrap_or_default();
+        mode.select(supported, auto)
+    }
+
+    /// See [`WallpaperFrameLatency`].
+    pub(crate) fn frame_latency(&self) -> u32 {
+        self.frame_latency
+            .as_deref()
+            .copied()
+            .unwrap_or_default()
+            .0
+            .max(1)
+    }
+
+    /// Whether [`WallpaperAllowTearing`] is set.
+    #[cfg(feature = "wayland")]
+    pub(crate) fn allow_tearing(&self) -> bool {
+        self.allow_tearing.as_deref().is_some_and(|allow| allow.0)
+    }
+}
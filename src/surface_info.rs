@@ -1,16 +1,50 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, render::render_resource::TextureFormat};
+
+use crate::{WallpaperBackend, WallpaperDisplayMode};
 
 /// Combined wallpaper surface extents in logical coordinates.
 ///
 /// On Wayland, this is derived from layer-surface configure events and output
 /// logical positions (xdg-output / wl_output). On other platforms it currently
 /// stays at the default value unless implemented.
-#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
 pub struct WallpaperSurfaceInfo {
     /// Logical top-left of the wallpaper area (e.g., min x/y across outputs).
     pub offset_position: Vec2,
     /// Logical width/height of the wallpaper area.
     pub size: Vec2,
+    /// Format negotiated for the wallpaper surfaces (and used by the render
+    /// target image). Tracked on Wayland and X11; elsewhere it stays at
+    /// `Bgra8UnormSrgb`.
+    pub format: TextureFormat,
+    /// Physical pixels per logical pixel: the largest integer output scale
+    /// on Wayland (shared by all outputs), 1 on X11, the window scale factor
+    /// in windowed mode. `None` on Windows and macOS. Halved under memory
+    /// pressure, see [`crate::WallpaperMemoryPolicy`].
+    pub scale_factor: Option<f32>,
+}
+
+impl Default for WallpaperSurfaceInfo {
+    fn default() -> Self {
+        Self {
+            offset_position: Vec2::ZERO,
+            size: Vec2::ZERO,
+            format: TextureFormat::Bgra8UnormSrgb,
+            scale_factor: None,
+        }
+    }
+}
+
+/// Sent when [`WallpaperSurfaceInfo`] changes, e.g. after a monitor was
+/// plugged in or the window was resized.
+#[derive(Message, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct WallpaperSurfaceChanged {
+    pub old: WallpaperSurfaceInfo,
+    pub new: WallpaperSurfaceInfo,
+    /// Backend that reported the change; [`WallpaperBackend::Windowed`] for
+    /// window resizes.
+    pub backend: WallpaperBackend,
 }
 
 impl WallpaperSurfaceInfo {
@@ -18,4 +52,56 @@ impl WallpaperSurfaceInfo {
         self.offset_position = Vec2::new(offset_x as f32, offset_y as f32);
         self.size = Vec2::new(width as f32, height as f32);
     }
+
+    /// Width divided by height; `0.0` before the size is known.
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.size.y > 0.0 {
+            self.size.x / self.size.y
+        } else {
+            0.0
+        }
+    }
+
+    /// Logical center of the wallpaper area.
+    pub fn center(&self) -> Vec2 {
+        self.offset_position + self.size / 2.0
+    }
+
+    /// Logical bounds of the wallpaper area.
+    pub fn rect(&self) -> Rect {
+        Rect::from_corners(self.offset_position, self.offset_position + self.size)
+    }
+
+    /// Logical pixels per physical pixel, the inverse of
+    /// [`Self::scale_factor`].
+    pub fn pixel_density(&self) -> Option<f32> {
+        self.scale_factor
+            .filter(|scale| *scale > 0.0)
+            .map(|scale| scale.recip())
+    }
+}
+
+/// Writes [`WallpaperSurfaceChanged`] when the backends updated
+/// [`WallpaperSurfaceInfo`] to a different value.
+pub(crate) fn detect_surface_changes_system(
+    info: Res<WallpaperSurfaceInfo>,
+    mode: Res<WallpaperDisplayMode>,
+    backend: Option<Res<WallpaperBackend>>,
+    mut last: Local<Option<WallpaperSurfaceInfo>>,
+    mut changes: MessageWriter<WallpaperSurfaceChanged>,
+) {
+    let old = last.unwrap_or_default();
+    if *info == old {
+        return;
+    }
+    *last = Some(*info);
+    let backend = match (*mode, backend) {
+        (WallpaperDisplayMode::Wallpaper, Some(backend)) => *backend,
+        _ => WallpaperBackend::Windowed,
+    };
+    changes.write(WallpaperSurfaceChanged {
+        old,
+        new: *info,
+        backend,
+    });
 }
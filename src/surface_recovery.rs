@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+#[cfg(any(feature = "wayland", feature = "x11"))]
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+
+/// Frames skipped before retrying a surface that failed again right after
+/// being recreated, doubled per consecutive failure up to this cap.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+const MAX_BACKOFF_FRAMES: u32 = 120;
+
+/// Lost/outdated wgpu surfaces per output, to tell when a surface is flapping
+/// (e.g. a driver returning `Lost` every frame).
+///
+/// Filled by the Wayland backend (keyed like [`crate::PointerSample::output`])
+/// and the X11 backend (keyed by window id). Repeated failures back off
+/// exponentially instead of recreating the surface every frame. Other backends
+/// leave it empty.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperSurfaceRecovery {
+    pub surfaces: HashMap<u32, SurfaceRecoveryInfo>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub struct SurfaceRecoveryInfo {
+    /// Failures since the last successful present.
+    pub consecutive_failures: u32,
+    /// Failures since the surface was first created.
+    pub total_failures: u32,
+    /// Frames left before the surface is recreated.
+    pub backoff_frames: u32,
+}
+
+/// Per-surface backoff kept next to the wgpu surface in the render world.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct SurfaceBackoff(SurfaceRecoveryInfo);
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+impl SurfaceBackoff {
+    /// Records a lost/outdated surface. The first failure is retried on the
+    /// next frame; later ones wait 1, 2, 4, ... frames.
+    pub(crate) fn record_failure(&mut self) {
+        let info = &mut self.0;
+        info.backoff_frames = match info.consecutive_failures {
+            0 => 0,
+            n => 1u32
+                .checked_shl(n - 1)
+                .unwrap_or(u32::MAX)
+                .min(MAX_BACKOFF_FRAMES),
+        };
+        info.consecutive_failures += 1;
+        info.total_failures += 1;
+    }
+
+    /// Whether recreation should still be skipped this frame; counts the
+    /// frame down.
+    pub(crate) fn waiting(&mut self) -> bool {
+        if self.0.backoff_frames == 0 {
+            return false;
+        }
+        self.0.backoff_frames -= 1;
+        true
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.0.consecutive_failures = 0;
+        self.0.backoff_frames = 0;
+    }
+
+    pub(crate) fn info(&self) -> SurfaceRecoveryInfo {
+        self.0
+    }
+}
+
+/// Render-world counters shared with the main world.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+#[derive(Resource, Clone, Default)]
+pub(crate) struct SharedSurfaceRecovery(Arc<Mutex<HashMap<u32, SurfaceRecoveryInfo>>>);
+
+#[cfg(any(feature = "wayland", feature = "x11"))]
+impl SharedSurfaceRecovery {
+    pub(crate) fn publish(&self, surfaces: impl IntoIterator<Item = (u32, SurfaceRecoveryInfo)>) {
+        *self.0.lock().unwrap_or_else(|err| err.into_inner()) = surfaces.into_iter().collect();
+    }
+}
+
+/// Mirrors the render world's counters into [`WallpaperSurfaceRecovery`].
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(crate) fn sync_surface_recovery(
+    shared: Res<SharedSurfaceRecovery>,
+    mut recovery: ResMut<WallpaperSurfaceRecovery>,
+) {
+    let surfaces = shared
+        .0
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    recovery.set_if_neq(WallpaperSurfaceRecovery { surfaces });
+}
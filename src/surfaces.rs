@@ -0,0 +1,74 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+#[cfg(feature = "wayland")]
+use crate::WaylandSurfaceHandles;
+#[cfg(feature = "x11")]
+use crate::X11SurfaceHandles;
+
+/// Raw handles of one wallpaper surface, tagged with the backend that owns it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WallpaperSurfaceHandles {
+    #[cfg(feature = "wayland")]
+    Wayland(WaylandSurfaceHandles),
+    #[cfg(feature = "x11")]
+    X11(X11SurfaceHandles),
+}
+
+/// A live wallpaper surface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WallpaperSurface {
+    pub handles: WallpaperSurfaceHandles,
+    /// Logical size of the surface.
+    pub size: UVec2,
+    /// Integer output scale on Wayland (1.0 until the compositor reports one);
+    /// always 1.0 on X11.
+    pub scale: f32,
+    /// Refresh rate of the output's current mode, when the backend knows it.
+    pub refresh_hz: Option<f64>,
+    /// Physical size of the monitor in millimetres, when it reports one
+    /// (projectors often report none).
+    pub physical_size_mm: Option<UVec2>,
+    /// Dots per inch along the diagonal, from the monitor's resolution in
+    /// physical pixels and [`Self::physical_size_mm`].
+    pub dpi: Option<f32>,
+    /// Whether the surface is on the primary monitor: the RandR primary on
+    /// X11, the output [`crate::WallpaperTargetMonitor::Primary`] picks on
+    /// Wayland (see [`crate::WaylandPrimaryOutput`]).
+    pub primary: bool,
+    /// Connector name of the monitor, e.g. `DP-1`, as matched by
+    /// [`crate::WallpaperTargetMonitor::Name`].
+    pub name: Option<String>,
+}
+
+/// DPI of a monitor `pixels` large with a `size_mm` panel; `None` for an
+/// unknown (zero) size.
+#[cfg(any(feature = "wayland", feature = "x11"))]
+pub(crate) fn monitor_dpi(pixels: UVec2, size_mm: Option<UVec2>) -> Option<f32> {
+    let size_mm = size_mm?.as_vec2();
+    let diagonal_mm = size_mm.length();
+    (size_mm.min_element() > 0.0).then(|| pixels.as_vec2().length() * 25.4 / diagonal_mm)
+}
+
+/// Handles of the surfaces the wallpaper currently presents to.
+///
+/// Keys are the Wayland `wl_output` global name (as in
+/// [`crate::PointerSample::output`]) or the X11 window id. The map is rebuilt
+/// whenever the backend reconfigures its surfaces, so re-read it on change
+/// rather than caching handles; they're invalid once removed from here. The
+/// Windows backend leaves it empty.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct WallpaperSurfaces {
+    pub surfaces: BTreeMap<u32, WallpaperSurface>,
+}
+
+impl WallpaperSurfaces {
+    pub fn get(&self, id: u32) -> Option<&WallpaperSurface> {
+        self.surfaces.get(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &WallpaperSurface)> {
+        self.surfaces.iter().map(|(id, surface)| (*id, surface))
+    }
+}
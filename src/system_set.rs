@@ -0,0 +1,19 @@
+use bevy::prelude::*;
+
+/// Stages of the wallpaper backends in `PostUpdate`, run in this order.
+///
+/// Order systems against these instead of the backend systems themselves,
+/// e.g. `.after(WallpaperSystemSet::BackendEvents)` to read an up to date
+/// [`crate::WallpaperSurfaceInfo`] and [`crate::WallpaperPointerState`]. The
+/// Windows and macOS backends update both in `Update` and don't use these
+/// sets.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WallpaperSystemSet {
+    /// Compositor / X server events are applied: surfaces, surface info and
+    /// input state.
+    BackendEvents,
+    /// The render target image is resized to the wallpaper surfaces.
+    SyncRenderTarget,
+    /// Wallpaper cameras are pointed at the render target.
+    AssignCamera,
+}
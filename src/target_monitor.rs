@@ -1,13 +1,42 @@
-use bevy::prelude::Resource;
+use bevy::prelude::{Reflect, ReflectResource, Resource};
 
 /// Selects which monitor(s) should display the wallpaper.
-#[derive(Default, Clone, Copy, Debug, Resource)]
+///
+/// Inserted as a resource by the plugin. Every backend reacts to changes at
+/// runtime: Wayland replaces its layer surfaces, X11 moves and resizes its
+/// window, and Windows repositions the attached window.
+#[derive(Default, Clone, Debug, PartialEq, Eq, Resource, Reflect)]
+#[reflect(Resource)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum WallpaperTargetMonitor {
-    /// Uses the primary monitor of the system.
+    /// Uses the primary monitor of the system; on Wayland, which has none, see
+    /// [`crate::WaylandPrimaryOutput`].
     #[default]
     Primary,
     /// Uses the monitor with the specified index.
     Index(usize),
     /// Uses all monitors as one large logical desktop.
     All,
+    /// Uses the monitor with this connector name (e.g. `DP-1`) or, on
+    /// Wayland, this xdg-output description (e.g. `Dell Inc. DELL U2722D`).
+    /// Matching is exact; nothing is shown until such a monitor exists. On
+    /// Windows it matches the device name (e.g. `\\.\DISPLAY1`), the
+    /// monitor's friendly name or the name Bevy reports, and on X11 the RandR
+    /// output name; macOS doesn't match names.
+    Name(String),
+}
+
+impl WallpaperTargetMonitor {
+    /// Whether a monitor called `name` (and described as `description`)
+    /// matches [`Self::Name`].
+    #[cfg(any(feature = "wayland", feature = "x11", target_os = "windows"))]
+    pub(crate) fn matches_name(&self, name: Option<&str>, description: Option<&str>) -> bool {
+        match self {
+            Self::Name(wanted) => {
+                name == Some(wanted.as_str()) || description == Some(wanted.as_str())
+            }
+            _ => false,
+        }
+    }
 }
@@ -0,0 +1,25 @@
+use std::collections::HashSet;
+
+use bevy::{prelude::*, render::extract_resource::ExtractResource};
+
+/// (Wayland only) Lets the compositor tear to cut presentation latency.
+///
+/// When set, each wallpaper surface gets the `async` hint through
+/// `wp_tearing_control_v1` and `Immediate` is preferred when
+/// [`crate::WallpaperPresentMode`] is `Auto`. Compositors without the protocol
+/// and other backends keep presenting as usual. Changing it at runtime
+/// reconfigures the surfaces.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperAllowTearing(pub bool);
+
+/// Outputs (as in [`crate::PointerSample::output`]) whose surface currently
+/// carries the async presentation hint.
+///
+/// The hint is only a request: the compositor may still sync to vblank, e.g.
+/// when the output doesn't support async page flips.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperTearingState {
+    pub granted: HashSet<u32>,
+}
@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use bevy::{prelude::*, render::extract_resource::ExtractResource};
+
+/// Which outputs can currently show the wallpaper.
+///
+/// On Wayland, outputs are reported as powered off through
+/// `zwlr_output_power_manager_v1`; presenting to them is skipped until they
+/// wake up, at which point one redraw is forced so no stale frame is shown.
+/// Wallpaper surfaces are also tracked through `wl_surface` enter/leave: a
+/// surface that left every output (e.g. its output was disabled but not
+/// removed) is occluded. Compositors without these signals and other backends
+/// leave the sets empty, i.e. [`OutputVisibility::Unknown`].
+#[derive(Resource, ExtractResource, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperVisibility {
+    /// Backend-specific output identifiers (as in
+    /// [`crate::PointerSample::output`]) whose display is powered off.
+    pub powered_off: HashSet<u32>,
+    /// Outputs whose wallpaper surface is known to be on screen.
+    pub visible: HashSet<u32>,
+    /// Outputs whose wallpaper surface is known not to be on screen.
+    pub occluded: HashSet<u32>,
+    /// Also skip presenting to occluded outputs, not just powered off ones.
+    /// Set from [`crate::LiveWallpaperPlugin::skip_occluded_outputs`].
+    pub skip_occluded: bool,
+}
+
+/// Visibility of one output's wallpaper surface.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum OutputVisibility {
+    Visible,
+    /// Powered off, or the surface isn't on any output.
+    Occluded,
+    /// The backend has no information about the output.
+    #[default]
+    Unknown,
+}
+
+impl WallpaperVisibility {
+    /// Whether `output` is powered on (or its power state is unknown).
+    pub fn is_output_visible(&self, output: u32) -> bool {
+        !self.powered_off.contains(&output)
+    }
+
+    pub fn output_visibility(&self, output: u32) -> OutputVisibility {
+        if self.powered_off.contains(&output) || self.occluded.contains(&output) {
+            OutputVisibility::Occluded
+        } else if self.visible.contains(&output) {
+            OutputVisibility::Visible
+        } else {
+            OutputVisibility::Unknown
+        }
+    }
+
+    /// Whether frames for `output` should be presented at all.
+    pub fn should_present(&self, output: u32) -> bool {
+        self.is_output_visible(output) && !(self.skip_occluded && self.occluded.contains(&output))
+    }
+}
@@ -1,61 +1,187 @@
-use std::collections::HashSet;
-use std::io::ErrorKind;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use bevy::{
-    camera::RenderTarget,
+    camera::{RenderTarget, Viewport},
+    ecs::system::SystemParam,
     prelude::*,
     render::{
         Render, RenderApp, RenderSystems, extract_resource::ExtractResourcePlugin,
         render_resource::Extent3d,
     },
+    window::PrimaryWindow,
 };
 use wayland_client::{Connection, EventQueue, Proxy, QueueHandle};
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
 use crate::{
-    LiveWallpaperCamera, PointerButton, PointerSample, WallpaperPointerState, WallpaperSurfaceInfo,
-    WallpaperTargetMonitor,
+    LiveWallpaperCamera, PointerButton, PointerSample, WallpaperAllowTearing, WallpaperAlphaMode,
+    WallpaperBackend, WallpaperBackendClosed, WallpaperBackendStatus, WallpaperColorSpace,
+    WallpaperContentType, WallpaperCursor, WallpaperDisplayMode, WallpaperFifoPacing,
+    WallpaperFocusEvent, WallpaperForceRedraw, WallpaperFrameLatency, WallpaperFrameStats,
+    WallpaperGestureEvent, WallpaperIdlePolicy, WallpaperIdleState, WallpaperKeyboardFocus,
+    WallpaperLockEvent, WallpaperLockScreen, WallpaperMemoryBudget, WallpaperMonitorConnected,
+    WallpaperPointerConstraint, WallpaperPointerEvent, WallpaperPointerEventKind,
+    WallpaperPointerState, WallpaperPreferredFormats, WallpaperPresentMode, WallpaperScrollEvent,
+    WallpaperStylusEvent, WallpaperStylusPhase, WallpaperStylusState, WallpaperSurface,
+    WallpaperSurfaceFormat, WallpaperSurfaceHandles, WallpaperSurfaceInfo, WallpaperSurfaces,
+    WallpaperSystemSet, WallpaperTargetMonitor, WallpaperTearingState, WallpaperVisibility,
+    WallpaperWaylandDiagnostics, WaylandExclusiveZone, WaylandFallback, WaylandGlobalInfo,
+    WaylandKeyboardInteractivity, WaylandLayer, WaylandMargins, WaylandOutputDiagnostics,
+    WaylandOutputOrder, WaylandPrimaryOutput,
+    idle::CompositorIdleNotify,
+    monitor_events::MonitorMessages,
+    plugin::wallpaper_mode_active,
+    surface_format::NegotiatedSurfaceFormat,
+    surface_recovery::{SharedSurfaceRecovery, sync_surface_recovery},
+    surfaces::monitor_dpi,
 };
 
 use super::{
-    PendingPointerEvent, WaylandAppState,
+    OutputInfo, PendingPointerEvent, PendingPointerEventKind, PendingStylusEvent,
+    PendingStylusEventKind, SurfaceSwitch, WaylandAppState,
+    dispatch::{WaylandConnection, WaylandConnectionState, roundtrip_with_timeout},
+    frame_pacing::FramePacing,
     render::{
-        WaylandGpuSurfaceState, WaylandRenderTarget, WaylandSurfaceDescriptor,
-        create_wayland_image, prepare_wayland_surface, present_wayland_surface,
+        ConfiguredWaylandSurfaces, WaylandGpuSurfaceState, WaylandRenderTarget,
+        WaylandSurfaceDescriptor, create_wayland_image, prepare_wayland_surface,
+        present_wayland_surface, publish_configured_surfaces, to_physical,
     },
 };
 
-pub(crate) struct WaylandBackendPlugin;
+pub(crate) struct WaylandBackendPlugin {
+    pub fallback: WaylandFallback,
+    pub namespace: String,
+    pub placeholder_color: Option<Color>,
+    pub connect: WaylandConnectOptions,
+}
 
 impl Plugin for WaylandBackendPlugin {
     fn build(&self, app: &mut App) {
-        let conn = Connection::connect_to_env().unwrap();
-        let mut event_queue = conn.new_event_queue();
-        let qh = event_queue.handle();
-
-        let display = conn.display();
-        display.get_registry(&qh, ());
-
-        let mut app_state = WaylandAppState::new(display.clone());
-
-        info!("Waiting for globals...");
-        event_queue.roundtrip(&mut app_state).unwrap();
-        info!("Globals received.");
+        app.insert_resource(WallpaperBackend::Wayland);
+        let alpha_mode = app
+            .world()
+            .get_resource::<WallpaperAlphaMode>()
+            .copied()
+            .unwrap_or_default();
+        let connected = match connect_wayland(alpha_mode, &self.connect) {
+            Ok(connected) => Some(connected),
+            Err(err) if self.fallback != WaylandFallback::None => {
+                error!(
+                    "Failed to connect to the Wayland compositor: {err} (fallback: {:?})",
+                    self.fallback
+                );
+                apply_wayland_fallback(app, self.fallback);
+                return;
+            }
+            Err(err) => {
+                // The compositor may just not be up yet (e.g. started from a
+                // user service); keep retrying in the background.
+                error!("Failed to connect to the Wayland compositor: {err}; retrying");
+                app.insert_resource(WallpaperBackendStatus::Failed(err));
+                None
+            }
+        };
+        if connected
+            .as_ref()
+            .is_some_and(|connection| connection.lock().app_state.layer_shell.is_none())
+        {
+            error!(
+                "The Wayland compositor does not support zwlr_layer_shell_v1; \
+                 wallpaper surfaces cannot be created (fallback: {:?})",
+                self.fallback
+            );
+            apply_wayland_fallback(app, self.fallback);
+            return;
+        }
 
         // At startup, create surfaces for the currently requested target monitor if available.
         let initial_target = app
             .world()
             .get_resource::<WallpaperTargetMonitor>()
+            .cloned()
+            .unwrap_or_default();
+        let initial_mode = app
+            .world()
+            .get_resource::<WallpaperDisplayMode>()
+            .copied()
+            .unwrap_or_default();
+        let initial_order = app
+            .world()
+            .get_resource::<WaylandOutputOrder>()
+            .copied()
+            .unwrap_or_default();
+        let initial_layer = app
+            .world()
+            .get_resource::<WaylandLayer>()
+            .copied()
+            .unwrap_or_default();
+        let initial_keyboard = app
+            .world()
+            .get_resource::<WaylandKeyboardInteractivity>()
             .copied()
             .unwrap_or_default();
-        ensure_surfaces_for_outputs(&mut app_state, &qh, &initial_target);
-        info!("Initial commit done. Waiting for configure event...");
+        let initial_exclusive_zone = app
+            .world()
+            .get_resource::<WaylandExclusiveZone>()
+            .copied()
+            .unwrap_or_default();
+        let initial_margins = app
+            .world()
+            .get_resource::<WaylandMargins>()
+            .copied()
+            .unwrap_or_default();
+        let initial_primary = app
+            .world()
+            .get_resource::<WaylandPrimaryOutput>()
+            .cloned()
+            .unwrap_or_default();
+        let namespace = WaylandLayerNamespace(self.namespace.clone());
+        if let Some(connection) = connected.as_ref()
+            && initial_mode == WallpaperDisplayMode::Wallpaper
+        {
+            let mut state = connection.lock();
+            let WaylandConnectionState {
+                event_queue,
+                app_state,
+                ..
+            } = &mut *state;
+            app_state.primary_output = primary_output(
+                &ordered_outputs(app_state, initial_order),
+                &app_state.output_info,
+                initial_primary.0.as_deref(),
+            );
+            ensure_surfaces_for_outputs(
+                app_state,
+                &event_queue.handle(),
+                &initial_target,
+                initial_order,
+                LayerSurfaceOptions {
+                    layer: initial_layer,
+                    keyboard: initial_keyboard,
+                    exclusive_zone: initial_exclusive_zone.resolve(initial_layer),
+                    margins: initial_margins,
+                },
+                &namespace,
+            );
+            info!("Initial commit done. Waiting for configure event...");
+        }
 
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
         };
 
+        let negotiated_format = NegotiatedSurfaceFormat::default();
+        let configured_surfaces = ConfiguredWaylandSurfaces::default();
+        let surface_recovery = SharedSurfaceRecovery::default();
+        let frame_pacing = FramePacing::default();
         render_app
+            .insert_resource(negotiated_format.clone())
+            .insert_resource(frame_pacing.clone())
+            .insert_resource(configured_surfaces.clone())
+            .insert_resource(surface_recovery.clone())
             .init_resource::<WaylandGpuSurfaceState>()
             .add_systems(
                 Render,
@@ -63,12 +189,16 @@ impl Plugin for WaylandBackendPlugin {
             )
             .add_systems(
                 Render,
-                present_wayland_surface.in_set(RenderSystems::Cleanup),
+                (
+                    present_wayland_surface,
+                    publish_configured_surfaces.after(present_wayland_surface),
+                )
+                    .in_set(RenderSystems::Cleanup),
             );
 
         let target_image = {
             let mut images = app.world_mut().resource_mut::<Assets<Image>>();
-            create_wayland_image(&mut images)
+            create_wayland_image(&mut images, alpha_mode)
         };
 
         app.insert_resource(WaylandSurfaceDescriptor::new())
@@ -76,44 +206,383 @@ impl Plugin for WaylandBackendPlugin {
             .add_plugins((
                 ExtractResourcePlugin::<WaylandSurfaceDescriptor>::default(),
                 ExtractResourcePlugin::<WaylandRenderTarget>::default(),
+                ExtractResourcePlugin::<WallpaperAlphaMode>::default(),
+                ExtractResourcePlugin::<WallpaperVisibility>::default(),
+                ExtractResourcePlugin::<WallpaperPreferredFormats>::default(),
+                ExtractResourcePlugin::<WallpaperSurfaceFormat>::default(),
+                ExtractResourcePlugin::<WallpaperPresentMode>::default(),
+                ExtractResourcePlugin::<WallpaperFrameLatency>::default(),
+                ExtractResourcePlugin::<WallpaperAllowTearing>::default(),
+                ExtractResourcePlugin::<WallpaperColorSpace>::default(),
             ))
-            .add_systems(PostUpdate, wayland_event_system)
+            .insert_resource(negotiated_format)
+            .insert_resource(configured_surfaces)
+            .insert_resource(surface_recovery)
+            .insert_resource(frame_pacing)
+            .init_resource::<WallpaperWaylandDiagnostics>()
             .add_systems(
                 PostUpdate,
                 (
-                    sync_wayland_render_target_image.after(wayland_event_system),
-                    assign_wayland_camera_target.after(sync_wayland_render_target_image),
+                    reconfigure_surfaces
+                        .before(WallpaperSystemSet::BackendEvents)
+                        .run_if(
+                            resource_changed::<WallpaperPreferredFormats>
+                                .or_else(resource_changed::<WallpaperSurfaceFormat>)
+                                .or_else(resource_changed::<WallpaperPresentMode>)
+                                .or_else(resource_changed::<WallpaperFrameLatency>)
+                                .or_else(resource_changed::<WallpaperAllowTearing>)
+                                .or_else(resource_changed::<WallpaperColorSpace>),
+                        ),
+                    connect_wayland_system.before(WallpaperSystemSet::BackendEvents),
+                    sync_wayland_placeholders
+                        .after(connect_wayland_system)
+                        .before(WallpaperSystemSet::BackendEvents),
+                    wayland_event_system.in_set(WallpaperSystemSet::BackendEvents),
                 ),
             )
-            .insert_non_send(WaylandEventQueue(event_queue))
-            .insert_non_send(app_state);
+            .add_systems(
+                PostUpdate,
+                (
+                    sync_wayland_render_target_image.in_set(WallpaperSystemSet::SyncRenderTarget),
+                    assign_wayland_camera_target
+                        .in_set(WallpaperSystemSet::AssignCamera)
+                        .run_if(wallpaper_mode_active),
+                    auto_assign_output_viewports
+                        .in_set(WallpaperSystemSet::AssignCamera)
+                        .run_if(wallpaper_mode_active),
+                    sync_wallpaper_surfaces
+                        .after(WallpaperSystemSet::BackendEvents)
+                        .run_if(resource_changed::<WaylandSurfaceDescriptor>),
+                    update_wayland_diagnostics.after(WallpaperSystemSet::BackendEvents),
+                    write_monitor_messages.after(WallpaperSystemSet::BackendEvents),
+                    sync_surface_recovery,
+                ),
+            )
+            .init_resource::<WaylandReconnectState>()
+            .insert_resource(namespace)
+            .insert_resource(WaylandPlaceholderColor(self.placeholder_color))
+            .insert_resource(self.connect.clone());
+        if let Some(connection) = connected {
+            app.insert_resource(connection);
+        }
     }
 }
 
-#[derive(Resource, Deref, DerefMut)]
-struct WaylandEventQueue(EventQueue<WaylandAppState>);
+fn apply_wayland_fallback(app: &mut App, fallback: WaylandFallback) {
+    match fallback {
+        WaylandFallback::None => {
+            app.insert_resource(WallpaperBackendStatus::Unsupported);
+        }
+        WaylandFallback::X11 => {
+            #[cfg(feature = "x11")]
+            {
+                info!("Falling back to the X11 backend via XWayland.");
+                app.add_plugins(crate::x11::backend::X11BackendPlugin);
+            }
+            #[cfg(not(feature = "x11"))]
+            {
+                warn!("X11 fallback requested but the 'x11' feature is not enabled.");
+                app.insert_resource(WallpaperBackendStatus::Unsupported);
+            }
+        }
+        WaylandFallback::Windowed => {
+            info!("Falling back to windowed mode.");
+            app.insert_resource(WallpaperDisplayMode::Windowed);
+            let world = app.world_mut();
+            let has_primary = world
+                .query_filtered::<(), With<PrimaryWindow>>()
+                .iter(world)
+                .next()
+                .is_some();
+            if !has_primary {
+                world.spawn((Window::default(), PrimaryWindow));
+            }
+        }
+    }
+}
 
+/// Initial delay before retrying a lost compositor connection.
+const RECONNECT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Upper bound for the reconnect backoff.
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Layer-shell namespace requested through [`crate::LiveWallpaperPlugin`].
+#[derive(Resource, Clone, Debug)]
+struct WaylandLayerNamespace(String);
+
+impl WaylandLayerNamespace {
+    fn for_output(&self, output: u32) -> String {
+        if self.0.is_empty() {
+            format!("egl_background_{output}")
+        } else {
+            self.0.clone()
+        }
+    }
+}
+
+/// Color of the placeholder shown until the first frame is presented.
+#[derive(Resource, Clone, Copy, Debug)]
+struct WaylandPlaceholderColor(Option<Color>);
+
+/// Where every (re)connection attempt connects to.
+#[derive(Resource, Clone, Debug, Default)]
+pub(crate) struct WaylandConnectOptions {
+    /// Socket name or absolute path; `None` follows `WAYLAND_DISPLAY`.
+    pub socket: Option<String>,
+    /// Deadline for the initial roundtrip.
+    pub timeout: Option<Duration>,
+    /// Bind seats; see [`crate::LiveWallpaperPlugin::capture_input`].
+    pub capture_input: bool,
+}
+
+/// Tracks when the next reconnection attempt should happen after the
+/// compositor connection was lost.
+#[derive(Resource)]
+struct WaylandReconnectState {
+    backoff: Duration,
+    next_attempt: Option<Instant>,
+}
+
+impl Default for WaylandReconnectState {
+    fn default() -> Self {
+        Self {
+            backoff: RECONNECT_INITIAL_BACKOFF,
+            next_attempt: None,
+        }
+    }
+}
+
+impl WaylandReconnectState {
+    /// Schedules the next attempt and returns the delay until it.
+    fn schedule(&mut self, now: Instant) -> Duration {
+        let delay = self.backoff;
+        self.next_attempt = Some(now + delay);
+        self.backoff = (self.backoff * 2).min(RECONNECT_MAX_BACKOFF);
+        delay
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Connects to the compositor on the configured socket (or the one named by
+/// the environment) and performs the initial roundtrip so all globals are
+/// bound, then hands the connection to its dispatch thread. A compositor that
+/// doesn't answer the roundtrip within the timeout counts as a failed
+/// connection.
+fn connect_wayland(
+    alpha_mode: WallpaperAlphaMode,
+    options: &WaylandConnectOptions,
+) -> Result<WaylandConnection, String> {
+    let conn = match options.socket.as_deref() {
+        Some(socket) => connect_to_socket(socket)?,
+        None => Connection::connect_to_env()
+            .map_err(|err| format!("Failed to connect to Wayland: {err}"))?,
+    };
+    let mut event_queue = conn.new_event_queue();
+    let qh = event_queue.handle();
+
+    let display = conn.display();
+    display.get_registry(&qh, ());
+
+    let mut app_state = WaylandAppState::new(display.clone());
+    app_state.transparent = alpha_mode == WallpaperAlphaMode::Transparent;
+    app_state.capture_input = options.capture_input;
+
+    info!("Waiting for globals...");
+    match options.timeout {
+        Some(timeout) => roundtrip_with_timeout(&conn, &mut event_queue, &mut app_state, timeout)?,
+        None => {
+            event_queue
+                .roundtrip(&mut app_state)
+                .map_err(|err| format!("Failed to receive Wayland globals: {err}"))?;
+        }
+    }
+    info!("Globals received.");
+
+    WaylandConnection::spawn(conn, event_queue, app_state)
+}
+
+/// Opens `socket` the way libwayland resolves `WAYLAND_DISPLAY`: an absolute
+/// path as is, anything else relative to `XDG_RUNTIME_DIR`.
+fn connect_to_socket(socket: &str) -> Result<Connection, String> {
+    let path = Path::new(socket);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .ok_or("XDG_RUNTIME_DIR is not set; cannot locate the Wayland socket")?;
+        Path::new(&runtime_dir).join(socket)
+    };
+    let stream = UnixStream::connect(&path)
+        .map_err(|err| format!("Failed to open Wayland socket {}: {err}", path.display()))?;
+    Connection::from_socket(stream)
+        .map_err(|err| format!("Failed to connect to Wayland at {}: {err}", path.display()))
+}
+
+#[derive(SystemParam)]
+struct WaylandConnectionParams<'w> {
+    connection: Option<ResMut<'w, WaylandConnection>>,
+    reconnect: ResMut<'w, WaylandReconnectState>,
+    status: ResMut<'w, WallpaperBackendStatus>,
+    alpha_mode: Res<'w, WallpaperAlphaMode>,
+    connect_options: Res<'w, WaylandConnectOptions>,
+}
+
+/// Input state and message writers fed from Wayland input events.
+#[derive(SystemParam)]
+struct WaylandInputParams<'w> {
+    pointer_state: ResMut<'w, WallpaperPointerState>,
+    pointer_events: MessageWriter<'w, WallpaperPointerEvent>,
+    scroll_events: MessageWriter<'w, WallpaperScrollEvent>,
+    stylus_state: ResMut<'w, WallpaperStylusState>,
+    stylus_events: MessageWriter<'w, WallpaperStylusEvent>,
+    gesture_events: MessageWriter<'w, WallpaperGestureEvent>,
+    keyboard_focus: ResMut<'w, WallpaperKeyboardFocus>,
+    focus_events: MessageWriter<'w, WallpaperFocusEvent>,
+}
+
+/// What the user asked to be shown.
+#[derive(SystemParam)]
+struct WaylandTargetParams<'w> {
+    target_monitor: Res<'w, WallpaperTargetMonitor>,
+    display_mode: Res<'w, WallpaperDisplayMode>,
+    idle_policy: Res<'w, WallpaperIdlePolicy>,
+    output_order: Res<'w, WaylandOutputOrder>,
+    primary_output: Res<'w, WaylandPrimaryOutput>,
+    layer: Res<'w, WaylandLayer>,
+    keyboard_interactivity: Res<'w, WaylandKeyboardInteractivity>,
+    exclusive_zone: Res<'w, WaylandExclusiveZone>,
+    margins: Res<'w, WaylandMargins>,
+    namespace: Res<'w, WaylandLayerNamespace>,
+    cursor: Res<'w, WallpaperCursor>,
+    pointer_constraint: Res<'w, WallpaperPointerConstraint>,
+    lock_screen: ResMut<'w, WallpaperLockScreen>,
+    allow_tearing: Res<'w, WallpaperAllowTearing>,
+    content_type: Res<'w, WallpaperContentType>,
+    color_space: Res<'w, WallpaperColorSpace>,
+    present_mode: Res<'w, WallpaperPresentMode>,
+    fifo_pacing: Res<'w, WallpaperFifoPacing>,
+}
+
+/// Resources the backend reports compositor state into.
+#[derive(SystemParam)]
+struct WaylandReportParams<'w> {
+    surface_info: ResMut<'w, WallpaperSurfaceInfo>,
+    frame_stats: ResMut<'w, WallpaperFrameStats>,
+    visibility: ResMut<'w, WallpaperVisibility>,
+    force_redraw: ResMut<'w, WallpaperForceRedraw>,
+    idle_state: ResMut<'w, WallpaperIdleState>,
+    compositor_idle: ResMut<'w, CompositorIdleNotify>,
+    tearing_state: ResMut<'w, WallpaperTearingState>,
+    memory_budget: Res<'w, WallpaperMemoryBudget>,
+    lock_events: MessageWriter<'w, WallpaperLockEvent>,
+}
+
+/// Drives the Wayland connection once per frame.
+///
+/// The socket is read on the dispatch thread (see [`WaylandConnection`]); this
+/// system only drains what it digested and never waits for compositor
+/// traffic, so the app ticks at its own rate (winit / frame pacing). Requests
+/// issued during the tick are flushed before returning so new surfaces don't
+/// wait an extra frame.
 fn wayland_event_system(
-    mut event_queue: NonSendMut<WaylandEventQueue>,
-    mut app_state: NonSendMut<WaylandAppState>,
+    mut connection: WaylandConnectionParams,
     mut surface_descriptor: ResMut<WaylandSurfaceDescriptor>,
-    target_monitor: Res<WallpaperTargetMonitor>,
-    mut pointer_state: ResMut<WallpaperPointerState>,
-    mut surface_info: ResMut<WallpaperSurfaceInfo>,
+    mut target: WaylandTargetParams,
+    mut input: WaylandInputParams,
+    mut report: WaylandReportParams,
+    mut closed_events: MessageWriter<WallpaperBackendClosed>,
+    frame_pacing: Res<FramePacing>,
 ) {
+    if matches!(
+        *connection.status,
+        WallpaperBackendStatus::Closed | WallpaperBackendStatus::Unsupported
+    ) {
+        return;
+    }
+    // Without a connection yet, `connect_wayland_system` keeps trying.
+    let WaylandConnectionParams {
+        connection: Some(connection),
+        reconnect,
+        status,
+        alpha_mode,
+        connect_options,
+    } = &mut connection
+    else {
+        return;
+    };
+    if !status.is_connected() {
+        try_reconnect(connection, reconnect, status, **alpha_mode, connect_options);
+        return;
+    }
+
+    let mut state = connection.lock();
+    let WaylandConnectionState {
+        event_queue,
+        app_state,
+        error,
+    } = &mut *state;
+    app_state.sync_cursor(*target.cursor, &event_queue.handle());
     if app_state.is_running() {
-        if let Err(err) = pump_wayland_events(&mut event_queue, &mut app_state) {
-            warn!("Wayland event dispatch failed: {err:?}; closing background surface");
+        if let Err(err) = pump_wayland_events(event_queue, app_state, error) {
+            warn!("Wayland event dispatch failed: {err:?}; tearing down surfaces and reconnecting");
             app_state.closed = true;
             surface_descriptor.surfaces.clear();
             surface_descriptor.bump_generation();
+            input.pointer_state.last = None;
+            input.pointer_state.by_seat.clear();
+            *input.stylus_state = WallpaperStylusState::default();
+            input.keyboard_focus.set_if_neq(default());
+            **status = WallpaperBackendStatus::Disconnected;
+            reconnect.schedule(Instant::now());
+            return;
+        }
+
+        if app_state.closed {
+            info!("All Wayland layer surfaces were closed by the compositor");
+            surface_descriptor.surfaces.clear();
+            surface_descriptor.bump_generation();
+            **status = WallpaperBackendStatus::Closed;
+            closed_events.write(WallpaperBackendClosed);
             return;
         }
 
         let qh = event_queue.handle();
-        let (mut touched, removed) =
-            ensure_surfaces_for_outputs(&mut app_state, &qh, &target_monitor);
+        for event in app_state.pending_lock_events.drain(..) {
+            if event == WallpaperLockEvent::Denied {
+                target.lock_screen.locked = false;
+            }
+            report.lock_events.write(event);
+        }
+        app_state.sync_session_lock(target.lock_screen.locked, &qh);
+        app_state.sync_pointer_constraint(target.pointer_constraint.constraint, &qh);
 
+        app_state.primary_output = primary_output(
+            &ordered_outputs(app_state, *target.output_order),
+            &app_state.output_info,
+            target.primary_output.0.as_deref(),
+        );
+        let (mut touched, mut removed) = match *target.display_mode {
+            WallpaperDisplayMode::Wallpaper => ensure_surfaces_for_outputs(
+                app_state,
+                &qh,
+                &target.target_monitor,
+                *target.output_order,
+                LayerSurfaceOptions {
+                    layer: *target.layer,
+                    keyboard: *target.keyboard_interactivity,
+                    exclusive_zone: target.exclusive_zone.resolve(*target.layer),
+                    margins: *target.margins,
+                },
+                &target.namespace,
+            ),
+            // Windowed mode renders through a normal window; drop every layer surface.
+            WallpaperDisplayMode::Windowed => (false, destroy_all_surfaces(app_state)),
+        };
+
+        removed.append(&mut app_state.dropped_surfaces);
         if !removed.is_empty() {
             surface_descriptor
                 .surfaces
@@ -131,51 +600,193 @@ fn wayland_event_system(
         }
 
         // Integrate fresh logical positions/sizes from xdg-output / wl_output.
-        if apply_output_info_updates(&mut surface_descriptor, &mut app_state) {
+        if apply_output_info_updates(&mut surface_descriptor, app_state) {
             touched = true;
         }
 
         if touched {
             surface_descriptor.bump_generation();
+            app_state.sync_buffer_scale(surface_descriptor.render_scale());
         }
 
-        let had_pointer_events = !app_state.pending_pointer_events.is_empty();
+        let pointer_events = app_state.take_pointer_events();
+        let had_pointer_events = !pointer_events.is_empty();
         apply_pointer_events(
-            &mut pointer_state,
-            app_state.pending_pointer_events.drain(..),
+            &mut input.pointer_state,
+            pointer_events,
+            &mut input.pointer_events,
+            &mut input.scroll_events,
+        );
+
+        if !had_pointer_events {
+            let pointer_state = &mut *input.pointer_state;
+            for sample in pointer_state
+                .last
+                .iter_mut()
+                .chain(pointer_state.by_seat.values_mut())
+            {
+                sample.delta = Vec2::ZERO;
+                sample.entered_output = false;
+                sample.last_button = None;
+            }
+        }
+
+        apply_stylus_events(
+            &mut input.stylus_state,
+            app_state.pending_stylus_events.drain(..),
+            &mut input.stylus_events,
         );
+        input
+            .gesture_events
+            .write_batch(app_state.pending_gesture_events.drain(..));
+        input
+            .keyboard_focus
+            .set_if_neq(app_state.wallpaper_keyboard_focus());
+        input
+            .focus_events
+            .write_batch(app_state.pending_focus_events.drain(..));
 
-        if !had_pointer_events && let Some(sample) = pointer_state.last.as_mut() {
-            sample.delta = Vec2::ZERO;
-            sample.last_button = None;
+        if let Some((min_x, min_y, w, h)) = ready_bounds(
+            &surface_descriptor,
+            app_state,
+            &target.target_monitor,
+            *target.output_order,
+        ) {
+            report.surface_info.set(min_x, min_y, w, h);
+        }
+        let scale_factor = (!surface_descriptor.surfaces.is_empty()).then(|| {
+            surface_descriptor.render_scale() as f32 * report.memory_budget.resolution_scale
+        });
+        if report.surface_info.scale_factor != scale_factor {
+            report.surface_info.scale_factor = scale_factor;
         }
 
-        if let Some((min_x, min_y, w, h)) =
-            ready_bounds(&surface_descriptor, &app_state, &target_monitor)
-        {
-            surface_info.set(min_x, min_y, w, h);
+        apply_visibility(app_state, &mut report);
+
+        app_state.sync_idle_notification(target.idle_policy.timeout, &qh);
+        let compositor_idle = app_state.idle_notification.is_some();
+        if report.compositor_idle.0 != compositor_idle {
+            report.compositor_idle.0 = compositor_idle;
+        }
+        if compositor_idle {
+            report.idle_state.set_if_neq(WallpaperIdleState {
+                idle: app_state.idled,
+            });
+        }
+        app_state.sync_tearing_hints(target.allow_tearing.0, &qh);
+        app_state.sync_content_type(*target.content_type, &qh);
+        app_state.sync_color_space(*target.color_space, &qh);
+        app_state.sync_commit_pacing(
+            target.fifo_pacing.0 && *target.present_mode == WallpaperPresentMode::Mailbox,
+            &qh,
+        );
+        frame_pacing.sync(app_state, &qh);
+        let granted = app_state.tearing_outputs();
+        if report.tearing_state.granted != granted {
+            report.tearing_state.granted = granted;
+        }
+        request_presentation_feedback(app_state, &qh);
+        apply_presented_frames(app_state, &mut report.frame_stats);
+
+        if let Err(err) = event_queue.flush() {
+            debug!("Failed to flush Wayland requests: {err:?}");
         }
     }
 }
 
-fn pump_wayland_events(
-    event_queue: &mut WaylandEventQueue,
-    app_state: &mut WaylandAppState,
-) -> Result<(), wayland_client::DispatchError> {
-    while event_queue.dispatch_pending(app_state)? > 0 {}
+/// Retries the compositor connection once the backoff has elapsed.
+///
+/// On success the connection is replaced wholesale; globals are re-bound by the
+/// initial roundtrip and layer surfaces are recreated by
+/// `ensure_surfaces_for_outputs` on the next tick. The dead connection stays
+/// alive until a replacement succeeds, which gives the render world time to
+/// drop its wgpu surfaces first.
+fn try_reconnect(
+    connection: &mut WaylandConnection,
+    reconnect: &mut WaylandReconnectState,
+    status: &mut WallpaperBackendStatus,
+    alpha_mode: WallpaperAlphaMode,
+    options: &WaylandConnectOptions,
+) {
+    let now = Instant::now();
+    if reconnect.next_attempt.is_some_and(|next| now < next) {
+        return;
+    }
+
+    *status = WallpaperBackendStatus::Reconnecting;
+    match connect_wayland(alpha_mode, options) {
+        Ok(new_connection) => {
+            info!("Reconnected to the Wayland compositor");
+            *connection = new_connection;
+            reconnect.reset();
+            *status = WallpaperBackendStatus::Connected;
+        }
+        Err(err) => {
+            let delay = reconnect.schedule(now);
+            warn!("Wayland reconnect failed: {err}; retrying in {delay:?}");
+            *status = WallpaperBackendStatus::Disconnected;
+        }
+    }
+}
 
-    event_queue.flush()?;
+/// Retries the initial connection when the compositor wasn't reachable while
+/// the plugin was built. Surfaces are created by the next event system run.
+fn connect_wayland_system(world: &mut World) {
+    if world.contains_resource::<WaylandConnection>() {
+        return;
+    }
+    let now = Instant::now();
+    let Some(reconnect) = world.get_resource::<WaylandReconnectState>() else {
+        return;
+    };
+    if reconnect.next_attempt.is_some_and(|next| now < next) {
+        return;
+    }
 
-    if let Some(read_guard) = event_queue.prepare_read() {
-        match read_guard.read() {
-            Ok(_) => while event_queue.dispatch_pending(app_state)? > 0 {},
-            Err(wayland_client::backend::WaylandError::Io(err))
-                if err.kind() == ErrorKind::WouldBlock => {}
-            Err(err) => return Err(err.into()),
+    let alpha_mode = world
+        .get_resource::<WallpaperAlphaMode>()
+        .copied()
+        .unwrap_or_default();
+    let options = world
+        .get_resource::<WaylandConnectOptions>()
+        .cloned()
+        .unwrap_or_default();
+    match connect_wayland(alpha_mode, &options) {
+        Ok(connection) => {
+            let status = if connection.lock().app_state.layer_shell.is_some() {
+                info!("Connected to the Wayland compositor");
+                WallpaperBackendStatus::Connected
+            } else {
+                // Fallback backends can only be added while building the app.
+                error!(
+                    "The Wayland compositor does not support zwlr_layer_shell_v1; \
+                     wallpaper surfaces cannot be created"
+                );
+                WallpaperBackendStatus::Unsupported
+            };
+            world.insert_resource(status);
+            world.resource_mut::<WaylandReconnectState>().reset();
+            world.insert_resource(connection);
+        }
+        Err(err) => {
+            let delay = world.resource_mut::<WaylandReconnectState>().schedule(now);
+            warn!("Wayland connection failed: {err}; retrying in {delay:?}");
+            world.insert_resource(WallpaperBackendStatus::Failed(err));
         }
-    } else {
-        while event_queue.dispatch_pending(app_state)? > 0 {}
     }
+}
+
+/// Surfaces an error from the dispatch thread and dispatches whatever it read
+/// since its last pass. Never touches the socket itself.
+fn pump_wayland_events(
+    event_queue: &mut EventQueue<WaylandAppState>,
+    app_state: &mut WaylandAppState,
+    error: &mut Option<wayland_client::DispatchError>,
+) -> Result<(), wayland_client::DispatchError> {
+    if let Some(err) = error.take() {
+        return Err(err);
+    }
+    while event_queue.dispatch_pending(app_state)? > 0 {}
 
     Ok(())
 }
@@ -184,8 +795,9 @@ fn ready_bounds(
     descriptor: &WaylandSurfaceDescriptor,
     app_state: &WaylandAppState,
     target: &WallpaperTargetMonitor,
+    order: WaylandOutputOrder,
 ) -> Option<(i32, i32, u32, u32)> {
-    let selected = selected_outputs(app_state, target)?;
+    let selected = selected_outputs(app_state, target, order)?;
 
     let have_all_selected = selected.iter().all(|output| {
         descriptor
@@ -216,26 +828,93 @@ fn ready_bounds(
 fn apply_pointer_events(
     state: &mut WallpaperPointerState,
     pending: impl IntoIterator<Item = PendingPointerEvent>,
+    pointer_events: &mut MessageWriter<WallpaperPointerEvent>,
+    scroll_events: &mut MessageWriter<WallpaperScrollEvent>,
 ) {
+    // Scroll is per frame; the rest of the sample carries over.
+    for sample in state.last.iter_mut().chain(state.by_seat.values_mut()) {
+        sample.scroll_discrete = Vec2::ZERO;
+        sample.scroll_smooth = Vec2::ZERO;
+    }
+
+    // Seats already updated by this batch keep accumulating their delta and
+    // last button instead of overwriting them event by event.
+    let mut updated = HashSet::new();
     for evt in pending {
-        let prev_position = state
-            .last
-            .as_ref()
+        let prev_sample = state.by_seat.get(&evt.seat);
+        let prev_position = prev_sample
             .map(|s| s.position)
             .unwrap_or(evt.position + evt.offset);
         let new_position = evt.position + evt.offset;
 
+        let kind = match evt.kind {
+            PendingPointerEventKind::Enter => WallpaperPointerEventKind::Entered,
+            PendingPointerEventKind::Leave => WallpaperPointerEventKind::Left,
+            PendingPointerEventKind::Motion => WallpaperPointerEventKind::Moved {
+                delta: new_position - prev_position,
+            },
+            PendingPointerEventKind::Button { button, pressed } => {
+                WallpaperPointerEventKind::Button { button, pressed }
+            }
+            PendingPointerEventKind::Scroll { discrete, smooth } => {
+                let sample = state
+                    .by_seat
+                    .entry(evt.seat)
+                    .or_insert_with(|| PointerSample {
+                        seat: Some(evt.seat),
+                        output: Some(evt.output),
+                        position: new_position,
+                        ..default()
+                    });
+                sample.scroll_discrete += discrete;
+                sample.scroll_smooth += smooth;
+                state.last = Some(sample.clone());
+                continue;
+            }
+        };
+        pointer_events.write(WallpaperPointerEvent {
+            output: Some(evt.output),
+            position: new_position,
+            kind,
+        });
+
+        if matches!(evt.kind, PendingPointerEventKind::Leave) {
+            // Keep the last position, but mark the pointer as off the wallpaper.
+            if let Some(sample) = state.by_seat.get_mut(&evt.seat) {
+                sample.output = None;
+            }
+            if let Some(last) = state.last.as_mut().filter(|s| s.seat == Some(evt.seat)) {
+                last.output = None;
+            }
+            continue;
+        }
+
+        let accumulate = !updated.insert(evt.seat);
+        let prev_sample = prev_sample.cloned().unwrap_or_default();
+        // Entering starts over; the jump from another output isn't motion.
+        let entered = matches!(evt.kind, PendingPointerEventKind::Enter);
         let mut sample = PointerSample {
+            seat: Some(evt.seat),
             output: Some(evt.output),
             position: new_position,
-            delta: new_position - prev_position,
-            ..state.last.clone().unwrap_or_default()
+            delta: if entered {
+                Vec2::ZERO
+            } else {
+                new_position - prev_position
+            },
+            entered_output: entered,
+            ..prev_sample.clone()
         };
+        if accumulate && !entered {
+            sample.delta += prev_sample.delta;
+            sample.entered_output = prev_sample.entered_output;
+        }
 
         sample.last_button = evt
             .kind
             .button_change()
-            .map(|(button, pressed)| PointerButton { button, pressed });
+            .map(|(button, pressed)| PointerButton { button, pressed })
+            .or(prev_sample.last_button.filter(|_| accumulate));
 
         if let Some(btn) = sample.last_button
             && let Some(button) = btn.button
@@ -247,8 +926,138 @@ fn apply_pointer_events(
             }
         }
 
+        state.by_seat.insert(evt.seat, sample.clone());
         state.last = Some(sample);
     }
+
+    scroll_events.write_batch(
+        state
+            .by_seat
+            .values()
+            .filter_map(WallpaperScrollEvent::from_sample),
+    );
+}
+
+fn apply_stylus_events(
+    state: &mut WallpaperStylusState,
+    pending: impl IntoIterator<Item = PendingStylusEvent>,
+    stylus_events: &mut MessageWriter<WallpaperStylusEvent>,
+) {
+    for evt in pending {
+        let position = evt.position.map(|position| position + evt.offset);
+        let phase = match evt.kind {
+            PendingStylusEventKind::ProximityIn(tool) => {
+                state.in_proximity = true;
+                state.position = None;
+                state.tool = tool;
+                WallpaperStylusPhase::ProximityIn
+            }
+            PendingStylusEventKind::ProximityOut => {
+                state.in_proximity = false;
+                state.position = None;
+                state.pressure = 0.0;
+                state.down = false;
+                state.tool = None;
+                state.buttons.clear();
+                WallpaperStylusPhase::ProximityOut
+            }
+            PendingStylusEventKind::Motion => {
+                state.position = position;
+                WallpaperStylusPhase::Motion
+            }
+            PendingStylusEventKind::Down => {
+                state.down = true;
+                WallpaperStylusPhase::Down
+            }
+            PendingStylusEventKind::Up => {
+                state.down = false;
+                WallpaperStylusPhase::Up
+            }
+            PendingStylusEventKind::Pressure(pressure) => {
+                state.pressure = pressure;
+                continue;
+            }
+            PendingStylusEventKind::Tilt(tilt) => {
+                state.tilt = tilt;
+                continue;
+            }
+            PendingStylusEventKind::Button { button, pressed } => {
+                if pressed {
+                    state.buttons.insert(button);
+                } else {
+                    state.buttons.remove(&button);
+                }
+                WallpaperStylusPhase::Button { button, pressed }
+            }
+            PendingStylusEventKind::Frame => WallpaperStylusPhase::Frame,
+        };
+        stylus_events.write(WallpaperStylusEvent {
+            output: Some(evt.output),
+            position,
+            phase,
+        });
+    }
+}
+
+/// Mirrors the compositor's output power state and surface enter/leave into
+/// [`WallpaperVisibility`].
+///
+/// An output that presents again gets a forced redraw, since the last frame it
+/// was sent may predate the time it spent skipped.
+fn apply_visibility(app_state: &WaylandAppState, report: &mut WaylandReportParams) {
+    let (visible, occluded) = app_state.surface_visibility();
+    let visibility = &mut *report.visibility;
+    if visibility.powered_off == app_state.powered_off
+        && visibility.visible == visible
+        && visibility.occluded == occluded
+    {
+        return;
+    }
+
+    let skipped: Vec<u32> = visibility
+        .powered_off
+        .iter()
+        .chain(&visibility.occluded)
+        .copied()
+        .filter(|output| !visibility.should_present(*output))
+        .collect();
+    visibility.powered_off = app_state.powered_off.clone();
+    visibility.visible = visible;
+    visibility.occluded = occluded;
+    if skipped
+        .into_iter()
+        .any(|output| visibility.should_present(output))
+    {
+        report.force_redraw.0 = true;
+    }
+}
+
+/// Asks for presentation feedback on the next commit of every surface that
+/// doesn't already have a request outstanding.
+fn request_presentation_feedback(
+    app_state: &mut WaylandAppState,
+    qh: &QueueHandle<WaylandAppState>,
+) {
+    let Some(presentation) = app_state.presentation.as_ref() else {
+        return;
+    };
+
+    for (output, surface) in &app_state.surfaces {
+        if app_state.pending_feedback.insert(*output) {
+            presentation.feedback(&surface.surface, qh, *output);
+        }
+    }
+}
+
+fn apply_presented_frames(app_state: &mut WaylandAppState, stats: &mut WallpaperFrameStats) {
+    for frame in app_state.presented_frames.drain(..) {
+        let interval = app_state
+            .last_presented
+            .insert(frame.output, frame.presented_at)
+            .and_then(|prev| frame.presented_at.checked_sub(prev));
+        stats.record(frame.presented_at, interval, frame.refresh_ns, frame.flags);
+    }
+    stats.discarded += std::mem::take(&mut app_state.discarded_frames);
 }
 
 /// Apply the latest logical position/size info to existing surface descriptors.
@@ -279,14 +1088,29 @@ fn apply_output_info_updates(
         if let Some(info) = app_state.output_info.get(&surface.output) {
             let mut changed = false;
 
-            update_if(&mut surface.offset_x, info.x, &mut changed);
-            update_if(&mut surface.offset_y, info.y, &mut changed);
+            let origin = app_state.surface_origin(surface.output);
+            update_if(&mut surface.offset_x, origin.x, &mut changed);
+            update_if(&mut surface.offset_y, origin.y, &mut changed);
+            update_if(
+                &mut surface.scale,
+                app_state.output_scale(surface.output),
+                &mut changed,
+            );
 
-            if info.width > 0 {
-                update_if(&mut surface.width, info.width as u32, &mut changed);
+            // The layer surface is the output minus its margins.
+            let margins = app_state
+                .surfaces
+                .get(&surface.output)
+                .filter(|_| !app_state.lock_surfaces.contains_key(&surface.output))
+                .map(|s| s.margins)
+                .unwrap_or_default();
+            let width = info.width - margins.left - margins.right;
+            let height = info.height - margins.top - margins.bottom;
+            if width > 0 {
+                update_if(&mut surface.width, width as u32, &mut changed);
             }
-            if info.height > 0 {
-                update_if(&mut surface.height, info.height as u32, &mut changed);
+            if height > 0 {
+                update_if(&mut surface.height, height as u32, &mut changed);
             }
 
             changed_any |= changed;
@@ -297,14 +1121,189 @@ fn apply_output_info_updates(
     changed_any
 }
 
+/// Forgets the negotiated format and recreates the surfaces so changed
+/// surface preferences (formats, present mode) take effect.
+fn reconfigure_surfaces(
+    negotiated: Res<NegotiatedSurfaceFormat>,
+    mut descriptor: ResMut<WaylandSurfaceDescriptor>,
+) {
+    negotiated.set(None);
+    descriptor.bump_generation();
+}
+
+/// Mirrors the live surfaces into the public [`WallpaperSurfaces`] resource.
+fn sync_wallpaper_surfaces(
+    descriptor: Res<WaylandSurfaceDescriptor>,
+    connection: Option<Res<WaylandConnection>>,
+    mut surfaces: ResMut<WallpaperSurfaces>,
+) {
+    let state = connection.as_ref().map(|connection| connection.lock());
+    let live = descriptor
+        .surfaces
+        .iter()
+        .filter_map(|entry| {
+            let handles = entry.handles?;
+            let info = state
+                .as_ref()
+                .and_then(|state| state.app_state.output_info.get(&entry.output));
+            let scale = info.map_or(1, |info| info.scale.max(1));
+            let surface = WallpaperSurface {
+                handles: WallpaperSurfaceHandles::Wayland(handles),
+                size: UVec2::new(entry.width, entry.height),
+                scale: scale as f32,
+                refresh_hz: info
+                    .filter(|info| info.refresh_rate_mhz > 0)
+                    .map(|info| f64::from(info.refresh_rate_mhz) / 1000.0),
+                physical_size_mm: info.and_then(|info| info.physical_size_mm),
+                dpi: info.and_then(|info| monitor_dpi(info.mode?, info.physical_size_mm)),
+                primary: state
+                    .as_ref()
+                    .is_some_and(|state| state.app_state.primary_output == Some(entry.output)),
+                name: info
+                    .and_then(|info| info.connector_name())
+                    .map(str::to_owned),
+            };
+            Some((entry.output, surface))
+        })
+        .collect();
+    surfaces.set_if_neq(WallpaperSurfaces { surfaces: live });
+}
+
+/// Hands the placeholder color to the connection and drops placeholders
+/// once the render world presented to their surface.
+fn sync_wayland_placeholders(
+    connection: Option<Res<WaylandConnection>>,
+    color: Res<WaylandPlaceholderColor>,
+    configured: Res<ConfiguredWaylandSurfaces>,
+) {
+    let Some(connection) = connection else {
+        return;
+    };
+    let app_state = &mut connection.lock().app_state;
+    app_state.placeholder_color = color.0.map(|color| color.to_srgba());
+    app_state.release_placeholders(|output| configured.contains(output));
+}
+
+/// Reports outputs coming and going once their size and position are known.
+fn write_monitor_messages(
+    connection: Option<Res<WaylandConnection>>,
+    mut messages: MonitorMessages,
+) {
+    let Some(connection) = connection else {
+        return;
+    };
+    let state = connection.lock();
+    let app_state = &state.app_state;
+    let current = app_state
+        .output_order
+        .iter()
+        .filter_map(|output| {
+            let info = app_state.output_info.get(output)?;
+            let known =
+                info.width > 0 && info.height > 0 && app_state.output_position_known(*output);
+            known.then(|| WallpaperMonitorConnected {
+                output: *output,
+                name: info.connector_name().map(str::to_owned),
+                size: UVec2::new(info.width as u32, info.height as u32),
+                position: IVec2::new(info.x, info.y),
+            })
+        })
+        .collect();
+    messages.update(current);
+}
+
+/// Refreshes [`WallpaperWaylandDiagnostics`] and logs it once connected.
+fn update_wayland_diagnostics(
+    connection: Option<Res<WaylandConnection>>,
+    configured: Res<ConfiguredWaylandSurfaces>,
+    mut diagnostics: ResMut<WallpaperWaylandDiagnostics>,
+    mut logged: Local<bool>,
+) {
+    let Some(connection) = connection else {
+        return;
+    };
+    let state = connection.lock();
+    let app_state = &state.app_state;
+    let globals = app_state
+        .globals
+        .iter()
+        .map(|(name, (interface, version))| WaylandGlobalInfo {
+            name: *name,
+            interface: interface.clone(),
+            version: *version,
+        })
+        .collect();
+    let outputs = app_state
+        .output_order
+        .iter()
+        .map(|output| {
+            let info = app_state.output_info.get(output);
+            let head = app_state.output_head(*output);
+            WaylandOutputDiagnostics {
+                output: *output,
+                name: info.and_then(|info| info.connector_name().map(str::to_owned)),
+                description: info.and_then(|info| info.description.clone()),
+                make: head.and_then(|head| head.make.clone()),
+                model: head.and_then(|head| head.model.clone()),
+                serial_number: head.and_then(|head| head.serial_number.clone()),
+                enabled: head.is_none_or(|head| head.enabled),
+                mode: info.and_then(|info| info.mode),
+                scale: info.map_or(1, |info| info.scale),
+                layer_surface: app_state.surfaces.contains_key(output),
+                gpu_surface: configured.contains(*output),
+                primary: app_state.primary_output == Some(*output),
+            }
+        })
+        .collect();
+    diagnostics.set_if_neq(WallpaperWaylandDiagnostics {
+        globals,
+        compositor_version: app_state
+            .compositor
+            .as_ref()
+            .map(|(compositor, _)| compositor.version()),
+        layer_shell_version: app_state
+            .layer_shell
+            .as_ref()
+            .map(|(layer_shell, _)| layer_shell.version()),
+        xdg_output: app_state.xdg_output_manager.is_some(),
+        output_management: app_state.output_manager.is_some(),
+        seat_count: app_state.seats.len(),
+        outputs,
+    });
+
+    if !*logged {
+        *logged = true;
+        info!("Wayland diagnostics: {:#?}", *diagnostics);
+    }
+}
+
 fn sync_wayland_render_target_image(
     descriptor: Res<WaylandSurfaceDescriptor>,
+    negotiated: Res<NegotiatedSurfaceFormat>,
+    mut surface_info: ResMut<WallpaperSurfaceInfo>,
     mut target: ResMut<WaylandRenderTarget>,
     mut images: ResMut<Assets<Image>>,
 ) {
+    // Copies to the surfaces need the render target in the surface format.
+    if let Some(format) = negotiated.get() {
+        let outdated = images
+            .get(&target.image)
+            .is_some_and(|image| image.texture_descriptor.format != format);
+        if outdated && let Some(mut image) = images.get_mut(&target.image) {
+            info!("Wayland surface format: {:?}", format);
+            image.texture_descriptor.format = format;
+            image.data = None;
+        }
+        if surface_info.format != format {
+            surface_info.format = format;
+        }
+    }
+
     let Some((_, _, width, height)) = descriptor.overall_bounds() else {
         return;
     };
+    // The bounds are logical; render at the scale of the sharpest output.
+    let scale = descriptor.render_scale();
 
     if target.last_applied_generation == descriptor.generation {
         return;
@@ -312,8 +1311,8 @@ fn sync_wayland_render_target_image(
 
     if let Some(mut image) = images.get_mut(&target.image) {
         let size = Extent3d {
-            width,
-            height,
+            width: to_physical(width, scale).max(1),
+            height: to_physical(height, scale).max(1),
             depth_or_array_layers: 1,
         };
 
@@ -339,16 +1338,114 @@ fn assign_wayland_camera_target(
     }
 }
 
+/// Keeps cameras with [`LiveWallpaperCamera::output_id`] set on their output's
+/// sub-rectangle of the combined render target.
+fn auto_assign_output_viewports(
+    descriptor: Res<WaylandSurfaceDescriptor>,
+    mut cameras: Query<(&LiveWallpaperCamera, &mut Camera)>,
+) {
+    let Some((min_x, min_y, _, _)) = descriptor.overall_bounds() else {
+        return;
+    };
+    let scale = descriptor.render_scale();
+
+    for (wallpaper_camera, mut camera) in &mut cameras {
+        let Some(output) = wallpaper_camera.output_id else {
+            continue;
+        };
+        let Some(entry) = descriptor
+            .surfaces
+            .iter()
+            .find(|s| s.output == output && s.handles.is_some())
+        else {
+            continue;
+        };
+
+        let viewport = Viewport {
+            physical_position: UVec2::new(
+                to_physical((entry.offset_x - min_x).max(0) as u32, scale),
+                to_physical((entry.offset_y - min_y).max(0) as u32, scale),
+            ),
+            physical_size: UVec2::new(
+                to_physical(entry.width, scale).max(1),
+                to_physical(entry.height, scale).max(1),
+            ),
+            ..default()
+        };
+
+        let unchanged = camera.viewport.as_ref().is_some_and(|current| {
+            current.physical_position == viewport.physical_position
+                && current.physical_size == viewport.physical_size
+        });
+        if !unchanged {
+            camera.viewport = Some(viewport);
+        }
+    }
+}
+
 /// Ensure we have a layer-surface for every known output.
 /// Returns (touched, removed_outputs).
+///
+/// Called every tick, so a changed [`WallpaperTargetMonitor`] is picked up on
+/// the next frame. Surfaces on outputs that are no longer targeted stay up
+/// until the new surfaces have a frame, so switching outputs doesn't flash.
 fn ensure_surfaces_for_outputs(
     app_state: &mut WaylandAppState,
     qh: &QueueHandle<WaylandAppState>,
     target: &WallpaperTargetMonitor,
+    order: WaylandOutputOrder,
+    options: LayerSurfaceOptions,
+    namespace: &WaylandLayerNamespace,
 ) -> (bool, Vec<u32>) {
+    let LayerSurfaceOptions {
+        layer,
+        keyboard,
+        exclusive_zone,
+        margins,
+    } = options;
     let mut touched = false;
     let mut removed: Vec<u32> = Vec::new();
 
+    finish_surface_switch(app_state);
+
+    // Surfaces can't move between layers; recreate those on a stale layer.
+    let stale: Vec<u32> = app_state
+        .surfaces
+        .iter()
+        .filter(|(_, surface)| surface.layer != layer)
+        .map(|(output, _)| *output)
+        .collect();
+    for output in stale {
+        destroy_surface(app_state, output);
+        touched = true;
+        removed.push(output);
+    }
+
+    // Keyboard interactivity can be changed in place; it applies on commit.
+    for surface in app_state.surfaces.values_mut() {
+        if surface.keyboard_interactivity != keyboard {
+            surface
+                .layer_surface
+                .set_keyboard_interactivity(keyboard_interactivity(
+                    &surface.layer_surface,
+                    keyboard,
+                ));
+            surface.surface.commit();
+            surface.keyboard_interactivity = keyboard;
+            touched = true;
+        }
+        // So can the exclusive zone and margins; the compositor answers with
+        // a configure for the new size.
+        if surface.exclusive_zone != exclusive_zone || surface.margins != margins {
+            surface.layer_surface.set_exclusive_zone(exclusive_zone);
+            set_margins(&surface.layer_surface, margins);
+            surface.surface.commit();
+            surface.exclusive_zone = exclusive_zone;
+            surface.margins = margins;
+            touched = true;
+        }
+    }
+
     let Some(compositor) = app_state.compositor.as_ref() else {
         return (touched, removed);
     };
@@ -356,7 +1453,7 @@ fn ensure_surfaces_for_outputs(
         return (touched, removed);
     };
 
-    let Some(selected) = selected_outputs(app_state, target) else {
+    let Some(selected) = selected_outputs(app_state, target, order) else {
         // Invalid selection (e.g., Index out of range); keep current surfaces as-is.
         return (touched, removed);
     };
@@ -366,7 +1463,9 @@ fn ensure_surfaces_for_outputs(
         let Some(output) = app_state.outputs.get(output_name) else {
             continue;
         };
-        if app_state.surfaces.contains_key(output_name) {
+        if app_state.surfaces.contains_key(output_name)
+            || app_state.closed_outputs.contains(output_name)
+        {
             continue;
         }
         let surface = compositor.0.create_surface(qh, ());
@@ -374,12 +1473,13 @@ fn ensure_surfaces_for_outputs(
         let layer_surface = layer_shell.0.get_layer_surface(
             &surface,
             Some(output),
-            zwlr_layer_shell_v1::Layer::Bottom,
-            format!("egl_background_{output_name}"),
+            layer_shell_layer(layer),
+            namespace.for_output(*output_name),
             qh,
             (),
         );
-        layer_surface.set_exclusive_zone(-1);
+        layer_surface.set_exclusive_zone(exclusive_zone);
+        set_margins(&layer_surface, margins);
         layer_surface.set_anchor(
             zwlr_layer_surface_v1::Anchor::Top
                 | zwlr_layer_surface_v1::Anchor::Bottom
@@ -387,19 +1487,36 @@ fn ensure_surfaces_for_outputs(
                 | zwlr_layer_surface_v1::Anchor::Right,
         );
         layer_surface.set_size(0, 0);
+        layer_surface.set_keyboard_interactivity(keyboard_interactivity(&layer_surface, keyboard));
         surface.commit();
         app_state.surfaces.insert(
             *output_name,
             super::OutputSurface {
                 surface: surface.clone(),
                 layer_surface,
+                opaque_size: None,
+                tearing: None,
+                content_type: None,
+                color_surface: None,
+                fifo: None,
+                commit_timer: None,
+                layer,
+                keyboard_interactivity: keyboard,
+                exclusive_zone,
+                margins,
+                placeholder: None,
+                presented: false,
+                buffer_scale: 1,
+                entered_outputs: None,
             },
         );
         app_state.surface_to_output.insert(surface_id, *output_name);
         touched = true;
     }
 
-    // remove surfaces whose outputs vanished
+    // Remove surfaces on outputs that are gone or no longer selected. While
+    // a new surface has yet to show a frame, those on outputs that still
+    // exist stay up until it does.
     let outputs: HashSet<u32> = selected.into_iter().collect();
     let to_remove: Vec<u32> = app_state
         .surfaces
@@ -407,14 +1524,15 @@ fn ensure_surfaces_for_outputs(
         .filter(|k| !outputs.contains(k))
         .copied()
         .collect();
+    let awaiting_frame = app_state
+        .surfaces
+        .iter()
+        .any(|(output, surface)| outputs.contains(output) && !surface.presented);
     for key in to_remove {
-        if let Some(surface) = app_state.surfaces.remove(&key) {
-            // Explicitly destroy to stop showing on that output.
-            surface.layer_surface.destroy();
-            surface.surface.destroy();
-            app_state
-                .surface_to_output
-                .remove(&surface.surface.id().protocol_id());
+        if awaiting_frame && app_state.outputs.contains_key(&key) {
+            retire_surface(app_state, key);
+        } else {
+            destroy_surface(app_state, key);
         }
         touched = true;
         removed.push(key);
@@ -423,23 +1541,571 @@ fn ensure_surfaces_for_outputs(
     (touched, removed)
 }
 
+/// How long a target switch keeps the old surfaces up at most, in case a new
+/// surface never gets a frame (e.g. its output is never configured).
+const SURFACE_SWITCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Moves the surface of `output` out of the live set but keeps it on screen
+/// until [`finish_surface_switch`] destroys it.
+fn retire_surface(app_state: &mut WaylandAppState, output: u32) {
+    let Some(surface) = detach_surface(app_state, output) else {
+        return;
+    };
+    debug!("Keeping the surface on output {output} until the new target has a frame");
+    app_state
+        .surface_switch
+        .get_or_insert_with(|| SurfaceSwitch {
+            retiring: Vec::new(),
+            started: Instant::now(),
+        })
+        .retiring
+        .push((output, surface));
+}
+
+/// Destroys the surfaces a target switch kept up once every live surface has
+/// presented, or after [`SURFACE_SWITCH_TIMEOUT`].
+fn finish_surface_switch(app_state: &mut WaylandAppState) {
+    let Some(switch) = app_state.surface_switch.as_ref() else {
+        return;
+    };
+    let presented = app_state.surfaces.values().all(|surface| surface.presented);
+    if !presented && switch.started.elapsed() < SURFACE_SWITCH_TIMEOUT {
+        return;
+    }
+    if let Some(switch) = app_state.surface_switch.take() {
+        for (output, surface) in switch.retiring {
+            debug!("Target switch done; destroying the surface on output {output}");
+            surface.destroy();
+        }
+    }
+}
+
+/// How new layer surfaces are set up; see [`ensure_surfaces_for_outputs`].
+#[derive(Clone, Copy)]
+struct LayerSurfaceOptions {
+    layer: WaylandLayer,
+    keyboard: WaylandKeyboardInteractivity,
+    exclusive_zone: i32,
+    margins: WaylandMargins,
+}
+
+fn set_margins(layer_surface: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, margins: WaylandMargins) {
+    layer_surface.set_margin(margins.top, margins.right, margins.bottom, margins.left);
+}
+
+fn layer_shell_layer(layer: WaylandLayer) -> zwlr_layer_shell_v1::Layer {
+    match layer {
+        WaylandLayer::Background => zwlr_layer_shell_v1::Layer::Background,
+        WaylandLayer::Bottom => zwlr_layer_shell_v1::Layer::Bottom,
+        WaylandLayer::Top => zwlr_layer_shell_v1::Layer::Top,
+        WaylandLayer::Overlay => zwlr_layer_shell_v1::Layer::Overlay,
+    }
+}
+
+/// Maps [`WaylandKeyboardInteractivity`] to the protocol value. `OnDemand`
+/// needs layer-shell v4; older compositors get `None` instead.
+fn keyboard_interactivity(
+    layer_surface: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+    keyboard: WaylandKeyboardInteractivity,
+) -> zwlr_layer_surface_v1::KeyboardInteractivity {
+    match keyboard {
+        WaylandKeyboardInteractivity::None => zwlr_layer_surface_v1::KeyboardInteractivity::None,
+        WaylandKeyboardInteractivity::OnDemand if layer_surface.version() >= 4 => {
+            zwlr_layer_surface_v1::KeyboardInteractivity::OnDemand
+        }
+        WaylandKeyboardInteractivity::OnDemand => {
+            warn!("OnDemand keyboard interactivity needs zwlr_layer_shell_v1 v4; using None");
+            zwlr_layer_surface_v1::KeyboardInteractivity::None
+        }
+        WaylandKeyboardInteractivity::Exclusive => {
+            zwlr_layer_surface_v1::KeyboardInteractivity::Exclusive
+        }
+    }
+}
+
+/// Destroys every layer surface. Returns the outputs that lost their surface.
+fn destroy_all_surfaces(app_state: &mut WaylandAppState) -> Vec<u32> {
+    if let Some(switch) = app_state.surface_switch.take() {
+        for (_, surface) in switch.retiring {
+            surface.destroy();
+        }
+    }
+    let keys: Vec<u32> = app_state.surfaces.keys().copied().collect();
+    for key in &keys {
+        destroy_surface(app_state, *key);
+    }
+    keys
+}
+
+fn destroy_surface(app_state: &mut WaylandAppState, output: u32) {
+    if let Some(surface) = detach_surface(app_state, output) {
+        // Explicitly destroy to stop showing on that output.
+        surface.destroy();
+    }
+}
+
+/// Removes the surface of `output` from the live set without destroying it.
+fn detach_surface(app_state: &mut WaylandAppState, output: u32) -> Option<super::OutputSurface> {
+    app_state.layer_configs.remove(&output);
+    let surface = app_state.surfaces.remove(&output);
+    if let Some(surface) = &surface {
+        app_state
+            .surface_to_output
+            .remove(&surface.surface.id().protocol_id());
+    }
+    // Drop focus on the destroyed surface so later motion isn't attributed to it.
+    app_state
+        .pointer_focus
+        .retain(|_, focus| focus.output != output);
+    app_state
+        .stylus_focus
+        .retain(|_, focus| focus.output != output);
+    app_state
+        .keyboard_focus
+        .retain(|_, focused| *focused != output);
+    surface
+}
+
+/// Sorts outputs by connector name, then unnamed ones by logical position.
+fn sort_outputs(outputs: &mut [u32], info: &HashMap<u32, OutputInfo>) {
+    outputs.sort_by(|a, b| {
+        let (info_a, info_b) = (info.get(a), info.get(b));
+        let name_a = info_a.and_then(OutputInfo::connector_name);
+        let name_b = info_b.and_then(OutputInfo::connector_name);
+        let position = |i: Option<&OutputInfo>| i.map_or((0, 0), |i| (i.x, i.y));
+        name_a
+            .is_none()
+            .cmp(&name_b.is_none())
+            .then_with(|| name_a.cmp(&name_b))
+            .then_with(|| position(info_a).cmp(&position(info_b)))
+            .then_with(|| a.cmp(b))
+    });
+}
+
+/// Guesses the primary output, which Wayland has no notion of: the output
+/// named by `preferred`, else the one at the logical origin, else the largest
+/// (the earliest in `outputs` on ties). `None` until every output reported its
+/// geometry, so the guess doesn't flip (and recreate surfaces) at startup.
+fn primary_output(
+    outputs: &[u32],
+    output_info: &HashMap<u32, OutputInfo>,
+    preferred: Option<&str>,
+) -> Option<u32> {
+    let info = |output: &u32| output_info.get(output);
+    if let Some(output) = preferred.and_then(|name| {
+        outputs
+            .iter()
+            .find(|output| info(output).and_then(OutputInfo::connector_name) == Some(name))
+    }) {
+        return Some(*output);
+    }
+    // Geometry is all zero until the compositor reports it.
+    let sized = |output: &&u32| info(output).is_some_and(|info| info.width > 0 && info.height > 0);
+    if !outputs.iter().all(|output| sized(&output)) {
+        return None;
+    }
+    outputs
+        .iter()
+        .find(|output| info(output).is_some_and(|info| info.x == 0 && info.y == 0))
+        // `max_by_key` keeps the last maximum; reverse so ties go to the first.
+        .or_else(|| {
+            outputs.iter().rev().max_by_key(|output| {
+                info(output).map_or(0, |info| i64::from(info.width) * i64::from(info.height))
+            })
+        })
+        .copied()
+}
+
+/// Puts outputs, given in registry order, into [`WaylandOutputOrder`].
+fn order_outputs(
+    mut outputs: Vec<u32>,
+    output_info: &HashMap<u32, OutputInfo>,
+    order: WaylandOutputOrder,
+) -> Vec<u32> {
+    if order == WaylandOutputOrder::Name {
+        sort_outputs(&mut outputs, output_info);
+    }
+    outputs
+}
+
+/// Known outputs in [`WaylandOutputOrder`].
+fn ordered_outputs(app_state: &WaylandAppState, order: WaylandOutputOrder) -> Vec<u32> {
+    let mut outputs: Vec<u32> = app_state.output_order.clone();
+    // Disabled heads keep their wl_output with some tools; skip them.
+    outputs.retain(|id| app_state.outputs.contains_key(id) && app_state.output_enabled(*id));
+    order_outputs(outputs, &app_state.output_info, order)
+}
+
 /// Choose outputs according to target monitor selection.
 fn selected_outputs(
     app_state: &WaylandAppState,
     target: &WallpaperTargetMonitor,
+    order: WaylandOutputOrder,
 ) -> Option<Vec<u32>> {
-    let mut outputs: Vec<u32> = app_state.output_order.clone();
-    outputs.retain(|id| app_state.outputs.contains_key(id));
-
+    let outputs = ordered_outputs(app_state, order);
     match target {
         WallpaperTargetMonitor::All => Some(outputs),
-        WallpaperTargetMonitor::Primary => {
-            let v: Vec<u32> = outputs.into_iter().take(1).collect();
-            if v.is_empty() { None } else { Some(v) }
-        }
+        WallpaperTargetMonitor::Primary => app_state
+            .primary_output
+            .filter(|output| outputs.contains(output))
+            .map(|output| vec![output]),
         WallpaperTargetMonitor::Index(n) => {
             let v: Vec<u32> = outputs.into_iter().skip(*n).take(1).collect();
             if v.is_empty() { None } else { Some(v) }
         }
+        WallpaperTargetMonitor::Name(_) => outputs
+            .into_iter()
+            .find(|output| {
+                app_state.output_info.get(output).is_some_and(|info| {
+                    target.matches_name(info.connector_name(), info.description.as_deref())
+                })
+            })
+            .map(|output| vec![output]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use wayland_protocols::xdg::xdg_output::zv1::client::zxdg_output_v1;
+
+    use super::*;
+    use crate::wayland::coalesce_pointer_frame;
+
+    fn output(name: Option<&str>, x: i32, y: i32) -> OutputInfo {
+        OutputInfo {
+            x,
+            y,
+            width: 1920,
+            height: 1080,
+            name: name.map(str::to_owned),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn name_order_sorts_by_connector_name() {
+        let info = HashMap::from([
+            (1, output(Some("HDMI-A-1"), 0, 0)),
+            (2, output(Some("DP-2"), 1920, 0)),
+            (3, output(Some("DP-1"), 3840, 0)),
+        ]);
+
+        assert_eq!(
+            order_outputs(vec![1, 2, 3], &info, WaylandOutputOrder::Name),
+            [3, 2, 1]
+        );
+    }
+
+    #[test]
+    fn name_order_puts_unnamed_outputs_last_by_position() {
+        let info = HashMap::from([
+            (1, output(None, 1920, 0)),
+            (2, output(None, 0, 1080)),
+            (3, output(Some("DP-1"), 3840, 0)),
+            (4, output(None, 0, 0)),
+        ]);
+
+        assert_eq!(
+            order_outputs(vec![1, 2, 3, 4], &info, WaylandOutputOrder::Name),
+            [3, 4, 2, 1]
+        );
+    }
+
+    #[test]
+    fn name_order_uses_xdg_output_name() {
+        let mut xdg_named = output(None, 0, 0);
+        xdg_named.logical_name = Some("DP-1".into());
+        let info = HashMap::from([(1, output(Some("DP-2"), 0, 0)), (2, xdg_named)]);
+
+        assert_eq!(
+            order_outputs(vec![1, 2], &info, WaylandOutputOrder::Name),
+            [2, 1]
+        );
+    }
+
+    #[test]
+    fn registry_order_is_kept() {
+        let info = HashMap::from([
+            (1, output(Some("HDMI-A-1"), 1920, 0)),
+            (2, output(Some("DP-1"), 0, 0)),
+            (3, output(None, 0, 1080)),
+        ]);
+
+        assert_eq!(
+            order_outputs(vec![3, 1, 2], &info, WaylandOutputOrder::Registry),
+            [3, 1, 2]
+        );
+    }
+
+    fn sized(name: &str, x: i32, y: i32, width: i32, height: i32) -> OutputInfo {
+        OutputInfo {
+            width,
+            height,
+            ..output(Some(name), x, y)
+        }
+    }
+
+    #[test]
+    fn primary_prefers_explicit_output() {
+        let info = HashMap::from([
+            (1, sized("DP-1", 0, 0, 3840, 2160)),
+            (2, sized("HDMI-A-1", 3840, 0, 1920, 1080)),
+        ]);
+
+        assert_eq!(primary_output(&[1, 2], &info, Some("HDMI-A-1")), Some(2));
+    }
+
+    #[test]
+    fn primary_falls_back_to_origin_output() {
+        let info = HashMap::from([
+            (1, sized("DP-1", -3840, 0, 3840, 2160)),
+            (2, sized("HDMI-A-1", 0, 0, 1920, 1080)),
+        ]);
+
+        assert_eq!(primary_output(&[1, 2], &info, Some("DP-9")), Some(2));
+        assert_eq!(primary_output(&[1, 2], &info, None), Some(2));
+    }
+
+    #[test]
+    fn primary_falls_back_to_largest_output() {
+        let info = HashMap::from([
+            (1, sized("DP-1", 100, 0, 1920, 1080)),
+            (2, sized("DP-2", 2020, 0, 3840, 2160)),
+        ]);
+
+        assert_eq!(primary_output(&[1, 2], &info, None), Some(2));
+    }
+
+    #[test]
+    fn primary_ties_go_to_first_output() {
+        let info = HashMap::from([
+            (1, sized("DP-1", 100, 0, 1920, 1080)),
+            (2, sized("DP-2", 2020, 0, 1920, 1080)),
+        ]);
+
+        assert_eq!(primary_output(&[2, 1], &info, None), Some(2));
+        assert_eq!(primary_output(&[1, 2], &info, None), Some(1));
+    }
+
+    #[test]
+    fn primary_waits_for_geometry() {
+        let info = HashMap::from([
+            (1, sized("DP-1", 0, 0, 1920, 1080)),
+            (2, sized("DP-2", 0, 0, 0, 0)),
+        ]);
+
+        assert_eq!(primary_output(&[1, 2], &info, None), None);
+        assert_eq!(primary_output(&[1, 2], &info, Some("DP-2")), Some(2));
+    }
+
+    /// Runs `apply_pointer_events` on `state`.
+    fn apply(state: &mut WallpaperPointerState, pending: Vec<PendingPointerEvent>) {
+        let mut world = World::new();
+        world.init_resource::<Messages<WallpaperPointerEvent>>();
+        world.init_resource::<Messages<WallpaperScrollEvent>>();
+        world.insert_resource(std::mem::take(state));
+        let mut pending = Some(pending);
+        world
+            .run_system_once(
+                move |mut state: ResMut<WallpaperPointerState>,
+                      mut pointer_events: MessageWriter<WallpaperPointerEvent>,
+                      mut scroll_events: MessageWriter<WallpaperScrollEvent>| {
+                    apply_pointer_events(
+                        &mut state,
+                        pending.take().unwrap_or_default(),
+                        &mut pointer_events,
+                        &mut scroll_events,
+                    );
+                },
+            )
+            .unwrap();
+        *state = world.remove_resource().unwrap();
+    }
+
+    fn pointer_event(x: f32, kind: PendingPointerEventKind) -> PendingPointerEvent {
+        PendingPointerEvent {
+            seat: 1,
+            output: 1,
+            position: Vec2::new(x, 0.0),
+            offset: Vec2::ZERO,
+            kind,
+        }
+    }
+
+    fn left(pressed: bool) -> PendingPointerEventKind {
+        PendingPointerEventKind::Button {
+            button: Some(MouseButton::Left),
+            pressed,
+        }
+    }
+
+    fn entered_at(x: f32) -> WallpaperPointerState {
+        let mut state = WallpaperPointerState::default();
+        apply(
+            &mut state,
+            vec![pointer_event(x, PendingPointerEventKind::Enter)],
+        );
+        state
+    }
+
+    #[test]
+    fn button_keeps_motion_delta_of_same_frame() {
+        let mut state = entered_at(10.0);
+        let frame = coalesce_pointer_frame(vec![
+            pointer_event(15.0, PendingPointerEventKind::Motion),
+            pointer_event(25.0, PendingPointerEventKind::Motion),
+            pointer_event(25.0, left(true)),
+        ]);
+        apply(&mut state, frame);
+
+        let sample = state.last.unwrap();
+        assert_eq!(sample.delta, Vec2::new(15.0, 0.0));
+        assert_eq!(
+            sample.last_button,
+            Some(PointerButton {
+                button: Some(MouseButton::Left),
+                pressed: true,
+            })
+        );
+        assert!(sample.pressed.contains(&MouseButton::Left));
+    }
+
+    #[test]
+    fn motion_keeps_button_of_same_frame() {
+        let mut state = entered_at(10.0);
+        let frame = coalesce_pointer_frame(vec![
+            pointer_event(10.0, left(true)),
+            pointer_event(12.0, PendingPointerEventKind::Motion),
+            pointer_event(14.0, PendingPointerEventKind::Motion),
+        ]);
+        apply(&mut state, frame);
+
+        let sample = state.last.unwrap();
+        assert_eq!(sample.position, Vec2::new(14.0, 0.0));
+        assert_eq!(sample.delta, Vec2::new(4.0, 0.0));
+        assert_eq!(sample.last_button.map(|button| button.pressed), Some(true));
+    }
+
+    #[test]
+    fn new_frame_resets_delta_and_button() {
+        let mut state = entered_at(10.0);
+        apply(
+            &mut state,
+            coalesce_pointer_frame(vec![
+                pointer_event(20.0, PendingPointerEventKind::Motion),
+                pointer_event(20.0, left(true)),
+            ]),
+        );
+        apply(
+            &mut state,
+            coalesce_pointer_frame(vec![pointer_event(23.0, PendingPointerEventKind::Motion)]),
+        );
+
+        let sample = state.last.unwrap();
+        assert_eq!(sample.delta, Vec2::new(3.0, 0.0));
+        assert_eq!(sample.last_button, None);
+        assert!(sample.pressed.contains(&MouseButton::Left));
+    }
+
+    #[test]
+    fn press_and_release_in_one_frame_leave_button_up() {
+        let mut state = entered_at(10.0);
+        apply(
+            &mut state,
+            coalesce_pointer_frame(vec![
+                pointer_event(10.0, left(true)),
+                pointer_event(10.0, left(false)),
+            ]),
+        );
+
+        let sample = state.last.unwrap();
+        assert_eq!(sample.last_button.map(|button| button.pressed), Some(false));
+        assert!(sample.pressed.is_empty());
+    }
+
+    #[test]
+    fn motion_waits_for_xdg_output_position() {
+        let (socket, _compositor) = UnixStream::pair().unwrap();
+        let connection = Connection::from_socket(socket).unwrap();
+        let mut app_state = WaylandAppState::new(connection.display());
+        app_state.xdg_outputs.insert(
+            1,
+            zxdg_output_v1::ZxdgOutputV1::inert(connection.backend().downgrade()),
+        );
+        // wl_output.geometry arrived, the xdg-output position hasn't yet.
+        let mut info = OutputInfo {
+            mode: Some(UVec2::new(1920, 1080)),
+            scale: 1,
+            ..default()
+        };
+        info.normalize();
+        app_state.output_info.insert(1, info);
+
+        let mut state = WallpaperPointerState::default();
+        app_state.pending_pointer_events.extend([
+            pointer_event(5.0, PendingPointerEventKind::Enter),
+            pointer_event(15.0, PendingPointerEventKind::Motion),
+        ]);
+        apply(&mut state, app_state.take_pointer_events());
+        assert!(state.last.is_none());
+        assert_eq!(app_state.pending_pointer_events.len(), 2);
+
+        let info = app_state.output_info.get_mut(&1).unwrap();
+        info.logical_position = Some(IVec2::new(1920, 0));
+        info.normalize();
+        apply(&mut state, app_state.take_pointer_events());
+
+        let sample = state.last.unwrap();
+        assert_eq!(sample.position, Vec2::new(1935.0, 0.0));
+        assert_eq!(sample.delta, Vec2::new(10.0, 0.0));
+        assert!(app_state.pending_pointer_events.is_empty());
+    }
+
+    #[test]
+    fn stylus_position_waits_for_first_motion() {
+        let stylus_event = |position: Option<Vec2>, kind| PendingStylusEvent {
+            output: 1,
+            position,
+            offset: Vec2::new(1920.0, 0.0),
+            kind,
+        };
+        let mut world = World::new();
+        world.init_resource::<Messages<WallpaperStylusEvent>>();
+        world.init_resource::<WallpaperStylusState>();
+        let apply = |world: &mut World, pending: Vec<PendingStylusEvent>| {
+            world
+                .run_system_once(
+                    move |mut state: ResMut<WallpaperStylusState>,
+                          mut stylus_events: MessageWriter<WallpaperStylusEvent>| {
+                        apply_stylus_events(&mut state, pending.clone(), &mut stylus_events);
+                    },
+                )
+                .unwrap();
+        };
+        apply(
+            &mut world,
+            vec![
+                stylus_event(
+                    None,
+                    PendingStylusEventKind::ProximityIn(Some(crate::StylusTool::Pen)),
+                ),
+                stylus_event(None, PendingStylusEventKind::Frame),
+            ],
+        );
+
+        let state = world.resource::<WallpaperStylusState>();
+        assert!(state.in_proximity);
+        assert_eq!(state.position, None);
+
+        apply(
+            &mut world,
+            vec![stylus_event(
+                Some(Vec2::new(5.0, 7.0)),
+                PendingStylusEventKind::Motion,
+            )],
+        );
+        let state = world.resource::<WallpaperStylusState>();
+        assert_eq!(state.position, Some(Vec2::new(1925.0, 7.0)));
     }
 }
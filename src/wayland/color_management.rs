@@ -0,0 +1,233 @@
+//! Color space tagging via `wp_color_manager_v1`.
+
+use bevy::prelude::*;
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+use wayland_protocols::wp::color_management::v1::client::{
+    wp_color_management_surface_v1, wp_color_manager_v1, wp_image_description_creator_params_v1,
+    wp_image_description_v1,
+};
+
+use super::WaylandAppState;
+use crate::WallpaperColorSpace;
+
+use wp_color_manager_v1::{Feature, Primaries, RenderIntent, TransferFunction};
+
+/// Color manager global and what it advertised.
+#[derive(Default)]
+pub(crate) struct ColorManagement {
+    pub manager: Option<wp_color_manager_v1::WpColorManagerV1>,
+    parametric: bool,
+    transfer_functions: Vec<TransferFunction>,
+    primaries: Vec<Primaries>,
+    /// Set once the manager sent `done`; support is unknown until then.
+    done: bool,
+    /// Image description for the requested color space.
+    description: Option<ImageDescription>,
+    /// Whether falling back to sRGB was already logged.
+    fallback_logged: bool,
+}
+
+struct ImageDescription {
+    color_space: WallpaperColorSpace,
+    object: wp_image_description_v1::WpImageDescriptionV1,
+    state: DescriptionState,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DescriptionState {
+    Pending,
+    Ready,
+    Failed,
+}
+
+impl ColorManagement {
+    /// Named transfer function and primaries for `color_space`, if the
+    /// compositor supports them.
+    fn parameters(
+        &self,
+        color_space: WallpaperColorSpace,
+    ) -> Option<(TransferFunction, Primaries)> {
+        if !self.parametric {
+            return None;
+        }
+        let (transfer_functions, primaries): (&[TransferFunction], Primaries) = match color_space {
+            WallpaperColorSpace::Srgb => return None,
+            WallpaperColorSpace::DisplayP3 => (
+                &[TransferFunction::Srgb, TransferFunction::Gamma22],
+                Primaries::DisplayP3,
+            ),
+            WallpaperColorSpace::Rec2020Pq => (&[TransferFunction::St2084Pq], Primaries::Bt2020),
+        };
+        let transfer_function = transfer_functions
+            .iter()
+            .find(|tf| self.transfer_functions.contains(tf))?;
+        self.primaries
+            .contains(&primaries)
+            .then_some((*transfer_function, primaries))
+    }
+}
+
+impl WaylandAppState {
+    /// Tags every wallpaper surface with `color_space`, creating the image
+    /// description first. `Srgb`, or a color space the compositor can't
+    /// describe, leaves the surfaces untagged (sRGB).
+    pub(crate) fn sync_color_space(
+        &mut self,
+        color_space: WallpaperColorSpace,
+        qh: &QueueHandle<Self>,
+    ) {
+        let color = &mut self.color_management;
+        let Some(manager) = color.manager.as_ref() else {
+            return;
+        };
+        if !color.done {
+            return;
+        }
+
+        if color
+            .description
+            .as_ref()
+            .is_some_and(|description| description.color_space != color_space)
+            && let Some(description) = color.description.take()
+        {
+            description.object.destroy();
+        }
+
+        let parameters = color.parameters(color_space);
+        if color_space != WallpaperColorSpace::Srgb
+            && parameters.is_none()
+            && !color.fallback_logged
+        {
+            info!("Compositor can't describe {color_space:?}; wallpaper surfaces stay sRGB");
+            color.fallback_logged = true;
+        }
+        if color.description.is_none()
+            && let Some((transfer_function, primaries)) = parameters
+        {
+            let creator = manager.create_parametric_creator(qh, ());
+            creator.set_tf_named(transfer_function);
+            creator.set_primaries_named(primaries);
+            color.description = Some(ImageDescription {
+                color_space,
+                object: creator.create(qh, ()),
+                state: DescriptionState::Pending,
+            });
+        }
+
+        let description = color
+            .description
+            .as_ref()
+            .filter(|description| description.state == DescriptionState::Ready);
+        for surface in self.surfaces.values_mut() {
+            let applied = surface.color_surface.as_ref().map(|(_, applied)| *applied);
+            match description {
+                Some(description) if applied != Some(color_space) => {
+                    let color_surface = match surface.color_surface.take() {
+                        Some((color_surface, _)) => color_surface,
+                        None => manager.get_surface(&surface.surface, qh, ()),
+                    };
+                    color_surface
+                        .set_image_description(&description.object, RenderIntent::Perceptual);
+                    surface.color_surface = Some((color_surface, color_space));
+                }
+                // Untagged surfaces are sRGB.
+                None if applied.is_some() => {
+                    if let Some((color_surface, _)) = surface.color_surface.take() {
+                        color_surface.destroy();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Dispatch<wp_color_manager_v1::WpColorManagerV1, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _manager: &wp_color_manager_v1::WpColorManagerV1,
+        event: wp_color_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let color = &mut state.color_management;
+        match event {
+            wp_color_manager_v1::Event::SupportedFeature {
+                feature: WEnum::Value(Feature::Parametric),
+            } => color.parametric = true,
+            wp_color_manager_v1::Event::SupportedTfNamed {
+                tf: WEnum::Value(tf),
+            } => {
+                color.transfer_functions.push(tf);
+            }
+            wp_color_manager_v1::Event::SupportedPrimariesNamed {
+                primaries: WEnum::Value(primaries),
+            } => color.primaries.push(primaries),
+            wp_color_manager_v1::Event::Done => color.done = true,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1, ()>
+    for WaylandAppState
+{
+    fn event(
+        _state: &mut Self,
+        _creator: &wp_image_description_creator_params_v1::WpImageDescriptionCreatorParamsV1,
+        _event: wp_image_description_creator_params_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the creator has no events.
+    }
+}
+
+impl Dispatch<wp_image_description_v1::WpImageDescriptionV1, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        object: &wp_image_description_v1::WpImageDescriptionV1,
+        event: wp_image_description_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let Some(description) = state
+            .color_management
+            .description
+            .as_mut()
+            .filter(|description| description.object == *object)
+        else {
+            return;
+        };
+        match event {
+            wp_image_description_v1::Event::Ready { .. } => {
+                debug!("Image description for {:?} ready", description.color_space);
+                description.state = DescriptionState::Ready;
+            }
+            wp_image_description_v1::Event::Failed { msg, .. } => {
+                warn!(
+                    "Compositor rejected the {:?} image description: {msg}; wallpaper surfaces stay sRGB",
+                    description.color_space
+                );
+                description.state = DescriptionState::Failed;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wp_color_management_surface_v1::WpColorManagementSurfaceV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _surface: &wp_color_management_surface_v1::WpColorManagementSurfaceV1,
+        _event: wp_color_management_surface_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the color management surface has no events.
+    }
+}
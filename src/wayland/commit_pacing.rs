@@ -0,0 +1,97 @@
+//! Commit pacing via `wp_fifo_v1` and `wp_commit_timing_v1`.
+//!
+//! With [`crate::WallpaperFifoPacing`] on, every `Mailbox` commit sets a FIFO
+//! barrier and waits for the previous one, so the compositor applies at most
+//! one frame per refresh instead of the GPU racing ahead. Where commit timing
+//! is available the commit also carries a target presentation time, see
+//! [`super::frame_pacing::FramePacing`].
+
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::commit_timing::v1::client::{
+    wp_commit_timer_v1, wp_commit_timing_manager_v1,
+};
+use wayland_protocols::wp::fifo::v1::client::{wp_fifo_manager_v1, wp_fifo_v1};
+
+use super::WaylandAppState;
+
+impl WaylandAppState {
+    /// Creates or destroys the per-surface fifo and commit timer objects.
+    ///
+    /// A surface can only have one of each, and Vulkan drivers create their
+    /// own for `Fifo` swapchains, so ours only exist while `enabled`.
+    pub(crate) fn sync_commit_pacing(&mut self, enabled: bool, qh: &QueueHandle<Self>) {
+        for surface in self.surfaces.values_mut() {
+            if !enabled {
+                if let Some(fifo) = surface.fifo.take() {
+                    fifo.destroy();
+                }
+                if let Some(commit_timer) = surface.commit_timer.take() {
+                    commit_timer.destroy();
+                }
+                continue;
+            }
+            if surface.fifo.is_none()
+                && let Some(manager) = self.fifo_manager.as_ref()
+            {
+                surface.fifo = Some(manager.get_fifo(&surface.surface, qh, ()));
+            }
+            if surface.commit_timer.is_none()
+                && let Some(manager) = self.commit_timing_manager.as_ref()
+            {
+                surface.commit_timer = Some(manager.get_timer(&surface.surface, qh, ()));
+            }
+        }
+    }
+}
+
+impl Dispatch<wp_fifo_manager_v1::WpFifoManagerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &wp_fifo_manager_v1::WpFifoManagerV1,
+        _event: wp_fifo_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<wp_fifo_v1::WpFifoV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _fifo: &wp_fifo_v1::WpFifoV1,
+        _event: wp_fifo_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the fifo object has no events.
+    }
+}
+
+impl Dispatch<wp_commit_timing_manager_v1::WpCommitTimingManagerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &wp_commit_timing_manager_v1::WpCommitTimingManagerV1,
+        _event: wp_commit_timing_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<wp_commit_timer_v1::WpCommitTimerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _timer: &wp_commit_timer_v1::WpCommitTimerV1,
+        _event: wp_commit_timer_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the commit timer has no events.
+    }
+}
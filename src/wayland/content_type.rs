@@ -0,0 +1,110 @@
+//! Content type hints via `wp_content_type_v1`.
+
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::content_type::v1::client::{
+    wp_content_type_manager_v1, wp_content_type_v1,
+};
+
+use super::WaylandAppState;
+use crate::WallpaperContentType;
+
+impl WaylandAppState {
+    /// Sets `content_type` on every wallpaper surface whose hint differs. The
+    /// hint takes effect on the next commit.
+    pub(crate) fn sync_content_type(
+        &mut self,
+        content_type: WallpaperContentType,
+        qh: &QueueHandle<Self>,
+    ) {
+        let Some(manager) = self.content_type_manager.as_ref() else {
+            return;
+        };
+        for surface in self.surfaces.values_mut() {
+            let applied = surface.content_type.as_ref().map(|(_, applied)| *applied);
+            let Some(hint) = content_type_request(applied, content_type) else {
+                continue;
+            };
+            let object = match surface.content_type.take() {
+                Some((object, _)) => object,
+                None => manager.get_surface_content_type(&surface.surface, qh, ()),
+            };
+            object.set_content_type(hint);
+            surface.content_type = Some((object, content_type));
+        }
+    }
+}
+
+/// Hint to send to a surface last given `applied`, or `None` when it is
+/// already current.
+fn content_type_request(
+    applied: Option<WallpaperContentType>,
+    content_type: WallpaperContentType,
+) -> Option<wp_content_type_v1::Type> {
+    // Surfaces start out without a content type.
+    if applied.unwrap_or_default() == content_type {
+        return None;
+    }
+    Some(match content_type {
+        WallpaperContentType::None => wp_content_type_v1::Type::None,
+        WallpaperContentType::Photo => wp_content_type_v1::Type::Photo,
+        WallpaperContentType::Video => wp_content_type_v1::Type::Video,
+        WallpaperContentType::Game => wp_content_type_v1::Type::Game,
+    })
+}
+
+impl Dispatch<wp_content_type_manager_v1::WpContentTypeManagerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &wp_content_type_manager_v1::WpContentTypeManagerV1,
+        _event: wp_content_type_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<wp_content_type_v1::WpContentTypeV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _content_type: &wp_content_type_v1::WpContentTypeV1,
+        _event: wp_content_type_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the content type object has no events.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_surface_gets_non_default_hint() {
+        assert_eq!(content_type_request(None, WallpaperContentType::None), None);
+        assert_eq!(
+            content_type_request(None, WallpaperContentType::Game),
+            Some(wp_content_type_v1::Type::Game)
+        );
+    }
+
+    #[test]
+    fn hint_is_reissued_after_runtime_change() {
+        let applied = Some(WallpaperContentType::Game);
+        assert_eq!(
+            content_type_request(applied, WallpaperContentType::Game),
+            None
+        );
+        assert_eq!(
+            content_type_request(applied, WallpaperContentType::Video),
+            Some(wp_content_type_v1::Type::Video)
+        );
+        assert_eq!(
+            content_type_request(applied, WallpaperContentType::None),
+            Some(wp_content_type_v1::Type::None)
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Cursor over the wallpaper via `wp_cursor_shape_manager_v1`.
+
+use bevy::window::SystemCursorIcon;
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::cursor_shape::v1::client::{
+    wp_cursor_shape_device_v1::{self, Shape},
+    wp_cursor_shape_manager_v1,
+};
+
+use super::WaylandAppState;
+use crate::WallpaperCursor;
+
+impl WaylandAppState {
+    /// Stores the requested cursor and re-applies it to pointers currently
+    /// over a wallpaper surface.
+    pub(crate) fn sync_cursor(&mut self, cursor: WallpaperCursor, qh: &QueueHandle<Self>) {
+        if self.cursor == cursor {
+            return;
+        }
+        self.cursor = cursor;
+        let focused: Vec<(u32, u32)> = self
+            .pointer_focus
+            .iter()
+            .map(|(seat, focus)| (*seat, focus.serial))
+            .collect();
+        for (seat, serial) in focused {
+            self.apply_cursor(seat, serial, qh);
+        }
+    }
+
+    /// Sets the cursor of the pointer on `seat`, using the `serial` of its
+    /// latest enter event.
+    pub(crate) fn apply_cursor(&mut self, seat: u32, serial: u32, qh: &QueueHandle<Self>) {
+        let Some(pointer) = self.pointers.get(&seat) else {
+            return;
+        };
+        let shape = match self.cursor {
+            WallpaperCursor::Hidden => {
+                pointer.set_cursor(serial, None, 0, 0);
+                return;
+            }
+            WallpaperCursor::Default => Shape::Default,
+            WallpaperCursor::Shape(icon) => cursor_shape(icon),
+        };
+        // Without the shape protocol a cursor theme would have to be loaded;
+        // leave the compositor's cursor in place instead.
+        let Some(manager) = self.cursor_shape_manager.as_ref() else {
+            return;
+        };
+        self.cursor_shape_devices
+            .entry(seat)
+            .or_insert_with(|| manager.get_pointer(pointer, qh, ()))
+            .set_shape(serial, shape);
+    }
+
+    /// Destroys the shape device of `seat`; call before releasing its pointer.
+    pub(crate) fn release_cursor_shape_device(&mut self, seat: u32) {
+        if let Some(device) = self.cursor_shape_devices.remove(&seat) {
+            device.destroy();
+        }
+    }
+}
+
+fn cursor_shape(icon: SystemCursorIcon) -> Shape {
+    match icon {
+        SystemCursorIcon::Default => Shape::Default,
+        SystemCursorIcon::ContextMenu => Shape::ContextMenu,
+        SystemCursorIcon::Help => Shape::Help,
+        SystemCursorIcon::Pointer => Shape::Pointer,
+        SystemCursorIcon::Progress => Shape::Progress,
+        SystemCursorIcon::Wait => Shape::Wait,
+        SystemCursorIcon::Cell => Shape::Cell,
+        SystemCursorIcon::Crosshair => Shape::Crosshair,
+        SystemCursorIcon::Text => Shape::Text,
+        SystemCursorIcon::VerticalText => Shape::VerticalText,
+        SystemCursorIcon::Alias => Shape::Alias,
+        SystemCursorIcon::Copy => Shape::Copy,
+        SystemCursorIcon::Move => Shape::Move,
+        SystemCursorIcon::NoDrop => Shape::NoDrop,
+        SystemCursorIcon::NotAllowed => Shape::NotAllowed,
+        SystemCursorIcon::Grab => Shape::Grab,
+        SystemCursorIcon::Grabbing => Shape::Grabbing,
+        SystemCursorIcon::EResize => Shape::EResize,
+        SystemCursorIcon::NResize => Shape::NResize,
+        SystemCursorIcon::NeResize => Shape::NeResize,
+        SystemCursorIcon::NwResize => Shape::NwResize,
+        SystemCursorIcon::SResize => Shape::SResize,
+        SystemCursorIcon::SeResize => Shape::SeResize,
+        SystemCursorIcon::SwResize => Shape::SwResize,
+        SystemCursorIcon::WResize => Shape::WResize,
+        SystemCursorIcon::EwResize => Shape::EwResize,
+        SystemCursorIcon::NsResize => Shape::NsResize,
+        SystemCursorIcon::NeswResize => Shape::NeswResize,
+        SystemCursorIcon::NwseResize => Shape::NwseResize,
+        SystemCursorIcon::ColResize => Shape::ColResize,
+        SystemCursorIcon::RowResize => Shape::RowResize,
+        SystemCursorIcon::AllScroll => Shape::AllScroll,
+        SystemCursorIcon::ZoomIn => Shape::ZoomIn,
+        SystemCursorIcon::ZoomOut => Shape::ZoomOut,
+    }
+}
+
+impl Dispatch<wp_cursor_shape_manager_v1::WpCursorShapeManagerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &wp_cursor_shape_manager_v1::WpCursorShapeManagerV1,
+        _event: wp_cursor_shape_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<wp_cursor_shape_device_v1::WpCursorShapeDeviceV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _device: &wp_cursor_shape_device_v1::WpCursorShapeDeviceV1,
+        _event: wp_cursor_shape_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the device has no events.
+    }
+}
@@ -0,0 +1,200 @@
+//! Reads the Wayland socket on a dedicated thread.
+//!
+//! The thread sleeps in `poll` until the compositor sends something, reads it
+//! and dispatches it into [`WaylandAppState`] under the connection lock. Event
+//! handlers only digest events into the `pending_*` queues and output maps,
+//! which the main-world event system drains once per frame, so bursts of
+//! compositor traffic (e.g. pointer motion while dragging a window across the
+//! wallpaper) no longer run inside the schedule. Requests are still issued
+//! from the main world while it holds the lock, so surfaces handed to the
+//! render world are only ever created and destroyed there.
+
+use std::io::ErrorKind;
+use std::os::fd::BorrowedFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use rustix::event::{PollFd, PollFlags, Timespec, poll};
+use wayland_client::{
+    Connection, Dispatch, DispatchError, EventQueue, QueueHandle, backend::WaylandError,
+    protocol::wl_callback,
+};
+
+use super::WaylandAppState;
+
+/// How long the thread waits on a quiet socket before checking whether the
+/// connection was dropped.
+const POLL_TIMEOUT: Timespec = Timespec {
+    tv_sec: 0,
+    tv_nsec: 100_000_000,
+};
+
+pub(crate) struct WaylandConnectionState {
+    pub event_queue: EventQueue<WaylandAppState>,
+    pub app_state: WaylandAppState,
+    /// Set by the dispatch thread when reading or dispatching failed; the
+    /// thread exits afterwards.
+    pub error: Option<DispatchError>,
+}
+
+/// The compositor connection, shared between the main world and its dispatch
+/// thread. Dropping the last handle stops the thread.
+#[derive(Resource, Clone)]
+pub(crate) struct WaylandConnection(Arc<Mutex<WaylandConnectionState>>);
+
+impl WaylandConnection {
+    /// Takes over a connection after the initial roundtrip and starts its
+    /// dispatch thread.
+    pub fn spawn(
+        conn: Connection,
+        event_queue: EventQueue<WaylandAppState>,
+        app_state: WaylandAppState,
+    ) -> Result<Self, String> {
+        let shared = Arc::new(Mutex::new(WaylandConnectionState {
+            event_queue,
+            app_state,
+            error: None,
+        }));
+        let weak = Arc::downgrade(&shared);
+        thread::Builder::new()
+            .name("wayland-dispatch".into())
+            .spawn(move || dispatch_thread(conn, weak))
+            .map_err(|err| format!("Failed to spawn the Wayland dispatch thread: {err}"))?;
+        Ok(Self(shared))
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, WaylandConnectionState> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+fn dispatch_thread(conn: Connection, shared: Weak<Mutex<WaylandConnectionState>>) {
+    loop {
+        // `None` means events are already queued; dispatch them right away.
+        if let Some(guard) = conn.prepare_read() {
+            match wait_readable(guard.connection_fd()) {
+                Ok(true) => {}
+                Ok(false) => {
+                    drop(guard);
+                    if shared.strong_count() == 0 {
+                        return;
+                    }
+                    continue;
+                }
+                Err(err) => {
+                    report_error(&shared, WaylandError::Io(err).into());
+                    return;
+                }
+            }
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => continue,
+                Err(err) => {
+                    report_error(&shared, err.into());
+                    return;
+                }
+            }
+        }
+
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let mut state = shared.lock().unwrap_or_else(|err| err.into_inner());
+        let WaylandConnectionState {
+            event_queue,
+            app_state,
+            error,
+        } = &mut *state;
+        if let Err(err) = event_queue.dispatch_pending(app_state) {
+            *error = Some(err);
+            return;
+        }
+    }
+}
+
+/// User data of the `wl_display.sync` callback sent by
+/// [`roundtrip_with_timeout`].
+pub(crate) struct InitialSync(Arc<AtomicBool>);
+
+/// Like [`EventQueue::roundtrip`], but gives up once `timeout` elapsed so a
+/// hung compositor can't block the app before its first frame.
+pub(crate) fn roundtrip_with_timeout(
+    conn: &Connection,
+    event_queue: &mut EventQueue<WaylandAppState>,
+    app_state: &mut WaylandAppState,
+    timeout: Duration,
+) -> Result<(), String> {
+    let deadline = Instant::now() + timeout;
+    let done = Arc::new(AtomicBool::new(false));
+    conn.display()
+        .sync(&event_queue.handle(), InitialSync(done.clone()));
+
+    while !done.load(Ordering::Acquire) {
+        event_queue
+            .flush()
+            .map_err(|err| format!("Failed to send Wayland requests: {err}"))?;
+        if let Some(guard) = event_queue.prepare_read() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!(
+                    "The Wayland compositor did not answer within {timeout:?}"
+                ));
+            }
+            let poll_timeout = Timespec {
+                tv_sec: remaining.as_secs() as _,
+                tv_nsec: remaining.subsec_nanos() as _,
+            };
+            if wait_readable_for(guard.connection_fd(), &poll_timeout)
+                .map_err(|err| format!("Failed to poll the Wayland socket: {err}"))?
+            {
+                match guard.read() {
+                    Ok(_) => {}
+                    Err(WaylandError::Io(err)) if err.kind() == ErrorKind::WouldBlock => {}
+                    Err(err) => return Err(format!("Failed to receive Wayland globals: {err}")),
+                }
+            }
+        }
+        event_queue
+            .dispatch_pending(app_state)
+            .map_err(|err| format!("Failed to receive Wayland globals: {err}"))?;
+    }
+    Ok(())
+}
+
+impl Dispatch<wl_callback::WlCallback, InitialSync> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _callback: &wl_callback::WlCallback,
+        event: wl_callback::Event,
+        done: &InitialSync,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            done.0.store(true, Ordering::Release);
+        }
+    }
+}
+
+/// Waits up to [`POLL_TIMEOUT`] for the socket to become readable.
+fn wait_readable(fd: BorrowedFd<'_>) -> std::io::Result<bool> {
+    wait_readable_for(fd, &POLL_TIMEOUT)
+}
+
+fn wait_readable_for(fd: BorrowedFd<'_>, timeout: &Timespec) -> std::io::Result<bool> {
+    let mut fds = [PollFd::new(&fd, PollFlags::IN | PollFlags::ERR)];
+    match poll(&mut fds, Some(timeout)) {
+        Ok(ready) => Ok(ready > 0),
+        Err(rustix::io::Errno::INTR) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn report_error(shared: &Weak<Mutex<WaylandConnectionState>>, err: DispatchError) {
+    if let Some(shared) = shared.upgrade() {
+        shared.lock().unwrap_or_else(|err| err.into_inner()).error = Some(err);
+    }
+}
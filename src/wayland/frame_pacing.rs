@@ -0,0 +1,224 @@
+//! Per-output present pacing via `wl_surface.frame` callbacks.
+//!
+//! Each output only gets a new frame once the compositor asked for one, so a
+//! 60 Hz output no longer holds back a 144 Hz one by blocking in the FIFO
+//! queue; the faster surface presents on every tick while the slower one skips
+//! ticks. Surfaces with commit pacing (see `commit_pacing`) additionally
+//! set a FIFO barrier and a target presentation time on the same commit.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use wayland_client::{
+    Connection, Dispatch, QueueHandle,
+    protocol::{wl_callback, wl_surface},
+};
+use wayland_protocols::wp::commit_timing::v1::client::wp_commit_timer_v1;
+use wayland_protocols::wp::fifo::v1::client::wp_fifo_v1;
+
+use super::WaylandAppState;
+
+/// Longest a surface waits for its frame callback before presenting anyway.
+/// Compositors hold callbacks back while a surface is hidden, and a present
+/// that failed after the request leaves the callback unanswered.
+const FRAME_CALLBACK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Frame callback state per output, shared between the main world (which
+/// tracks the surfaces) and the render world (which presents).
+#[derive(Resource, Clone, Default)]
+pub(crate) struct FramePacing(Arc<Mutex<HashMap<u32, OutputFrame>>>);
+
+struct OutputFrame {
+    surface: wl_surface::WlSurface,
+    qh: QueueHandle<WaylandAppState>,
+    /// When the outstanding frame callback was requested.
+    requested_at: Option<Instant>,
+    fifo: Option<wp_fifo_v1::WpFifoV1>,
+    commit_timer: Option<wp_commit_timer_v1::WpCommitTimerV1>,
+    /// One refresh after the last presented frame, in the presentation clock.
+    target_time: Option<Duration>,
+}
+
+/// Whether the compositor asked for a new frame since the callback requested
+/// at `requested_at`, or gave no answer for too long.
+fn frame_ready(requested_at: Option<Instant>, now: Instant) -> bool {
+    requested_at
+        .is_none_or(|requested_at| now.duration_since(requested_at) >= FRAME_CALLBACK_TIMEOUT)
+}
+
+/// One refresh after `presented`; `None` while the refresh rate is unknown.
+fn next_target_time(presented: Duration, refresh_mhz: i32) -> Option<Duration> {
+    let refresh_mhz = u64::try_from(refresh_mhz).ok().filter(|mhz| *mhz > 0)?;
+    Some(presented + Duration::from_nanos(1_000_000_000_000 / refresh_mhz))
+}
+
+/// User data of a frame callback.
+pub(crate) struct FrameCallback {
+    output: u32,
+    pacing: FramePacing,
+}
+
+impl FramePacing {
+    fn lock(&self) -> MutexGuard<'_, HashMap<u32, OutputFrame>> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Follows the wallpaper surfaces of the current connection.
+    pub(crate) fn sync(&self, app_state: &WaylandAppState, qh: &QueueHandle<WaylandAppState>) {
+        let mut outputs = self.lock();
+        outputs.retain(|output, frame| {
+            app_state
+                .surfaces
+                .get(output)
+                .is_some_and(|surface| surface.surface == frame.surface)
+        });
+        for (output, surface) in &app_state.surfaces {
+            let frame = outputs.entry(*output).or_insert_with(|| OutputFrame {
+                surface: surface.surface.clone(),
+                qh: qh.clone(),
+                requested_at: None,
+                fifo: None,
+                commit_timer: None,
+                target_time: None,
+            });
+            frame.fifo = surface.fifo.clone();
+            frame.commit_timer = surface.commit_timer.clone();
+            let refresh_mhz = app_state
+                .output_info
+                .get(output)
+                .map_or(0, |info| info.refresh_rate_mhz);
+            frame.target_time = app_state
+                .last_presented
+                .get(output)
+                .and_then(|presented| next_target_time(*presented, refresh_mhz));
+        }
+    }
+
+    /// Whether `output` may present at `now`. Untracked outputs always may.
+    pub(crate) fn ready(&self, output: u32, now: Instant) -> bool {
+        self.lock()
+            .get(&output)
+            .is_none_or(|frame| frame_ready(frame.requested_at, now))
+    }
+
+    /// Asks to be told when the compositor wants the next frame of `output`.
+    /// Called right before presenting so the request rides on that commit.
+    pub(crate) fn request_frame(&self, output: u32) {
+        if let Some(frame) = self.lock().get_mut(&output) {
+            frame.surface.frame(
+                &frame.qh,
+                FrameCallback {
+                    output,
+                    pacing: self.clone(),
+                },
+            );
+            frame.requested_at = Some(Instant::now());
+            if let Some(fifo) = frame.fifo.as_ref() {
+                fifo.wait_barrier();
+                fifo.set_barrier();
+            }
+            if let (Some(commit_timer), Some(target)) =
+                (frame.commit_timer.as_ref(), frame.target_time.take())
+            {
+                let secs = target.as_secs();
+                commit_timer.set_timestamp((secs >> 32) as u32, secs as u32, target.subsec_nanos());
+            }
+        }
+    }
+
+    fn frame_done(&self, output: u32) {
+        if let Some(frame) = self.lock().get_mut(&output) {
+            frame.requested_at = None;
+        }
+    }
+}
+
+impl Dispatch<wl_callback::WlCallback, FrameCallback> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _callback: &wl_callback::WlCallback,
+        event: wl_callback::Event,
+        data: &FrameCallback,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            trace!("Frame callback for output {}", data.output);
+            data.pacing.frame_done(data.output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HZ_60: i32 = 60_000;
+    const HZ_144: i32 = 144_000;
+
+    fn refresh(refresh_mhz: i32) -> Duration {
+        next_target_time(Duration::ZERO, refresh_mhz).unwrap()
+    }
+
+    /// Presents on every tick an output is ready for, with the compositor
+    /// answering each frame callback one refresh later. Returns the number
+    /// of presents.
+    fn simulate(refresh_mhz: i32, tick: Duration, ticks: u32) -> u32 {
+        let start = Instant::now();
+        let mut requested_at = None;
+        let mut presents = 0;
+        for i in 0..ticks {
+            let now = start + tick * i;
+            if requested_at.is_some_and(|at| now >= at + refresh(refresh_mhz)) {
+                requested_at = None;
+            }
+            if frame_ready(requested_at, now) {
+                presents += 1;
+                requested_at = Some(now);
+            }
+        }
+        presents
+    }
+
+    #[test]
+    fn target_time_is_one_refresh_later() {
+        let presented = Duration::from_secs(10);
+        assert_eq!(
+            next_target_time(presented, HZ_60),
+            Some(presented + Duration::from_nanos(16_666_666))
+        );
+        assert_eq!(
+            next_target_time(presented, HZ_144),
+            Some(presented + Duration::from_nanos(6_944_444))
+        );
+        assert_eq!(next_target_time(presented, 0), None);
+    }
+
+    #[test]
+    fn outputs_present_at_their_own_rate() {
+        // One second of 1 ms ticks; each output presents at most once per
+        // refresh, rounded up to whole ticks.
+        let tick = Duration::from_millis(1);
+        let presents_144 = simulate(HZ_144, tick, 1000);
+        let presents_60 = simulate(HZ_60, tick, 1000);
+
+        assert!((140..=144).contains(&presents_144), "{presents_144}");
+        assert!((58..=60).contains(&presents_60), "{presents_60}");
+    }
+
+    #[test]
+    fn unanswered_callback_times_out() {
+        let requested_at = Instant::now();
+        assert!(frame_ready(None, requested_at));
+        assert!(!frame_ready(
+            Some(requested_at),
+            requested_at + Duration::from_millis(499)
+        ));
+        assert!(frame_ready(
+            Some(requested_at),
+            requested_at + FRAME_CALLBACK_TIMEOUT
+        ));
+    }
+}
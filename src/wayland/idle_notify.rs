@@ -0,0 +1,86 @@
+//! User idle tracking via `ext_idle_notifier_v1`.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1, ext_idle_notifier_v1,
+};
+
+use super::WaylandAppState;
+
+impl WaylandAppState {
+    /// Keeps the idle notification in line with the requested `timeout`.
+    ///
+    /// The notification is tied to one seat; the lowest-numbered seat is used.
+    pub(crate) fn sync_idle_notification(
+        &mut self,
+        timeout: Option<Duration>,
+        qh: &QueueHandle<Self>,
+    ) {
+        let current = self.idle_notification.as_ref().map(|(_, t)| *t);
+        if current == timeout {
+            return;
+        }
+        self.release_idle_notification();
+
+        let (Some(timeout), Some((notifier, _))) = (timeout, self.idle_notifier.as_ref()) else {
+            return;
+        };
+        let Some(seat) = self
+            .seats
+            .iter()
+            .min_by_key(|(name, _)| **name)
+            .map(|(_, seat)| seat)
+        else {
+            return;
+        };
+        let timeout_ms = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        let notification = notifier.get_idle_notification(timeout_ms, seat, qh, ());
+        self.idle_notification = Some((notification, timeout));
+    }
+
+    pub(crate) fn release_idle_notification(&mut self) {
+        if let Some((notification, _)) = self.idle_notification.take() {
+            notification.destroy();
+        }
+        self.idled = false;
+    }
+}
+
+impl Dispatch<ext_idle_notifier_v1::ExtIdleNotifierV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _notifier: &ext_idle_notifier_v1::ExtIdleNotifierV1,
+        _event: ext_idle_notifier_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the notifier has no events.
+    }
+}
+
+impl Dispatch<ext_idle_notification_v1::ExtIdleNotificationV1, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _notification: &ext_idle_notification_v1::ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => {
+                debug!("User went idle");
+                state.idled = true;
+            }
+            ext_idle_notification_v1::Event::Resumed => {
+                debug!("User resumed");
+                state.idled = false;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Keyboard focus tracking via `wl_keyboard`.
+
+use bevy::prelude::*;
+use wayland_client::{
+    Connection, Dispatch, Proxy, QueueHandle,
+    protocol::{wl_keyboard, wl_seat},
+};
+
+use super::WaylandAppState;
+use crate::{WallpaperFocusEvent, WallpaperKeyboardFocus};
+
+impl WaylandAppState {
+    pub(crate) fn bind_keyboard(&mut self, seat: &wl_seat::WlSeat, qh: &QueueHandle<Self>) {
+        let seat_id = seat.id().protocol_id();
+        self.keyboards
+            .entry(seat_id)
+            .or_insert_with(|| seat.get_keyboard(qh, seat_id));
+    }
+
+    pub(crate) fn release_keyboard(&mut self, seat_id: u32) {
+        if let Some(keyboard) = self.keyboards.remove(&seat_id) {
+            keyboard.release();
+        }
+        if let Some(output) = self.keyboard_focus.remove(&seat_id) {
+            self.pending_focus_events.push(WallpaperFocusEvent {
+                gained: false,
+                output: Some(output),
+            });
+        }
+    }
+
+    /// Focus across all seats; with several focused seats the output of the
+    /// lowest seat id is reported.
+    pub(crate) fn wallpaper_keyboard_focus(&self) -> WallpaperKeyboardFocus {
+        WallpaperKeyboardFocus {
+            has_focus: !self.keyboard_focus.is_empty(),
+            focused_output: self
+                .keyboard_focus
+                .iter()
+                .min_by_key(|(seat, _)| **seat)
+                .map(|(_, output)| *output),
+        }
+    }
+}
+
+impl Dispatch<wl_keyboard::WlKeyboard, u32> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _keyboard: &wl_keyboard::WlKeyboard,
+        event: wl_keyboard::Event,
+        seat_id: &u32,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wl_keyboard::Event::Enter { surface, .. } => {
+                let Some(output) = state
+                    .surface_to_output
+                    .get(&surface.id().protocol_id())
+                    .copied()
+                else {
+                    return;
+                };
+                debug!("Keyboard focus entered output {output} (seat {seat_id})");
+                state.keyboard_focus.insert(*seat_id, output);
+                state.pending_focus_events.push(WallpaperFocusEvent {
+                    gained: true,
+                    output: Some(output),
+                });
+            }
+            wl_keyboard::Event::Leave { .. } => {
+                if let Some(output) = state.keyboard_focus.remove(seat_id) {
+                    debug!("Keyboard focus left output {output} (seat {seat_id})");
+                    state.pending_focus_events.push(WallpaperFocusEvent {
+                        gained: false,
+                        output: Some(output),
+                    });
+                }
+            }
+            // The keymap fd is closed when dropped; key events aren't
+            // exposed yet.
+            _ => {}
+        }
+    }
+}
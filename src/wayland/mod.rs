@@ -1,8 +1,25 @@
 pub mod backend;
+mod color_management;
+mod commit_pacing;
+mod content_type;
+mod cursor;
+mod dispatch;
+mod frame_pacing;
+mod idle_notify;
+mod keyboard;
+mod output_management;
+mod output_power;
+mod placeholder;
+mod pointer_constraints;
+mod pointer_gestures;
 pub mod render;
+mod session_lock;
 pub mod surface;
+mod tablet;
+mod tearing;
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 use bevy::prelude::*;
 use wayland_client::Proxy;
@@ -10,50 +27,249 @@ use wayland_client::protocol::wl_display;
 use wayland_client::{
     Connection, Dispatch, QueueHandle,
     protocol::{
-        wl_callback, wl_compositor, wl_output, wl_pointer, wl_registry, wl_seat, wl_surface,
+        wl_callback, wl_compositor, wl_keyboard, wl_output, wl_pointer, wl_region, wl_registry,
+        wl_seat, wl_shm, wl_surface,
     },
 };
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1, ext_idle_notifier_v1,
+};
+use wayland_protocols::ext::session_lock::v1::client::{
+    ext_session_lock_manager_v1, ext_session_lock_v1,
+};
+use wayland_protocols::wp::color_management::v1::client::wp_color_management_surface_v1;
+use wayland_protocols::wp::commit_timing::v1::client::{
+    wp_commit_timer_v1, wp_commit_timing_manager_v1,
+};
+use wayland_protocols::wp::content_type::v1::client::{
+    wp_content_type_manager_v1, wp_content_type_v1,
+};
+use wayland_protocols::wp::cursor_shape::v1::client::{
+    wp_cursor_shape_device_v1, wp_cursor_shape_manager_v1,
+};
+use wayland_protocols::wp::fifo::v1::client::{wp_fifo_manager_v1, wp_fifo_v1};
+use wayland_protocols::wp::pointer_constraints::zv1::client::zwp_pointer_constraints_v1;
+use wayland_protocols::wp::pointer_gestures::zv1::client::zwp_pointer_gestures_v1;
+use wayland_protocols::wp::presentation_time::client::{wp_presentation, wp_presentation_feedback};
+use wayland_protocols::wp::single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1;
+use wayland_protocols::wp::tablet::zv2::client::{zwp_tablet_manager_v2, zwp_tablet_seat_v2};
+use wayland_protocols::wp::tearing_control::v1::client::{
+    wp_tearing_control_manager_v1, wp_tearing_control_v1,
+};
+use wayland_protocols::wp::viewporter::client::{wp_viewport, wp_viewporter};
 use wayland_protocols::xdg::xdg_output::zv1::client::{zxdg_output_manager_v1, zxdg_output_v1};
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
+use wayland_protocols_wlr::output_management::v1::client::zwlr_output_manager_v1;
+use wayland_protocols_wlr::output_power_management::v1::client::{
+    zwlr_output_power_manager_v1, zwlr_output_power_v1,
+};
 
+use self::color_management::ColorManagement;
+use self::output_management::OutputHeads;
+use self::pointer_constraints::ActivePointerConstraint;
+use self::pointer_gestures::PointerGestures;
+use self::session_lock::LockSurface;
 use self::surface::WaylandSurfaceHandles;
+pub(crate) use self::tablet::{
+    PendingStylusEvent, PendingStylusEventKind, StylusFocus, TabletTool,
+};
 
 #[derive(Clone, Debug)]
 pub(crate) struct PointerFocus {
     output: u32,
     position: Vec2,
+    /// Serial of the enter event, needed to change the cursor.
+    serial: u32,
 }
 
 #[derive(Resource)]
 pub(crate) struct WaylandAppState {
     pub closed: bool,
+    /// Leave the opaque region unset ([`crate::WallpaperAlphaMode::Transparent`]).
+    pub transparent: bool,
+    /// Bind `wl_seat` globals; without them no input reaches the wallpaper.
+    pub capture_input: bool,
+    /// Cursor applied when a pointer enters a wallpaper surface.
+    pub cursor: crate::WallpaperCursor,
     pub pending_surface_config: Vec<WaylandSurfaceConfig>,
     /// Outputs whose geometry/scale changed since last frame.
     pub dirty_outputs: HashSet<u32>,
     pub pending_pointer_events: Vec<PendingPointerEvent>,
-    pub pointer_focus: Option<PointerFocus>,
+    /// Pointer events of the current `wl_pointer.frame`, keyed by wl_seat
+    /// protocol id; flushed into `pending_pointer_events` on `frame`.
+    pub pointer_frames: HashMap<u32, Vec<PendingPointerEvent>>,
+    /// Output chosen for [`crate::WallpaperTargetMonitor::Primary`], updated
+    /// by the main world every tick.
+    pub primary_output: Option<u32>,
+    /// Pointer focus per seat (keyed by wl_seat protocol id).
+    pub pointer_focus: HashMap<u32, PointerFocus>,
     // Wayland objects
     pub display: wl_display::WlDisplay,
     pub compositor: Option<(wl_compositor::WlCompositor, u32)>,
     pub layer_shell: Option<(zwlr_layer_shell_v1::ZwlrLayerShellV1, u32)>,
     pub seats: HashMap<u32, wl_seat::WlSeat>,
     pub pointers: HashMap<u32, wl_pointer::WlPointer>,
+    /// Keyboards keyed by wl_seat protocol id.
+    pub keyboards: HashMap<u32, wl_keyboard::WlKeyboard>,
+    /// Output whose surface has keyboard focus, keyed by wl_seat protocol id.
+    pub keyboard_focus: HashMap<u32, u32>,
+    pub pending_focus_events: Vec<crate::WallpaperFocusEvent>,
     pub outputs: HashMap<u32, wl_output::WlOutput>,
     pub output_info: HashMap<u32, OutputInfo>,
+    /// Advertised globals (interface, version) keyed by registry name.
+    pub globals: BTreeMap<u32, (String, u32)>,
     pub output_order: Vec<u32>,
     pub surfaces: HashMap<u32, OutputSurface>,
+    /// Old surfaces of a target switch that is still in progress.
+    pub surface_switch: Option<SurfaceSwitch>,
     pub surface_to_output: HashMap<u32, u32>,
+    /// Outputs whose layer surface went away outside of surface management
+    /// (closed by the compositor or output removed) since last frame.
+    pub dropped_surfaces: Vec<u32>,
+    /// Outputs whose layer surface the compositor closed; no new surface is
+    /// created for them until the output is removed and re-added.
+    pub closed_outputs: HashSet<u32>,
     pub xdg_output_manager: Option<zxdg_output_manager_v1::ZxdgOutputManagerV1>,
     pub xdg_outputs: HashMap<u32, zxdg_output_v1::ZxdgOutputV1>,
+    pub presentation: Option<wp_presentation::WpPresentation>,
+    pub tablet_manager: Option<(zwp_tablet_manager_v2::ZwpTabletManagerV2, u32)>,
+    /// Tablet seats keyed by wl_seat protocol id.
+    pub tablet_seats: HashMap<u32, zwp_tablet_seat_v2::ZwpTabletSeatV2>,
+    /// Known tablet tools, keyed by tool protocol id.
+    pub tablet_tools: HashMap<u32, TabletTool>,
+    /// Tablet tools in proximity, keyed by tool protocol id.
+    pub stylus_focus: HashMap<u32, StylusFocus>,
+    pub pending_stylus_events: Vec<PendingStylusEvent>,
+    pub pointer_gestures_manager: Option<zwp_pointer_gestures_v1::ZwpPointerGesturesV1>,
+    /// Swipe / pinch objects keyed by wl_seat protocol id.
+    pub pointer_gestures: HashMap<u32, PointerGestures>,
+    pub pending_gesture_events: Vec<crate::WallpaperGestureEvent>,
+    pub pointer_constraints_manager: Option<zwp_pointer_constraints_v1::ZwpPointerConstraintsV1>,
+    pub pointer_constraint: Option<ActivePointerConstraint>,
+    pub output_power_manager: Option<zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1>,
+    /// Power mode trackers keyed by output registry name.
+    pub output_power: HashMap<u32, zwlr_output_power_v1::ZwlrOutputPowerV1>,
+    /// Outputs the compositor reported as powered off.
+    pub powered_off: HashSet<u32>,
+    pub output_manager: Option<zwlr_output_manager_v1::ZwlrOutputManagerV1>,
+    pub output_heads: OutputHeads,
+    pub idle_notifier: Option<(ext_idle_notifier_v1::ExtIdleNotifierV1, u32)>,
+    /// Active idle notification and the timeout it was created with.
+    pub idle_notification: Option<(ext_idle_notification_v1::ExtIdleNotificationV1, Duration)>,
+    /// Whether the compositor reported the user as idle.
+    pub idled: bool,
+    /// Color shown before the first frame; `None` disables the placeholder.
+    pub placeholder_color: Option<Srgba>,
+    pub single_pixel_buffer_manager:
+        Option<wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1>,
+    pub viewporter: Option<wp_viewporter::WpViewporter>,
+    pub shm: Option<wl_shm::WlShm>,
+    pub cursor_shape_manager: Option<wp_cursor_shape_manager_v1::WpCursorShapeManagerV1>,
+    /// Cursor shape devices keyed by wl_seat protocol id.
+    pub cursor_shape_devices: HashMap<u32, wp_cursor_shape_device_v1::WpCursorShapeDeviceV1>,
+    pub tearing_manager: Option<wp_tearing_control_manager_v1::WpTearingControlManagerV1>,
+    pub content_type_manager: Option<wp_content_type_manager_v1::WpContentTypeManagerV1>,
+    pub color_management: ColorManagement,
+    pub fifo_manager: Option<wp_fifo_manager_v1::WpFifoManagerV1>,
+    pub commit_timing_manager: Option<wp_commit_timing_manager_v1::WpCommitTimingManagerV1>,
+    pub session_lock_manager: Option<ext_session_lock_manager_v1::ExtSessionLockManagerV1>,
+    pub session_lock: Option<ext_session_lock_v1::ExtSessionLockV1>,
+    /// Whether the compositor confirmed the current session lock.
+    pub session_locked: bool,
+    /// Lock surfaces keyed by output registry name.
+    pub lock_surfaces: HashMap<u32, LockSurface>,
+    /// Last layer surface configuration per output, restored after unlocking.
+    pub layer_configs: HashMap<u32, WaylandSurfaceConfig>,
+    pub pending_lock_events: Vec<crate::WallpaperLockEvent>,
+    /// Outputs with an outstanding presentation feedback request.
+    pub pending_feedback: HashSet<u32>,
+    pub presented_frames: Vec<PresentedFrame>,
+    /// Presentation time of the last frame shown per output.
+    pub last_presented: HashMap<u32, Duration>,
+    /// Number of feedbacks discarded since last frame.
+    pub discarded_frames: u64,
 }
 
 pub(crate) struct OutputSurface {
     pub surface: wl_surface::WlSurface,
     pub layer_surface: zwlr_layer_surface_v1::ZwlrLayerSurfaceV1,
+    /// Size the opaque region was last set for.
+    pub opaque_size: Option<(u32, u32)>,
+    /// Present while the async presentation hint is set.
+    pub tearing: Option<wp_tearing_control_v1::WpTearingControlV1>,
+    /// Content type object and the hint last set through it.
+    pub content_type: Option<(
+        wp_content_type_v1::WpContentTypeV1,
+        crate::WallpaperContentType,
+    )>,
+    /// Color management surface and the color space it was tagged with.
+    pub color_surface: Option<(
+        wp_color_management_surface_v1::WpColorManagementSurfaceV1,
+        crate::WallpaperColorSpace,
+    )>,
+    /// Present while [`crate::WallpaperFifoPacing`] applies.
+    pub fifo: Option<wp_fifo_v1::WpFifoV1>,
+    pub commit_timer: Option<wp_commit_timer_v1::WpCommitTimerV1>,
+    pub layer: crate::WaylandLayer,
+    /// Keyboard interactivity last requested for the layer surface.
+    pub keyboard_interactivity: crate::WaylandKeyboardInteractivity,
+    /// Exclusive zone and margins last requested for the layer surface.
+    pub exclusive_zone: i32,
+    pub margins: crate::WaylandMargins,
+    /// Viewport stretching the placeholder buffer until the first frame.
+    pub placeholder: Option<wp_viewport::WpViewport>,
+    /// Whether wgpu has presented to the surface.
+    pub presented: bool,
+    /// Buffer scale last set on the surface.
+    pub buffer_scale: i32,
+    /// `wl_output` protocol ids the surface is on; `None` until the
+    /// compositor first reports one.
+    pub entered_outputs: Option<HashSet<u32>>,
+}
+
+impl OutputSurface {
+    pub(crate) fn destroy(self) {
+        if let Some(tearing) = self.tearing {
+            tearing.destroy();
+        }
+        if let Some((content_type, _)) = self.content_type {
+            content_type.destroy();
+        }
+        if let Some((color_surface, _)) = self.color_surface {
+            color_surface.destroy();
+        }
+        if let Some(fifo) = self.fifo {
+            fifo.destroy();
+        }
+        if let Some(commit_timer) = self.commit_timer {
+            commit_timer.destroy();
+        }
+        if let Some(viewport) = self.placeholder {
+            viewport.destroy();
+        }
+        self.layer_surface.destroy();
+        self.surface.destroy();
+    }
+}
+
+/// Surfaces left on outputs that are no longer targeted, kept up until the
+/// surfaces replacing them have a frame so the switch doesn't flash.
+pub(crate) struct SurfaceSwitch {
+    pub retiring: Vec<(u32, OutputSurface)>,
+    pub started: Instant,
+}
+
+impl SurfaceSwitch {
+    fn contains(&self, layer_surface: &zwlr_layer_surface_v1::ZwlrLayerSurfaceV1) -> bool {
+        self.retiring
+            .iter()
+            .any(|(_, surface)| surface.layer_surface == *layer_surface)
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct PendingPointerEvent {
+    seat: u32,
     output: u32,
     position: Vec2,
     offset: Vec2,
@@ -62,53 +278,386 @@ pub(crate) struct PendingPointerEvent {
 
 #[derive(Clone, Debug)]
 pub(crate) enum PendingPointerEventKind {
+    Enter,
+    Leave,
     Motion,
     Button {
         button: Option<MouseButton>,
         pressed: bool,
     },
+    Scroll {
+        discrete: Vec2,
+        smooth: Vec2,
+    },
 }
 
 impl PendingPointerEventKind {
     /// Returns button state transition if this event represents a button action.
     fn button_change(&self) -> Option<(Option<MouseButton>, bool)> {
         match self {
-            PendingPointerEventKind::Motion => None,
             PendingPointerEventKind::Button { button, pressed } => Some((*button, *pressed)),
+            _ => None,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+impl WaylandAppState {
+    /// Queues scrolling along `axis` for the focused output of `seat`.
+    fn push_scroll(
+        &mut self,
+        seat: u32,
+        axis: wayland_client::WEnum<wl_pointer::Axis>,
+        discrete: f32,
+        smooth: f32,
+    ) {
+        let mask = match axis {
+            wayland_client::WEnum::Value(wl_pointer::Axis::VerticalScroll) => Vec2::Y,
+            wayland_client::WEnum::Value(wl_pointer::Axis::HorizontalScroll) => Vec2::X,
+            _ => return,
+        };
+        let Some(focus) = self.pointer_focus.get(&seat) else {
+            return;
+        };
+        let offset = self.surface_origin(focus.output).as_vec2();
+        self.push_pointer_event(PendingPointerEvent {
+            seat,
+            output: focus.output,
+            position: focus.position,
+            offset,
+            kind: PendingPointerEventKind::Scroll {
+                discrete: mask * discrete,
+                smooth: mask * smooth,
+            },
+        });
+    }
+
+    /// Buffers a pointer event until its `wl_pointer.frame`, or queues it
+    /// right away for pointers older than version 5, which have no frames.
+    fn push_pointer_event(&mut self, event: PendingPointerEvent) {
+        let framed = self
+            .pointers
+            .get(&event.seat)
+            .is_some_and(|pointer| pointer.version() >= 5);
+        if framed {
+            self.pointer_frames
+                .entry(event.seat)
+                .or_default()
+                .push(event);
+        } else {
+            self.pending_pointer_events.push(event);
+        }
+    }
+
+    /// Queues the events of a finished `wl_pointer.frame` as one logical
+    /// update; see [`coalesce_pointer_frame`].
+    fn flush_pointer_frame(&mut self, seat: u32) {
+        let Some(events) = self.pointer_frames.remove(&seat) else {
+            return;
+        };
+        self.pending_pointer_events
+            .extend(coalesce_pointer_frame(events));
+    }
+
+    /// Forgets the pointer of `seat`, including a half-received frame.
+    fn release_pointer(&mut self, seat: u32) {
+        if let Some(pointer) = self.pointers.remove(&seat) {
+            pointer.release();
+        }
+        self.pointer_frames.remove(&seat);
+    }
+
+    /// Outputs the layer surface behind `surface` is on, if it is one.
+    fn entered_outputs_mut(
+        &mut self,
+        surface: &wl_surface::WlSurface,
+    ) -> Option<&mut HashSet<u32>> {
+        let output = self.surface_to_output.get(&surface.id().protocol_id())?;
+        let entry = self.surfaces.get_mut(output)?;
+        // Lock surfaces share `surface_to_output`.
+        if entry.surface != *surface {
+            return None;
+        }
+        Some(entry.entered_outputs.get_or_insert_default())
+    }
+
+    /// Outputs whose layer surface the compositor reported on, and off, screen.
+    pub(crate) fn surface_visibility(&self) -> (HashSet<u32>, HashSet<u32>) {
+        let mut visible = HashSet::new();
+        let mut occluded = HashSet::new();
+        for (output, surface) in &self.surfaces {
+            match &surface.entered_outputs {
+                Some(entered) if entered.is_empty() => occluded.insert(*output),
+                Some(_) => visible.insert(*output),
+                None => false,
+            };
+        }
+        (visible, occluded)
+    }
+}
+
+/// Folds the events of one `wl_pointer.frame`: motion is merged into the
+/// preceding enter or motion and axis events are summed, while
+/// enter/leave/button order is kept.
+fn coalesce_pointer_frame(events: Vec<PendingPointerEvent>) -> Vec<PendingPointerEvent> {
+    let mut coalesced: Vec<PendingPointerEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        if let Some(prev) = coalesced
+            .last_mut()
+            .filter(|prev| prev.output == event.output)
+        {
+            match (&mut prev.kind, &event.kind) {
+                (
+                    PendingPointerEventKind::Enter | PendingPointerEventKind::Motion,
+                    PendingPointerEventKind::Motion,
+                ) => {
+                    prev.position = event.position;
+                    prev.offset = event.offset;
+                    continue;
+                }
+                (
+                    PendingPointerEventKind::Scroll { discrete, smooth },
+                    PendingPointerEventKind::Scroll {
+                        discrete: more_discrete,
+                        smooth: more_smooth,
+                    },
+                ) => {
+                    *discrete += *more_discrete;
+                    *smooth += *more_smooth;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        coalesced.push(event);
+    }
+    coalesced
+}
+
+/// Maps a `wl_pointer.button` code (evdev `BTN_*`) to a Bevy button.
+fn map_pointer_button(code: u32) -> Option<MouseButton> {
+    match code {
+        272 => Some(MouseButton::Left),
+        273 => Some(MouseButton::Right),
+        274 => Some(MouseButton::Middle),
+        275 => Some(MouseButton::Back),
+        276 => Some(MouseButton::Forward),
+        other => u16::try_from(other).ok().map(MouseButton::Other),
+    }
+}
+
+/// A `wp_presentation_feedback::Event::Presented` for one output.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PresentedFrame {
+    pub output: u32,
+    pub presented_at: Duration,
+    pub refresh_ns: u32,
+    pub flags: u32,
+}
+
+/// What is known about one output, keyed by its `wl_output` registry name.
+///
+/// `x`/`y`/`width`/`height` are logical (compositor space) values derived by
+/// [`OutputInfo::normalize`]; the raw protocol values are kept alongside.
+#[derive(Clone, Debug, Default)]
 pub(crate) struct OutputInfo {
     pub x: i32,
     pub y: i32,
     pub width: i32,
     pub height: i32,
     pub scale: i32,
+    /// Connector name from `wl_output` v4 (e.g. `DP-1`).
+    pub name: Option<String>,
+    /// Name from `zxdg_output_v1` (v2+), usually the connector name too.
+    pub logical_name: Option<String>,
+    /// Human-readable description, e.g. the monitor model, from
+    /// `zxdg_output_v1` or `wl_output` v4.
+    pub description: Option<String>,
+    /// Current mode in physical pixels.
+    pub mode: Option<UVec2>,
+    /// Refresh rate of the current mode in mHz; 0 until reported.
+    pub refresh_rate_mhz: i32,
+    /// Position from `wl_output::geometry`.
+    pub geometry_position: IVec2,
+    /// Panel size from `wl_output::geometry`; `None` when reported as zero.
+    pub physical_size_mm: Option<UVec2>,
+    /// Whether `wl_output::geometry` reported a 90/270 degree transform.
+    pub rotated: bool,
+    /// Position from `zxdg_output_v1`, preferred over the geometry.
+    pub logical_position: Option<IVec2>,
+    /// Size from `zxdg_output_v1`, preferred over mode / scale.
+    pub logical_size: Option<IVec2>,
+}
+
+/// Resolves a layer surface configure size. Zero means the client picks,
+/// which for a wallpaper is the output's logical size less the surface
+/// `margins`; 1 is the last resort while that isn't known yet.
+fn configured_size(
+    width: u32,
+    height: u32,
+    info: Option<&OutputInfo>,
+    margins: crate::WaylandMargins,
+) -> (u32, u32) {
+    let output = info.map_or(IVec2::ZERO, |info| {
+        IVec2::new(
+            info.width - margins.left - margins.right,
+            info.height - margins.top - margins.bottom,
+        )
+    });
+    let pick = |configured: u32, output: i32| match configured {
+        0 => u32::try_from(output).unwrap_or(0).max(1),
+        configured => configured,
+    };
+    (pick(width, output.x), pick(height, output.y))
+}
+
+impl OutputInfo {
+    /// Connector name, from `wl_output` v4 or else xdg-output.
+    pub(crate) fn connector_name(&self) -> Option<&str> {
+        self.name.as_deref().or(self.logical_name.as_deref())
+    }
+
+    /// Recomputes the logical geometry from whatever has been reported.
+    ///
+    /// Without xdg-output the size is the current mode divided by the
+    /// integer scale (swapped for rotated outputs), so it shares units with
+    /// the positions and the layer surface sizes.
+    pub(crate) fn normalize(&mut self) {
+        let position = self.logical_position.unwrap_or(self.geometry_position);
+        self.x = position.x;
+        self.y = position.y;
+
+        let size = self.logical_size.or_else(|| {
+            let mode = self.mode?.as_ivec2() / self.scale.max(1);
+            Some(if self.rotated { mode.yx() } else { mode })
+        });
+        if let Some(size) = size {
+            self.width = size.x;
+            self.height = size.y;
+        }
+    }
 }
 
 impl WaylandAppState {
     pub(crate) fn new(display: wl_display::WlDisplay) -> Self {
         Self {
             closed: false,
+            transparent: false,
+            capture_input: true,
+            cursor: crate::WallpaperCursor::Default,
             pending_surface_config: Vec::new(),
             dirty_outputs: HashSet::new(),
             pending_pointer_events: Vec::new(),
-            pointer_focus: None,
+            pointer_frames: HashMap::new(),
+            primary_output: None,
+            pointer_focus: HashMap::new(),
             display,
             compositor: None,
             layer_shell: None,
             seats: HashMap::new(),
             pointers: HashMap::new(),
+            keyboards: HashMap::new(),
+            keyboard_focus: HashMap::new(),
+            pending_focus_events: Vec::new(),
             outputs: HashMap::new(),
             output_info: HashMap::new(),
+            globals: BTreeMap::new(),
             output_order: Vec::new(),
             surfaces: HashMap::new(),
+            surface_switch: None,
             surface_to_output: HashMap::new(),
+            dropped_surfaces: Vec::new(),
+            closed_outputs: HashSet::new(),
             xdg_output_manager: None,
             xdg_outputs: HashMap::new(),
+            presentation: None,
+            pending_feedback: HashSet::new(),
+            tablet_manager: None,
+            tablet_seats: HashMap::new(),
+            tablet_tools: HashMap::new(),
+            stylus_focus: HashMap::new(),
+            pending_stylus_events: Vec::new(),
+            pointer_gestures_manager: None,
+            pointer_gestures: HashMap::new(),
+            pending_gesture_events: Vec::new(),
+            pointer_constraints_manager: None,
+            pointer_constraint: None,
+            output_power_manager: None,
+            output_power: HashMap::new(),
+            powered_off: HashSet::new(),
+            output_manager: None,
+            output_heads: OutputHeads::default(),
+            idle_notifier: None,
+            idle_notification: None,
+            idled: false,
+            placeholder_color: None,
+            single_pixel_buffer_manager: None,
+            viewporter: None,
+            shm: None,
+            cursor_shape_manager: None,
+            cursor_shape_devices: HashMap::new(),
+            tearing_manager: None,
+            content_type_manager: None,
+            color_management: ColorManagement::default(),
+            fifo_manager: None,
+            commit_timing_manager: None,
+            session_lock_manager: None,
+            session_lock: None,
+            session_locked: false,
+            lock_surfaces: HashMap::new(),
+            layer_configs: HashMap::new(),
+            pending_lock_events: Vec::new(),
+            presented_frames: Vec::new(),
+            last_presented: HashMap::new(),
+            discarded_frames: 0,
+        }
+    }
+
+    /// Top-left corner of the wallpaper surface of `output` in the global
+    /// logical space: the output position plus the layer surface margins.
+    /// Lock surfaces always cover the whole output.
+    pub(crate) fn surface_origin(&self, output: u32) -> IVec2 {
+        let position = self
+            .output_info
+            .get(&output)
+            .map_or(IVec2::ZERO, |info| IVec2::new(info.x, info.y));
+        match self.surfaces.get(&output) {
+            Some(surface) if !self.lock_surfaces.contains_key(&output) => {
+                position + IVec2::new(surface.margins.left, surface.margins.top)
+            }
+            _ => position,
+        }
+    }
+
+    /// Whether the global position of `output` has been reported. When
+    /// xdg-output is bound, `wl_output.geometry` may arrive well before the
+    /// logical position and would place the surface at the wrong origin.
+    pub(crate) fn output_position_known(&self, output: u32) -> bool {
+        !self.xdg_outputs.contains_key(&output)
+            || self
+                .output_info
+                .get(&output)
+                .is_none_or(|info| info.logical_position.is_some())
+    }
+
+    /// Drains the pointer events that can be resolved to global coordinates,
+    /// filling in the surface origin as of now. Events on an output whose
+    /// position is still unknown stay queued, together with every later event
+    /// from the same seat so the order per seat is preserved.
+    pub(crate) fn take_pointer_events(&mut self) -> Vec<PendingPointerEvent> {
+        let mut ready = Vec::with_capacity(self.pending_pointer_events.len());
+        let mut held = Vec::new();
+        let mut blocked_seats = HashSet::new();
+        for mut event in std::mem::take(&mut self.pending_pointer_events) {
+            if blocked_seats.contains(&event.seat) || !self.output_position_known(event.output) {
+                blocked_seats.insert(event.seat);
+                held.push(event);
+                continue;
+            }
+            event.offset = self.surface_origin(event.output).as_vec2();
+            ready.push(event);
         }
+        self.pending_pointer_events = held;
+        ready
     }
 
     pub(crate) fn is_running(&self) -> bool {
@@ -122,6 +671,34 @@ impl WaylandAppState {
     pub(crate) fn take_surface_config(&mut self) -> Vec<WaylandSurfaceConfig> {
         std::mem::take(&mut self.pending_surface_config)
     }
+
+    /// Physical pixels per logical pixel on `output`; 1 until reported.
+    pub(crate) fn output_scale(&self, output: u32) -> f64 {
+        self.output_info
+            .get(&output)
+            .map_or(1.0, |info| f64::from(info.scale.max(1)))
+    }
+
+    /// Sets the buffer scale of every layer and lock surface, so buffers
+    /// rendered at `scale` map onto their logical size.
+    pub(crate) fn sync_buffer_scale(&mut self, scale: f64) {
+        let scale = scale.round().max(1.0) as i32;
+        let layer = self
+            .surfaces
+            .values_mut()
+            .map(|entry| (&entry.surface, &mut entry.buffer_scale));
+        let lock = self
+            .lock_surfaces
+            .values_mut()
+            .map(|entry| (&entry.surface, &mut entry.buffer_scale));
+        for (surface, current) in layer.chain(lock) {
+            // `set_buffer_scale` needs wl_surface v3.
+            if *current != scale && surface.version() >= 3 {
+                surface.set_buffer_scale(scale);
+                *current = scale;
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -132,6 +709,8 @@ pub(crate) struct WaylandSurfaceConfig {
     pub height: u32,
     pub offset_x: i32,
     pub offset_y: i32,
+    /// Scale of the output; the size and offset stay in logical pixels.
+    pub scale: f64,
 }
 
 impl Dispatch<wl_registry::WlRegistry, ()> for WaylandAppState {
@@ -151,20 +730,25 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandAppState {
             } => {
                 let _span_guard =
                     trace_span!("wl_registry::Event::Global", name, interface, version).entered();
+                state.globals.insert(name, (interface.clone(), version));
                 match interface.as_str() {
                     "wl_compositor" => {
                         info!("Compositor found: {} (version {})", name, version);
                         state.compositor = Some((registry.bind(name, version, qh, ()), name));
                     }
+                    "wl_seat" if !state.capture_input => {
+                        debug!("Seat {name} left unbound; input capture is disabled");
+                    }
                     "wl_seat" => {
                         info!("Seat found: {} (version {})", name, version);
                         let seat = registry.bind::<wl_seat::WlSeat, _, _>(name, version, qh, ());
+                        state.bind_tablet_seat(&seat, qh);
                         state.seats.insert(name, seat);
                     }
                     "wl_output" => {
                         info!("Output found: {} (version {})", name, version);
                         let output =
-                            registry.bind::<wl_output::WlOutput, _, _>(name, version, qh, ());
+                            registry.bind::<wl_output::WlOutput, _, _>(name, version, qh, name);
                         state.outputs.insert(name, output);
                         state.output_order.push(name);
                     }
@@ -172,6 +756,100 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandAppState {
                         info!("LayerShell found: {} (version {})", name, version);
                         state.layer_shell = Some((registry.bind(name, version, qh, ()), name));
                     }
+                    "zwp_tablet_manager_v2" => {
+                        info!("Tablet manager found: {} (version {})", name, version);
+                        let version = version.min(tablet::TABLET_MANAGER_VERSION);
+                        state.tablet_manager = Some((registry.bind(name, version, qh, ()), name));
+                        let seats: Vec<_> = state.seats.values().cloned().collect();
+                        for seat in &seats {
+                            state.bind_tablet_seat(seat, qh);
+                        }
+                    }
+                    "zwp_pointer_gestures_v1" => {
+                        info!("Pointer gestures found: {} (version {})", name, version);
+                        let version = version.min(pointer_gestures::POINTER_GESTURES_VERSION);
+                        state.pointer_gestures_manager = Some(registry.bind(name, version, qh, ()));
+                        let seat_ids: Vec<u32> = state.pointers.keys().copied().collect();
+                        for seat_id in seat_ids {
+                            state.bind_pointer_gestures(seat_id, qh);
+                        }
+                    }
+                    "zwp_pointer_constraints_v1" => {
+                        info!("Pointer constraints found: {} (version {})", name, version);
+                        state.pointer_constraints_manager = Some(registry.bind(name, 1, qh, ()));
+                    }
+                    "zwlr_output_power_manager_v1" => {
+                        info!("Output power manager found: {} (version {})", name, version);
+                        state.output_power_manager = Some(registry.bind(name, version, qh, ()));
+                        let outputs: Vec<u32> = state.outputs.keys().copied().collect();
+                        for output in outputs {
+                            state.bind_output_power(output, qh);
+                        }
+                    }
+                    "zwlr_output_manager_v1" => {
+                        info!("Output manager found: {} (version {})", name, version);
+                        state.output_manager = Some(registry.bind(name, version.min(3), qh, ()));
+                    }
+                    "ext_idle_notifier_v1" => {
+                        info!("Idle notifier found: {} (version {})", name, version);
+                        state.idle_notifier =
+                            Some((registry.bind(name, version.min(1), qh, ()), name));
+                    }
+                    "wp_single_pixel_buffer_manager_v1" => {
+                        info!(
+                            "Single pixel buffer manager found: {} (version {})",
+                            name, version
+                        );
+                        state.single_pixel_buffer_manager =
+                            Some(registry.bind(name, version.min(1), qh, ()));
+                    }
+                    "wp_viewporter" => {
+                        info!("Viewporter found: {} (version {})", name, version);
+                        state.viewporter = Some(registry.bind(name, version.min(1), qh, ()));
+                    }
+                    "wl_shm" => {
+                        state.shm = Some(registry.bind(name, version.min(1), qh, ()));
+                    }
+                    "wp_cursor_shape_manager_v1" => {
+                        info!("Cursor shape manager found: {} (version {})", name, version);
+                        state.cursor_shape_manager =
+                            Some(registry.bind(name, version.min(1), qh, ()));
+                    }
+                    "wp_color_manager_v1" => {
+                        info!("Color manager found: {} (version {})", name, version);
+                        state.color_management.manager = Some(registry.bind(name, 1, qh, ()));
+                    }
+                    "wp_content_type_manager_v1" => {
+                        info!("Content type manager found: {} (version {})", name, version);
+                        state.content_type_manager = Some(registry.bind(name, 1, qh, ()));
+                    }
+                    "wp_fifo_manager_v1" => {
+                        info!("Fifo manager found: {} (version {})", name, version);
+                        state.fifo_manager = Some(registry.bind(name, 1, qh, ()));
+                    }
+                    "wp_commit_timing_manager_v1" => {
+                        info!(
+                            "Commit timing manager found: {} (version {})",
+                            name, version
+                        );
+                        state.commit_timing_manager = Some(registry.bind(name, 1, qh, ()));
+                    }
+                    "wp_tearing_control_manager_v1" => {
+                        info!(
+                            "Tearing control manager found: {} (version {})",
+                            name, version
+                        );
+                        state.tearing_manager = Some(registry.bind(name, version.min(1), qh, ()));
+                    }
+                    "ext_session_lock_manager_v1" => {
+                        info!("Session lock manager found: {} (version {})", name, version);
+                        state.session_lock_manager =
+                            Some(registry.bind(name, version.min(1), qh, ()));
+                    }
+                    "wp_presentation" => {
+                        info!("wp_presentation found: {} (version {})", name, version);
+                        state.presentation = Some(registry.bind(name, version, qh, ()));
+                    }
                     "zxdg_output_manager_v1" => {
                         info!("xdg_output_manager found: {} (version {})", name, version);
                         state.xdg_output_manager = Some(registry.bind(name, version, qh, ()));
@@ -181,6 +859,7 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandAppState {
             }
             wl_registry::Event::GlobalRemove { name } => {
                 let _span_guard = trace_span!("wl_registry::Event::GlobalRemove", name).entered();
+                state.globals.remove(&name);
                 if let Some((_, compositor_name)) = &state.compositor
                     && *compositor_name == name
                 {
@@ -189,17 +868,30 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandAppState {
                 }
                 if state.outputs.remove(&name).is_some() {
                     warn!("Output {} removed", name);
-                    state.surfaces.remove(&name);
-                    state.surface_to_output.retain(|_, output| *output != name);
-                    state.output_order.retain(|n| *n != name);
-                    if state
-                        .pointer_focus
-                        .as_ref()
-                        .map(|focus| focus.output == name)
-                        .unwrap_or(false)
+                    state.release_lock_surface(name);
+                    state.layer_configs.remove(&name);
+                    if let Some(surface) = state.surfaces.remove(&name) {
+                        surface.destroy();
+                        state.dropped_surfaces.push(name);
+                    }
+                    if let Some(switch) = state.surface_switch.as_mut()
+                        && let Some(index) = switch
+                            .retiring
+                            .iter()
+                            .position(|(output, _)| *output == name)
                     {
-                        state.pointer_focus = None;
+                        switch.retiring.swap_remove(index).1.destroy();
                     }
+                    state.closed_outputs.remove(&name);
+                    state.output_info.remove(&name);
+                    state.release_output_power(name);
+                    state.surface_to_output.retain(|_, output| *output != name);
+                    state.output_order.retain(|n| *n != name);
+                    state.pending_feedback.remove(&name);
+                    state.last_presented.remove(&name);
+                    state.pointer_focus.retain(|_, focus| focus.output != name);
+                    state.stylus_focus.retain(|_, focus| focus.output != name);
+                    state.keyboard_focus.retain(|_, output| *output != name);
                     if let Some(xdg) = state.xdg_outputs.remove(&name) {
                         xdg.destroy();
                     }
@@ -207,10 +899,28 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandAppState {
                 if let Some(seat) = state.seats.remove(&name) {
                     warn!("Seat {} removed", name);
                     let seat_id = seat.id().protocol_id();
-                    if let Some(pointer) = state.pointers.remove(&seat_id) {
-                        pointer.release();
+                    state.release_cursor_shape_device(seat_id);
+                    state.release_pointer_gestures(seat_id);
+                    state.release_pointer(seat_id);
+                    state.pointer_focus.remove(&seat_id);
+                    state.release_keyboard(seat_id);
+                    if let Some(tablet_seat) = state.tablet_seats.remove(&seat_id) {
+                        tablet_seat.destroy();
+                    }
+                    for tool in state.tablet_tools.values_mut() {
+                        if tool.seat == Some(seat_id) {
+                            tool.seat = None;
+                        }
                     }
                     seat.release();
+                    // The notification may belong to this seat; recreated next tick.
+                    state.release_idle_notification();
+                }
+                if let Some((_, notifier_name)) = &state.idle_notifier
+                    && *notifier_name == name
+                {
+                    state.release_idle_notification();
+                    state.idle_notifier = None;
                 }
                 if let Some((_, layer_shell_name)) = &state.layer_shell
                     && *layer_shell_name == name
@@ -253,15 +963,29 @@ impl Dispatch<wl_seat::WlSeat, ()> for WaylandAppState {
                     wayland_client::WEnum::Value(cap)
                         if cap.contains(wl_seat::Capability::Pointer)
                 );
+                let has_keyboard = matches!(
+                    capabilities,
+                    wayland_client::WEnum::Value(cap)
+                        if cap.contains(wl_seat::Capability::Keyboard)
+                );
                 let seat_id = seat.id().protocol_id();
 
+                if has_keyboard {
+                    state.bind_keyboard(seat, qh);
+                } else {
+                    state.release_keyboard(seat_id);
+                }
+
                 if has_pointer {
                     state
                         .pointers
                         .entry(seat_id)
                         .or_insert_with(|| seat.get_pointer(qh, seat_id));
-                } else if let Some(pointer) = state.pointers.remove(&seat_id) {
-                    pointer.release();
+                    state.bind_pointer_gestures(seat_id, qh);
+                } else if state.pointers.contains_key(&seat_id) {
+                    state.release_cursor_shape_device(seat_id);
+                    state.release_pointer_gestures(seat_id);
+                    state.release_pointer(seat_id);
                 }
             }
             wl_seat::Event::Name { .. } => {}
@@ -275,81 +999,85 @@ impl Dispatch<wl_pointer::WlPointer, u32> for WaylandAppState {
         state: &mut Self,
         _pointer: &wl_pointer::WlPointer,
         event: wl_pointer::Event,
-        _seat_id: &u32,
+        seat_id: &u32,
         _conn: &Connection,
-        _qh: &QueueHandle<Self>,
+        qh: &QueueHandle<Self>,
     ) {
         match event {
             wl_pointer::Event::Enter {
+                serial,
                 surface,
                 surface_x,
                 surface_y,
-                ..
             } => {
+                state.apply_cursor(*seat_id, serial, qh);
                 let output = state
                     .surface_to_output
                     .get(&surface.id().protocol_id())
                     .copied()
                     .unwrap_or(u32::MAX);
-                let offset = state
-                    .output_info
-                    .get(&output)
-                    .map(|info| Vec2::new(info.x as f32, info.y as f32))
-                    .unwrap_or(Vec2::ZERO);
+                let offset = state.surface_origin(output).as_vec2();
                 let position = Vec2::new(surface_x as f32, surface_y as f32);
-                state.pointer_focus = Some(PointerFocus { output, position });
-                state.pending_pointer_events.push(PendingPointerEvent {
+                state.pointer_focus.insert(
+                    *seat_id,
+                    PointerFocus {
+                        output,
+                        position,
+                        serial,
+                    },
+                );
+                state.push_pointer_event(PendingPointerEvent {
+                    seat: *seat_id,
                     output,
                     position,
                     offset,
-                    kind: PendingPointerEventKind::Motion,
+                    kind: PendingPointerEventKind::Enter,
                 });
             }
             wl_pointer::Event::Leave { .. } => {
-                state.pointer_focus = None;
+                if let Some(focus) = state.pointer_focus.remove(seat_id) {
+                    let offset = state.surface_origin(focus.output).as_vec2();
+                    state.push_pointer_event(PendingPointerEvent {
+                        seat: *seat_id,
+                        output: focus.output,
+                        position: focus.position,
+                        offset,
+                        kind: PendingPointerEventKind::Leave,
+                    });
+                }
             }
             wl_pointer::Event::Motion {
                 surface_x,
                 surface_y,
                 ..
             } => {
-                if let Some(focus) = state.pointer_focus.as_mut() {
-                    let offset = state
-                        .output_info
-                        .get(&focus.output)
-                        .map(|info| Vec2::new(info.x as f32, info.y as f32))
-                        .unwrap_or(Vec2::ZERO);
-                    focus.position = Vec2::new(surface_x as f32, surface_y as f32);
-                    state.pending_pointer_events.push(PendingPointerEvent {
-                        output: focus.output,
-                        position: focus.position,
-                        offset,
-                        kind: PendingPointerEventKind::Motion,
-                    });
+                let Some(output) = state.pointer_focus.get(seat_id).map(|focus| focus.output)
+                else {
+                    return;
+                };
+                let offset = state.surface_origin(output).as_vec2();
+                let position = Vec2::new(surface_x as f32, surface_y as f32);
+                if let Some(focus) = state.pointer_focus.get_mut(seat_id) {
+                    focus.position = position;
                 }
+                state.push_pointer_event(PendingPointerEvent {
+                    seat: *seat_id,
+                    output,
+                    position,
+                    offset,
+                    kind: PendingPointerEventKind::Motion,
+                });
             }
             wl_pointer::Event::Button {
                 button,
                 state: btn_state,
                 ..
             } => {
-                if let Some(focus) = state.pointer_focus.as_ref() {
-                    let offset = state
-                        .output_info
-                        .get(&focus.output)
-                        .map(|info| Vec2::new(info.x as f32, info.y as f32))
-                        .unwrap_or(Vec2::ZERO);
-
-                    let map_pointer_button = |code: u32| -> Option<MouseButton> {
-                        match code {
-                            272 => Some(MouseButton::Left),
-                            273 => Some(MouseButton::Right),
-                            274 => Some(MouseButton::Middle),
-                            other => u16::try_from(other).ok().map(MouseButton::Other),
-                        }
-                    };
+                if let Some(focus) = state.pointer_focus.get(seat_id) {
+                    let offset = state.surface_origin(focus.output).as_vec2();
 
-                    state.pending_pointer_events.push(PendingPointerEvent {
+                    state.push_pointer_event(PendingPointerEvent {
+                        seat: *seat_id,
                         output: focus.output,
                         position: focus.position,
                         offset,
@@ -363,6 +1091,17 @@ impl Dispatch<wl_pointer::WlPointer, u32> for WaylandAppState {
                     });
                 }
             }
+            wl_pointer::Event::Axis { axis, value, .. } => {
+                state.push_scroll(*seat_id, axis, 0.0, value as f32);
+            }
+            wl_pointer::Event::AxisDiscrete { axis, discrete } => {
+                state.push_scroll(*seat_id, axis, discrete as f32, 0.0);
+            }
+            // Replaces AxisDiscrete from wl_pointer v8; 120 is one wheel click.
+            wl_pointer::Event::AxisValue120 { axis, value120 } => {
+                state.push_scroll(*seat_id, axis, value120 as f32 / 120.0, 0.0);
+            }
+            wl_pointer::Event::Frame => state.flush_pointer_frame(*seat_id),
             _ => {}
         }
     }
@@ -380,60 +1119,130 @@ impl Dispatch<zwlr_layer_surface_v1::ZwlrLayerSurfaceV1, ()> for WaylandAppState
         match event {
             zwlr_layer_surface_v1::Event::Configure {
                 serial,
-                width,
-                height,
+                width: configured_width,
+                height: configured_height,
             } => {
                 let _span_guard = trace_span!(
                     "zwlr_layer_surface_v1::Event::Configure",
                     serial,
-                    width,
-                    height
+                    configured_width,
+                    configured_height
                 );
                 info!(
                     "Layer surface configured: serial={}, width={}, height={}",
-                    serial, width, height
+                    serial, configured_width, configured_height
                 );
                 surface.ack_configure(serial);
-                if let Some((output, surf)) = state
+                if let Some(output) = state
                     .surfaces
                     .iter()
                     .find(|(_, entry)| entry.layer_surface == *surface)
+                    .map(|(output, _)| *output)
                 {
                     // bind xdg_output if available and not yet bound
-                    if let (Some(manager), Some(wl_output)) =
-                        (state.xdg_output_manager.as_ref(), state.outputs.get(output))
-                    {
+                    if let (Some(manager), Some(wl_output)) = (
+                        state.xdg_output_manager.as_ref(),
+                        state.outputs.get(&output),
+                    ) {
                         state
                             .xdg_outputs
-                            .entry(*output)
-                            .or_insert_with(|| manager.get_xdg_output(wl_output, qh, *output));
+                            .entry(output)
+                            .or_insert_with(|| manager.get_xdg_output(wl_output, qh, output));
                     }
 
-                    let handles = WaylandSurfaceHandles::new(&state.display, &surf.surface);
-                    let width = width.max(1);
-                    let height = height.max(1);
-                    let (offset_x, offset_y) = state
+                    let margins = state.surfaces[&output].margins;
+                    let (width, height) = configured_size(
+                        configured_width,
+                        configured_height,
+                        state.output_info.get(&output),
+                        margins,
+                    );
+                    let output_size_known = state
                         .output_info
-                        .get(output)
-                        .map(|i| (i.x, i.y))
-                        .unwrap_or((0, 0));
-                    state.queue_surface_config(WaylandSurfaceConfig {
-                        output: *output,
+                        .get(&output)
+                        .is_some_and(|info| info.width > 0 && info.height > 0);
+                    let Some(surf) = state.surfaces.get_mut(&output) else {
+                        return;
+                    };
+                    if output_size_known && (width, height) != (configured_width, configured_height)
+                    {
+                        // Agree on the size we picked; the next configure
+                        // carries it. A 1x1 guess is not worth pinning.
+                        surface.set_size(width, height);
+                        surf.surface.commit();
+                    }
+                    let handles = WaylandSurfaceHandles::new(&state.display, &surf.surface);
+
+                    // Tell the compositor the wallpaper needs no blending.
+                    if !state.transparent
+                        && surf.opaque_size != Some((width, height))
+                        && let Some((compositor, _)) = state.compositor.as_ref()
+                    {
+                        let region = compositor.create_region(qh, ());
+                        region.add(0, 0, width as i32, height as i32);
+                        surf.surface.set_opaque_region(Some(&region));
+                        region.destroy();
+                        surf.opaque_size = Some((width, height));
+                    }
+
+                    let IVec2 {
+                        x: offset_x,
+                        y: offset_y,
+                    } = state.surface_origin(output);
+                    let config = WaylandSurfaceConfig {
+                        output,
                         handles,
                         width,
                         height,
                         offset_x,
                         offset_y,
-                    });
-                } else {
+                        scale: state.output_scale(output),
+                    };
+                    state.layer_configs.insert(output, config);
+                    // While locked the output shows its lock surface instead.
+                    if !state.lock_surfaces.contains_key(&output) {
+                        state.queue_surface_config(config);
+                    }
+                    state.attach_placeholder(output, width, height, qh);
+                } else if !state
+                    .surface_switch
+                    .as_ref()
+                    .is_some_and(|switch| switch.contains(surface))
+                {
                     warn!("Configure for unknown layer_surface");
                 }
             }
             zwlr_layer_surface_v1::Event::Closed => {
                 let _span_guard = trace_span!("zwlr_layer_surface_v1::Event::Closed").entered();
-                info!("Layer surface closed");
-                // Mark closed; will be cleaned up by event loop.
-                state.closed = true;
+                let Some(output) = state
+                    .surfaces
+                    .iter()
+                    .find(|(_, entry)| entry.layer_surface == *surface)
+                    .map(|(output, _)| *output)
+                else {
+                    return;
+                };
+                info!("Layer surface for output {} closed", output);
+                if let Some(closed) = state.surfaces.remove(&output) {
+                    closed.destroy();
+                }
+                state.layer_configs.remove(&output);
+                state.surface_to_output.retain(|_, o| *o != output);
+                state
+                    .pointer_focus
+                    .retain(|_, focus| focus.output != output);
+                state.closed_outputs.insert(output);
+                state.dropped_surfaces.push(output);
+
+                // Only give up once the compositor closed every output.
+                if state
+                    .outputs
+                    .keys()
+                    .all(|output| state.closed_outputs.contains(output))
+                {
+                    info!("All layer surfaces closed");
+                    state.closed = true;
+                }
             }
             _ => (),
         }
@@ -464,19 +1273,23 @@ impl Dispatch<wl_callback::WlCallback, ()> for WaylandAppState {
 
 impl Dispatch<wl_surface::WlSurface, ()> for WaylandAppState {
     fn event(
-        _state: &mut Self,
-        _surface: &wl_surface::WlSurface,
+        state: &mut Self,
+        surface: &wl_surface::WlSurface,
         event: wl_surface::Event,
         _data: &(),
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
         match event {
-            wl_surface::Event::Enter { .. } => {
-                // Do nothing: Cursor enter event is not needed for background.
+            wl_surface::Event::Enter { output } => {
+                if let Some(entered) = state.entered_outputs_mut(surface) {
+                    entered.insert(output.id().protocol_id());
+                }
             }
-            wl_surface::Event::Leave { .. } => {
-                // Do nothing: Cursor leave event is not needed for background.
+            wl_surface::Event::Leave { output } => {
+                if let Some(entered) = state.entered_outputs_mut(surface) {
+                    entered.remove(&output.id().protocol_id());
+                }
             }
             wl_surface::Event::PreferredBufferScale { factor } => {
                 debug!("Preferred buffer scale factor: {}", factor);
@@ -492,44 +1305,66 @@ impl Dispatch<wl_surface::WlSurface, ()> for WaylandAppState {
     }
 }
 
-impl Dispatch<wl_output::WlOutput, ()> for WaylandAppState {
+impl Dispatch<wl_output::WlOutput, u32> for WaylandAppState {
     fn event(
         state: &mut Self,
-        output: &wl_output::WlOutput,
+        _output: &wl_output::WlOutput,
         event: wl_output::Event,
-        _data: &(),
+        output_name: &u32,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
+        let info = state.output_info.entry(*output_name).or_default();
         match event {
-            wl_output::Event::Geometry { x, y, .. } => {
-                let info = state
-                    .output_info
-                    .entry(output.id().protocol_id())
-                    .or_default();
-                info.x = x;
-                info.y = y;
-                state.dirty_outputs.insert(output.id().protocol_id());
-            }
-            wl_output::Event::Mode { width, height, .. } => {
-                let info = state
-                    .output_info
-                    .entry(output.id().protocol_id())
-                    .or_default();
-                info.width = width;
-                info.height = height;
-                state.dirty_outputs.insert(output.id().protocol_id());
+            wl_output::Event::Geometry {
+                x,
+                y,
+                physical_width,
+                physical_height,
+                transform,
+                ..
+            } => {
+                info.geometry_position = IVec2::new(x, y);
+                info.physical_size_mm = (physical_width > 0 && physical_height > 0)
+                    .then(|| UVec2::new(physical_width as u32, physical_height as u32));
+                info.rotated = matches!(
+                    transform,
+                    wayland_client::WEnum::Value(
+                        wl_output::Transform::_90
+                            | wl_output::Transform::_270
+                            | wl_output::Transform::Flipped90
+                            | wl_output::Transform::Flipped270
+                    )
+                );
+            }
+            wl_output::Event::Mode {
+                flags,
+                width,
+                height,
+                refresh,
+            } => {
+                if !matches!(flags, wayland_client::WEnum::Value(flags) if flags.contains(wl_output::Mode::Current))
+                {
+                    return;
+                }
+                info.mode = Some(UVec2::new(width.max(0) as u32, height.max(0) as u32));
+                info.refresh_rate_mhz = refresh;
+            }
+            wl_output::Event::Name { name } => {
+                info.name = Some(name);
+                return;
+            }
+            wl_output::Event::Description { description } => {
+                info.description = Some(description);
+                return;
             }
             wl_output::Event::Scale { factor } => {
-                let info = state
-                    .output_info
-                    .entry(output.id().protocol_id())
-                    .or_default();
                 info.scale = factor;
-                state.dirty_outputs.insert(output.id().protocol_id());
             }
-            _ => {}
+            _ => return,
         }
+        info.normalize();
+        state.dirty_outputs.insert(*output_name);
     }
 }
 
@@ -558,16 +1393,24 @@ impl Dispatch<zxdg_output_v1::ZxdgOutputV1, u32> for WaylandAppState {
         match event {
             zxdg_output_v1::Event::LogicalPosition { x, y } => {
                 let info = state.output_info.entry(*output_name).or_default();
-                info.x = x;
-                info.y = y;
+                info.logical_position = Some(IVec2::new(x, y));
+                info.normalize();
                 state.dirty_outputs.insert(*output_name);
             }
             zxdg_output_v1::Event::LogicalSize { width, height } => {
                 let info = state.output_info.entry(*output_name).or_default();
-                info.width = width;
-                info.height = height;
+                info.logical_size = Some(IVec2::new(width, height));
+                info.normalize();
                 state.dirty_outputs.insert(*output_name);
             }
+            zxdg_output_v1::Event::Name { name } => {
+                let info = state.output_info.entry(*output_name).or_default();
+                info.logical_name = Some(name);
+            }
+            zxdg_output_v1::Event::Description { description } => {
+                let info = state.output_info.entry(*output_name).or_default();
+                info.description = Some(description);
+            }
             _ => {}
         }
     }
@@ -585,3 +1428,315 @@ impl Dispatch<wl_compositor::WlCompositor, ()> for WaylandAppState {
         // Do nothing: Compositor never dispatches events.
     }
 }
+
+impl Dispatch<wp_presentation::WpPresentation, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _presentation: &wp_presentation::WpPresentation,
+        event: wp_presentation::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wp_presentation::Event::ClockId { clk_id } = event {
+            debug!("Presentation clock id: {}", clk_id);
+        }
+    }
+}
+
+impl Dispatch<wp_presentation_feedback::WpPresentationFeedback, u32> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _feedback: &wp_presentation_feedback::WpPresentationFeedback,
+        event: wp_presentation_feedback::Event,
+        output: &u32,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            wp_presentation_feedback::Event::Presented {
+                tv_sec_hi,
+                tv_sec_lo,
+                tv_nsec,
+                refresh,
+                flags,
+                ..
+            } => {
+                state.pending_feedback.remove(output);
+                let secs = (u64::from(tv_sec_hi) << 32) | u64::from(tv_sec_lo);
+                let flags = match flags {
+                    wayland_client::WEnum::Value(kind) => kind.bits(),
+                    wayland_client::WEnum::Unknown(raw) => raw,
+                };
+                state.presented_frames.push(PresentedFrame {
+                    output: *output,
+                    presented_at: Duration::new(secs, tv_nsec),
+                    refresh_ns: refresh,
+                    flags,
+                });
+            }
+            wp_presentation_feedback::Event::Discarded => {
+                state.pending_feedback.remove(output);
+                state.discarded_frames += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<wl_region::WlRegion, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _region: &wl_region::WlRegion,
+        _event: wl_region::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: Region never dispatches events.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(mode: UVec2, scale: i32) -> OutputInfo {
+        OutputInfo {
+            mode: Some(mode),
+            scale,
+            geometry_position: IVec2::new(100, 50),
+            ..default()
+        }
+    }
+
+    fn geometry(info: &OutputInfo) -> (i32, i32, i32, i32) {
+        (info.x, info.y, info.width, info.height)
+    }
+
+    #[test]
+    fn normalize_divides_mode_by_scale() {
+        let mut info = output(UVec2::new(1920, 1080), 1);
+        info.normalize();
+        assert_eq!(geometry(&info), (100, 50, 1920, 1080));
+
+        let mut info = output(UVec2::new(3840, 2160), 2);
+        info.normalize();
+        assert_eq!(geometry(&info), (100, 50, 1920, 1080));
+    }
+
+    #[test]
+    fn normalize_treats_missing_scale_as_one() {
+        let mut info = output(UVec2::new(1920, 1080), 0);
+        info.normalize();
+        assert_eq!(geometry(&info), (100, 50, 1920, 1080));
+    }
+
+    #[test]
+    fn normalize_prefers_xdg_output() {
+        for scale in [1, 2] {
+            let mut info = OutputInfo {
+                logical_position: Some(IVec2::new(-1280, 0)),
+                logical_size: Some(IVec2::new(1280, 720)),
+                ..output(UVec2::new(3840, 2160), scale)
+            };
+            info.normalize();
+            assert_eq!(geometry(&info), (-1280, 0, 1280, 720));
+        }
+    }
+
+    #[test]
+    fn normalize_swaps_rotated_mode() {
+        let mut info = OutputInfo {
+            rotated: true,
+            ..output(UVec2::new(3840, 2160), 2)
+        };
+        info.normalize();
+        assert_eq!(geometry(&info), (100, 50, 1080, 1920));
+    }
+
+    #[test]
+    fn normalize_keeps_xdg_output_size_when_rotated() {
+        // xdg-output sizes already account for the transform.
+        let mut info = OutputInfo {
+            rotated: true,
+            logical_size: Some(IVec2::new(1080, 1920)),
+            ..output(UVec2::new(1920, 1080), 1)
+        };
+        info.normalize();
+        assert_eq!(geometry(&info), (100, 50, 1080, 1920));
+    }
+
+    #[test]
+    fn normalize_waits_for_mode() {
+        let mut info = OutputInfo {
+            scale: 2,
+            ..default()
+        };
+        info.normalize();
+        assert_eq!(geometry(&info), (0, 0, 0, 0));
+    }
+
+    fn pointer_event(output: u32, x: f32, kind: PendingPointerEventKind) -> PendingPointerEvent {
+        PendingPointerEvent {
+            seat: 1,
+            output,
+            position: Vec2::new(x, 0.0),
+            offset: Vec2::ZERO,
+            kind,
+        }
+    }
+
+    fn press(button: MouseButton) -> PendingPointerEventKind {
+        PendingPointerEventKind::Button {
+            button: Some(button),
+            pressed: true,
+        }
+    }
+
+    fn scroll(smooth: f32) -> PendingPointerEventKind {
+        PendingPointerEventKind::Scroll {
+            discrete: Vec2::ZERO,
+            smooth: Vec2::new(0.0, smooth),
+        }
+    }
+
+    /// Kind and x position of each event, for comparing sequences.
+    fn summary(events: &[PendingPointerEvent]) -> Vec<(String, f32)> {
+        events
+            .iter()
+            .map(|event| {
+                let kind = match &event.kind {
+                    PendingPointerEventKind::Enter => "enter".to_owned(),
+                    PendingPointerEventKind::Leave => "leave".to_owned(),
+                    PendingPointerEventKind::Motion => "motion".to_owned(),
+                    PendingPointerEventKind::Button { pressed, .. } => format!("button {pressed}"),
+                    PendingPointerEventKind::Scroll { smooth, .. } => {
+                        format!("scroll {}", smooth.y)
+                    }
+                };
+                (kind, event.position.x)
+            })
+            .collect()
+    }
+
+    fn expected(events: &[(&str, f32)]) -> Vec<(String, f32)> {
+        events
+            .iter()
+            .map(|(kind, x)| ((*kind).to_owned(), *x))
+            .collect()
+    }
+
+    #[test]
+    fn frame_folds_motion_but_keeps_button_order() {
+        let frame = vec![
+            pointer_event(1, 10.0, PendingPointerEventKind::Motion),
+            pointer_event(1, 20.0, PendingPointerEventKind::Motion),
+            pointer_event(1, 20.0, press(MouseButton::Left)),
+            pointer_event(1, 30.0, PendingPointerEventKind::Motion),
+            pointer_event(1, 40.0, PendingPointerEventKind::Motion),
+        ];
+
+        assert_eq!(
+            summary(&coalesce_pointer_frame(frame)),
+            expected(&[("motion", 20.0), ("button true", 20.0), ("motion", 40.0)])
+        );
+    }
+
+    #[test]
+    fn frame_sums_axis_events() {
+        let frame = vec![
+            pointer_event(1, 10.0, scroll(1.5)),
+            pointer_event(1, 10.0, scroll(2.5)),
+            pointer_event(1, 15.0, PendingPointerEventKind::Motion),
+            pointer_event(1, 15.0, scroll(-1.0)),
+        ];
+
+        assert_eq!(
+            summary(&coalesce_pointer_frame(frame)),
+            expected(&[("scroll 4", 10.0), ("motion", 15.0), ("scroll -1", 15.0)])
+        );
+    }
+
+    #[test]
+    fn frame_folds_motion_into_enter() {
+        let frame = vec![
+            pointer_event(1, 5.0, PendingPointerEventKind::Leave),
+            pointer_event(2, 0.0, PendingPointerEventKind::Enter),
+            pointer_event(2, 8.0, PendingPointerEventKind::Motion),
+        ];
+
+        let coalesced = coalesce_pointer_frame(frame);
+        assert_eq!(
+            summary(&coalesced),
+            expected(&[("leave", 5.0), ("enter", 8.0)])
+        );
+        assert_eq!(coalesced[1].output, 2);
+    }
+
+    #[test]
+    fn frame_keeps_motion_on_other_outputs_apart() {
+        let frame = vec![
+            pointer_event(1, 10.0, PendingPointerEventKind::Motion),
+            pointer_event(2, 20.0, PendingPointerEventKind::Motion),
+        ];
+
+        assert_eq!(
+            summary(&coalesce_pointer_frame(frame)),
+            expected(&[("motion", 10.0), ("motion", 20.0)])
+        );
+    }
+
+    #[test]
+    fn evdev_buttons_map_to_bevy_buttons() {
+        assert_eq!(map_pointer_button(272), Some(MouseButton::Left));
+        assert_eq!(map_pointer_button(273), Some(MouseButton::Right));
+        assert_eq!(map_pointer_button(274), Some(MouseButton::Middle));
+        assert_eq!(map_pointer_button(275), Some(MouseButton::Back));
+        assert_eq!(map_pointer_button(276), Some(MouseButton::Forward));
+        assert_eq!(map_pointer_button(277), Some(MouseButton::Other(277)));
+    }
+
+    #[test]
+    fn zero_configure_uses_output_size() {
+        let info = OutputInfo {
+            width: 1920,
+            height: 1080,
+            ..default()
+        };
+        let none = crate::WaylandMargins::default();
+
+        assert_eq!(configured_size(0, 0, Some(&info), none), (1920, 1080));
+        assert_eq!(configured_size(0, 40, Some(&info), none), (1920, 40));
+        assert_eq!(configured_size(800, 600, Some(&info), none), (800, 600));
+    }
+
+    #[test]
+    fn zero_configure_without_output_info_is_one_pixel() {
+        let none = crate::WaylandMargins::default();
+
+        assert_eq!(configured_size(0, 0, None, none), (1, 1));
+        assert_eq!(
+            configured_size(0, 0, Some(&OutputInfo::default()), none),
+            (1, 1)
+        );
+    }
+
+    #[test]
+    fn zero_configure_leaves_out_margins() {
+        let info = OutputInfo {
+            width: 1920,
+            height: 1080,
+            ..default()
+        };
+        let margins = crate::WaylandMargins {
+            top: 30,
+            right: 10,
+            bottom: 0,
+            left: 20,
+        };
+
+        assert_eq!(configured_size(0, 0, Some(&info), margins), (1890, 1050));
+        assert_eq!(configured_size(0, 0, None, margins), (1, 1));
+    }
+}
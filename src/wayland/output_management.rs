@@ -0,0 +1,156 @@
+//! Output heads via `zwlr_output_manager_v1`.
+//!
+//! Tools like kanshi disable a head without removing its `wl_output`. Such
+//! outputs are left out of the target selection, which drops their surfaces
+//! until the head is enabled again. Heads also carry the make, model and
+//! serial number, merged into [`crate::WaylandOutputDiagnostics`].
+
+use bevy::prelude::*;
+use wayland_client::{
+    Connection, Dispatch, Proxy, QueueHandle, backend::ObjectId, event_created_child,
+};
+use wayland_protocols_wlr::output_management::v1::client::{
+    zwlr_output_head_v1, zwlr_output_manager_v1, zwlr_output_mode_v1,
+};
+
+use super::WaylandAppState;
+
+/// State of one head, matched to a `wl_output` by connector name.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OutputHead {
+    pub name: Option<String>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub enabled: bool,
+}
+
+/// Heads as reported so far, and the state as of the last `done`.
+#[derive(Default)]
+pub(crate) struct OutputHeads {
+    pending: Vec<(ObjectId, OutputHead)>,
+    pub current: Vec<OutputHead>,
+}
+
+impl OutputHeads {
+    fn head_mut(&mut self, id: ObjectId) -> &mut OutputHead {
+        let index = match self.pending.iter().position(|(head, _)| *head == id) {
+            Some(index) => index,
+            None => {
+                self.pending.push((id, OutputHead::default()));
+                self.pending.len() - 1
+            }
+        };
+        &mut self.pending[index].1
+    }
+}
+
+impl WaylandAppState {
+    /// The head driving `output`, if the compositor reports heads.
+    pub(crate) fn output_head(&self, output: u32) -> Option<&OutputHead> {
+        let name = self.output_info.get(&output)?.connector_name()?;
+        self.output_heads
+            .current
+            .iter()
+            .find(|head| head.name.as_deref() == Some(name))
+    }
+
+    /// Whether `output` may show the wallpaper; outputs without a known head
+    /// count as enabled.
+    pub(crate) fn output_enabled(&self, output: u32) -> bool {
+        self.output_head(output).is_none_or(|head| head.enabled)
+    }
+}
+
+impl Dispatch<zwlr_output_manager_v1::ZwlrOutputManagerV1, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _manager: &zwlr_output_manager_v1::ZwlrOutputManagerV1,
+        event: zwlr_output_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_manager_v1::Event::Head { head } => {
+                state.output_heads.head_mut(head.id());
+            }
+            zwlr_output_manager_v1::Event::Done { .. } => {
+                let heads = &mut state.output_heads;
+                heads.current = heads.pending.iter().map(|(_, head)| head.clone()).collect();
+                for head in &heads.current {
+                    debug!(
+                        "Output head {:?}: enabled={}, make={:?}, model={:?}",
+                        head.name, head.enabled, head.make, head.model
+                    );
+                }
+            }
+            zwlr_output_manager_v1::Event::Finished => {
+                state.output_manager = None;
+                state.output_heads = OutputHeads::default();
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(WaylandAppState, zwlr_output_manager_v1::ZwlrOutputManagerV1, [
+        zwlr_output_manager_v1::EVT_HEAD_OPCODE => (zwlr_output_head_v1::ZwlrOutputHeadV1, ()),
+    ]);
+}
+
+impl Dispatch<zwlr_output_head_v1::ZwlrOutputHeadV1, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        head: &zwlr_output_head_v1::ZwlrOutputHeadV1,
+        event: zwlr_output_head_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let heads = &mut state.output_heads;
+        match event {
+            zwlr_output_head_v1::Event::Name { name } => {
+                heads.head_mut(head.id()).name = Some(name);
+            }
+            zwlr_output_head_v1::Event::Make { make } => {
+                heads.head_mut(head.id()).make = Some(make);
+            }
+            zwlr_output_head_v1::Event::Model { model } => {
+                heads.head_mut(head.id()).model = Some(model);
+            }
+            zwlr_output_head_v1::Event::SerialNumber { serial_number } => {
+                heads.head_mut(head.id()).serial_number = Some(serial_number);
+            }
+            zwlr_output_head_v1::Event::Enabled { enabled } => {
+                heads.head_mut(head.id()).enabled = enabled != 0;
+            }
+            zwlr_output_head_v1::Event::Finished => {
+                heads.pending.retain(|(id, _)| *id != head.id());
+                if head.version() >= 3 {
+                    head.release();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(WaylandAppState, zwlr_output_head_v1::ZwlrOutputHeadV1, [
+        zwlr_output_head_v1::EVT_MODE_OPCODE => (zwlr_output_mode_v1::ZwlrOutputModeV1, ()),
+    ]);
+}
+
+impl Dispatch<zwlr_output_mode_v1::ZwlrOutputModeV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        mode: &zwlr_output_mode_v1::ZwlrOutputModeV1,
+        event: zwlr_output_mode_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Modes are already known from `wl_output`.
+        if matches!(event, zwlr_output_mode_v1::Event::Finished) && mode.version() >= 3 {
+            mode.release();
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Output power state via `zwlr_output_power_manager_v1`.
+
+use bevy::prelude::*;
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+use wayland_protocols_wlr::output_power_management::v1::client::{
+    zwlr_output_power_manager_v1, zwlr_output_power_v1,
+};
+
+use super::WaylandAppState;
+
+impl WaylandAppState {
+    /// Starts tracking the power mode of `output` if the manager is bound.
+    pub(crate) fn bind_output_power(&mut self, output: u32, qh: &QueueHandle<Self>) {
+        let (Some(manager), Some(wl_output)) = (
+            self.output_power_manager.as_ref(),
+            self.outputs.get(&output),
+        ) else {
+            return;
+        };
+        self.output_power
+            .entry(output)
+            .or_insert_with(|| manager.get_output_power(wl_output, qh, output));
+    }
+
+    /// Stops tracking the power mode of `output`.
+    pub(crate) fn release_output_power(&mut self, output: u32) {
+        if let Some(power) = self.output_power.remove(&output) {
+            power.destroy();
+        }
+        self.powered_off.remove(&output);
+    }
+}
+
+impl Dispatch<zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1,
+        _event: zwlr_output_power_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<zwlr_output_power_v1::ZwlrOutputPowerV1, u32> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _power: &zwlr_output_power_v1::ZwlrOutputPowerV1,
+        event: zwlr_output_power_v1::Event,
+        output: &u32,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_power_v1::Event::Mode { mode } => {
+                let off = matches!(mode, WEnum::Value(zwlr_output_power_v1::Mode::Off));
+                debug!("Output {} power mode: {:?}", output, mode);
+                if off {
+                    state.powered_off.insert(*output);
+                } else {
+                    state.powered_off.remove(output);
+                }
+            }
+            zwlr_output_power_v1::Event::Failed => {
+                // The object is dead (output gone or another client took
+                // control); assume the output is on from now on.
+                debug!("Output power tracking for output {} failed", output);
+                state.release_output_power(*output);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Solid color shown on new layer surfaces until wgpu presents to them.
+//!
+//! The 1x1 buffer comes from `wp_single_pixel_buffer_manager_v1`, or from
+//! `wl_shm` otherwise, and is stretched over the surface with `wp_viewporter`.
+
+use std::{fs::File, io::Write, os::fd::AsFd};
+
+use bevy::prelude::*;
+use wayland_client::{
+    Connection, Dispatch, QueueHandle,
+    protocol::{wl_buffer, wl_shm, wl_shm_pool},
+};
+use wayland_protocols::wp::{
+    single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1,
+    viewporter::client::{wp_viewport, wp_viewporter},
+};
+
+use super::WaylandAppState;
+
+impl WaylandAppState {
+    /// Attaches the placeholder to the layer surface of `output` (or resizes
+    /// it) after a configure, unless wgpu already presented to it.
+    pub(crate) fn attach_placeholder(
+        &mut self,
+        output: u32,
+        width: u32,
+        height: u32,
+        qh: &QueueHandle<Self>,
+    ) {
+        let Some(color) = self.placeholder_color else {
+            return;
+        };
+        let Some(viewporter) = self.viewporter.as_ref() else {
+            return;
+        };
+        let Some(surface) = self.surfaces.get(&output) else {
+            return;
+        };
+        if surface.presented {
+            return;
+        }
+        if surface.placeholder.is_none() {
+            let Some(buffer) = self.placeholder_buffer(color, qh) else {
+                return;
+            };
+            let Some(surface) = self.surfaces.get_mut(&output) else {
+                return;
+            };
+            // The buffer destroys itself once released.
+            surface.surface.attach(Some(&buffer), 0, 0);
+            surface.surface.damage_buffer(0, 0, 1, 1);
+            surface.placeholder = Some(viewporter.get_viewport(&surface.surface, qh, ()));
+        }
+        let Some(surface) = self.surfaces.get(&output) else {
+            return;
+        };
+        if let Some(viewport) = surface.placeholder.as_ref() {
+            viewport.set_destination(width as i32, height as i32);
+            surface.surface.commit();
+        }
+    }
+
+    /// Drops the placeholder scaling of outputs wgpu has presented to.
+    pub(crate) fn release_placeholders(&mut self, presented: impl Fn(u32) -> bool) {
+        for (output, surface) in &mut self.surfaces {
+            if surface.presented || !presented(*output) {
+                continue;
+            }
+            surface.presented = true;
+            if let Some(viewport) = surface.placeholder.take() {
+                debug!("First frame presented on output {output}; dropping placeholder");
+                viewport.destroy();
+            }
+        }
+    }
+
+    fn placeholder_buffer(
+        &self,
+        color: Srgba,
+        qh: &QueueHandle<Self>,
+    ) -> Option<wl_buffer::WlBuffer> {
+        let [r, g, b, a] = [color.red, color.green, color.blue, color.alpha]
+            .map(|channel| channel.clamp(0.0, 1.0));
+        let premultiplied = [r * a, g * a, b * a, a];
+
+        if let Some(manager) = self.single_pixel_buffer_manager.as_ref() {
+            let [r, g, b, a] = premultiplied.map(|channel| (channel * u32::MAX as f32) as u32);
+            return Some(manager.create_u32_rgba_buffer(r, g, b, a, qh, ()));
+        }
+
+        let shm = self.shm.as_ref()?;
+        let [r, g, b, a] = premultiplied.map(|channel| (channel * 255.0).round() as u8);
+        let file = match shm_file(&[b, g, r, a]) {
+            Ok(file) => file,
+            Err(err) => {
+                debug!("Failed to create placeholder shm buffer: {err}");
+                return None;
+            }
+        };
+        let pool = shm.create_pool(file.as_fd(), 4, qh, ());
+        let buffer = pool.create_buffer(0, 1, 1, 4, wl_shm::Format::Argb8888, qh, ());
+        pool.destroy();
+        Some(buffer)
+    }
+}
+
+/// Creates an unlinked file holding `contents` for a `wl_shm` pool.
+fn shm_file(contents: &[u8]) -> std::io::Result<File> {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!(
+        "bevy_live_wallpaper-placeholder-{}",
+        std::process::id()
+    ));
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    let _ = std::fs::remove_file(&path);
+    file.write_all(contents)?;
+    Ok(file)
+}
+
+impl Dispatch<wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1, ()>
+    for WaylandAppState
+{
+    fn event(
+        _state: &mut Self,
+        _manager: &wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1,
+        _event: wp_single_pixel_buffer_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<wp_viewporter::WpViewporter, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _viewporter: &wp_viewporter::WpViewporter,
+        _event: wp_viewporter::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the viewporter has no events.
+    }
+}
+
+impl Dispatch<wp_viewport::WpViewport, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _viewport: &wp_viewport::WpViewport,
+        _event: wp_viewport::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the viewport has no events.
+    }
+}
+
+impl Dispatch<wl_shm::WlShm, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _shm: &wl_shm::WlShm,
+        _event: wl_shm::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: Argb8888 is always supported.
+    }
+}
+
+impl Dispatch<wl_shm_pool::WlShmPool, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _pool: &wl_shm_pool::WlShmPool,
+        _event: wl_shm_pool::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the pool has no events.
+    }
+}
+
+impl Dispatch<wl_buffer::WlBuffer, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        buffer: &wl_buffer::WlBuffer,
+        event: wl_buffer::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let wl_buffer::Event::Release = event {
+            buffer.destroy();
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Pointer lock / confinement via `zwp_pointer_constraints_v1`.
+
+use bevy::prelude::*;
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::pointer_constraints::zv1::client::{
+    zwp_confined_pointer_v1, zwp_locked_pointer_v1, zwp_pointer_constraints_v1,
+};
+
+use super::WaylandAppState;
+use crate::PointerConstraintKind;
+
+/// Constraint object created for [`crate::WallpaperPointerConstraint`].
+pub(crate) struct ActivePointerConstraint {
+    kind: PointerConstraintKind,
+    seat: u32,
+    output: u32,
+    object: ConstraintObject,
+}
+
+enum ConstraintObject {
+    Locked(zwp_locked_pointer_v1::ZwpLockedPointerV1),
+    Confined(zwp_confined_pointer_v1::ZwpConfinedPointerV1),
+}
+
+impl ActivePointerConstraint {
+    fn destroy(self) {
+        match self.object {
+            ConstraintObject::Locked(locked) => locked.destroy(),
+            ConstraintObject::Confined(confined) => confined.destroy(),
+        }
+    }
+}
+
+impl WaylandAppState {
+    /// Applies the requested constraint to the wallpaper surface under a
+    /// pointer, replacing one that no longer matches the request or whose
+    /// surface or pointer went away.
+    pub(crate) fn sync_pointer_constraint(
+        &mut self,
+        constraint: Option<PointerConstraintKind>,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let Some(active) = self.pointer_constraint.as_ref()
+            && (Some(active.kind) != constraint
+                || !self.surfaces.contains_key(&active.output)
+                || !self.pointers.contains_key(&active.seat))
+            && let Some(active) = self.pointer_constraint.take()
+        {
+            active.destroy();
+        }
+        if self.pointer_constraint.is_some() {
+            return;
+        }
+        let Some(kind) = constraint else {
+            return;
+        };
+        let Some(manager) = self.pointer_constraints_manager.as_ref() else {
+            return;
+        };
+        let Some((seat, output)) = self
+            .pointer_focus
+            .iter()
+            .map(|(seat, focus)| (*seat, focus.output))
+            .find(|(_, output)| !self.lock_surfaces.contains_key(output))
+        else {
+            return;
+        };
+        let (Some(pointer), Some(surface)) = (self.pointers.get(&seat), self.surfaces.get(&output))
+        else {
+            return;
+        };
+
+        let lifetime = zwp_pointer_constraints_v1::Lifetime::Persistent;
+        let object = match kind {
+            PointerConstraintKind::Locked => ConstraintObject::Locked(manager.lock_pointer(
+                &surface.surface,
+                pointer,
+                None,
+                lifetime,
+                qh,
+                (),
+            )),
+            PointerConstraintKind::Confined { region } => {
+                let region = region
+                    .zip(self.compositor.as_ref())
+                    .map(|(rect, (compositor, _))| {
+                        let region = compositor.create_region(qh, ());
+                        let size = rect.size();
+                        region.add(
+                            rect.min.x as i32,
+                            rect.min.y as i32,
+                            size.x as i32,
+                            size.y as i32,
+                        );
+                        region
+                    });
+                let confined = manager.confine_pointer(
+                    &surface.surface,
+                    pointer,
+                    region.as_ref(),
+                    lifetime,
+                    qh,
+                    (),
+                );
+                if let Some(region) = region {
+                    region.destroy();
+                }
+                ConstraintObject::Confined(confined)
+            }
+        };
+        debug!("Requested pointer constraint {kind:?} on output {output}");
+        self.pointer_constraint = Some(ActivePointerConstraint {
+            kind,
+            seat,
+            output,
+            object,
+        });
+    }
+}
+
+impl Dispatch<zwp_pointer_constraints_v1::ZwpPointerConstraintsV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &zwp_pointer_constraints_v1::ZwpPointerConstraintsV1,
+        _event: zwp_pointer_constraints_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<zwp_locked_pointer_v1::ZwpLockedPointerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _locked: &zwp_locked_pointer_v1::ZwpLockedPointerV1,
+        event: zwp_locked_pointer_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_locked_pointer_v1::Event::Locked => debug!("Pointer locked"),
+            zwp_locked_pointer_v1::Event::Unlocked => debug!("Pointer unlocked"),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_confined_pointer_v1::ZwpConfinedPointerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _confined: &zwp_confined_pointer_v1::ZwpConfinedPointerV1,
+        event: zwp_confined_pointer_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_confined_pointer_v1::Event::Confined => debug!("Pointer confined"),
+            zwp_confined_pointer_v1::Event::Unconfined => debug!("Pointer unconfined"),
+            _ => {}
+        }
+    }
+}
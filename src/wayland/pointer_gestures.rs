@@ -0,0 +1,148 @@
+//! Touchpad swipe / pinch gestures via `zwp_pointer_gestures_v1`.
+
+use bevy::prelude::*;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, protocol::wl_surface};
+use wayland_protocols::wp::pointer_gestures::zv1::client::{
+    zwp_pointer_gesture_pinch_v1, zwp_pointer_gesture_swipe_v1, zwp_pointer_gestures_v1,
+};
+
+use super::WaylandAppState;
+use crate::WallpaperGestureEvent;
+
+/// Highest protocol version handled here (swipe and pinch only).
+pub(crate) const POINTER_GESTURES_VERSION: u32 = 2;
+
+/// Gesture objects of one seat's pointer.
+pub(crate) struct PointerGestures {
+    swipe: zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1,
+    pinch: zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1,
+}
+
+impl PointerGestures {
+    pub(crate) fn destroy(self) {
+        self.swipe.destroy();
+        self.pinch.destroy();
+    }
+}
+
+impl WaylandAppState {
+    /// Creates the swipe and pinch objects for the pointer of `seat_id` if
+    /// the gestures manager is bound.
+    pub(crate) fn bind_pointer_gestures(&mut self, seat_id: u32, qh: &QueueHandle<Self>) {
+        let Some(manager) = self.pointer_gestures_manager.as_ref() else {
+            return;
+        };
+        let Some(pointer) = self.pointers.get(&seat_id) else {
+            return;
+        };
+        self.pointer_gestures
+            .entry(seat_id)
+            .or_insert_with(|| PointerGestures {
+                swipe: manager.get_swipe_gesture(pointer, qh, seat_id),
+                pinch: manager.get_pinch_gesture(pointer, qh, seat_id),
+            });
+    }
+
+    pub(crate) fn release_pointer_gestures(&mut self, seat_id: u32) {
+        if let Some(gestures) = self.pointer_gestures.remove(&seat_id) {
+            gestures.destroy();
+        }
+    }
+
+    fn surface_output(&self, surface: &wl_surface::WlSurface) -> Option<u32> {
+        self.surface_to_output
+            .get(&surface.id().protocol_id())
+            .copied()
+    }
+
+    fn focused_output(&self, seat_id: u32) -> Option<u32> {
+        self.pointer_focus.get(&seat_id).map(|focus| focus.output)
+    }
+}
+
+impl Dispatch<zwp_pointer_gestures_v1::ZwpPointerGesturesV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &zwp_pointer_gestures_v1::ZwpPointerGesturesV1,
+        _event: zwp_pointer_gestures_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1, u32> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _swipe: &zwp_pointer_gesture_swipe_v1::ZwpPointerGestureSwipeV1,
+        event: zwp_pointer_gesture_swipe_v1::Event,
+        seat_id: &u32,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let event = match event {
+            zwp_pointer_gesture_swipe_v1::Event::Begin {
+                surface, fingers, ..
+            } => WallpaperGestureEvent::SwipeBegin {
+                output: state.surface_output(&surface),
+                fingers,
+            },
+            zwp_pointer_gesture_swipe_v1::Event::Update { dx, dy, .. } => {
+                WallpaperGestureEvent::SwipeUpdate {
+                    output: state.focused_output(*seat_id),
+                    delta: Vec2::new(dx as f32, dy as f32),
+                }
+            }
+            zwp_pointer_gesture_swipe_v1::Event::End { cancelled, .. } => {
+                WallpaperGestureEvent::SwipeEnd {
+                    output: state.focused_output(*seat_id),
+                    cancelled: cancelled != 0,
+                }
+            }
+            _ => return,
+        };
+        state.pending_gesture_events.push(event);
+    }
+}
+
+impl Dispatch<zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1, u32> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _pinch: &zwp_pointer_gesture_pinch_v1::ZwpPointerGesturePinchV1,
+        event: zwp_pointer_gesture_pinch_v1::Event,
+        seat_id: &u32,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let event = match event {
+            zwp_pointer_gesture_pinch_v1::Event::Begin {
+                surface, fingers, ..
+            } => WallpaperGestureEvent::PinchBegin {
+                output: state.surface_output(&surface),
+                fingers,
+            },
+            zwp_pointer_gesture_pinch_v1::Event::Update {
+                dx,
+                dy,
+                scale,
+                rotation,
+                ..
+            } => WallpaperGestureEvent::PinchUpdate {
+                output: state.focused_output(*seat_id),
+                delta: Vec2::new(dx as f32, dy as f32),
+                scale: scale as f32,
+                rotation: rotation as f32,
+            },
+            zwp_pointer_gesture_pinch_v1::Event::End { cancelled, .. } => {
+                WallpaperGestureEvent::PinchEnd {
+                    output: state.focused_output(*seat_id),
+                    cancelled: cancelled != 0,
+                }
+            }
+            _ => return,
+        };
+        state.pending_gesture_events.push(event);
+    }
+}
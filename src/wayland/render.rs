@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use bevy::{
     asset::RenderAssetUsages,
+    ecs::system::SystemParam,
     log::{debug, error, warn},
     prelude::{Assets, Handle, Image, Res, ResMut, Resource},
     render::{
@@ -17,21 +20,35 @@ use wgpu::{
     SurfaceConfiguration, SurfaceTargetUnsafe, TextureAspect,
 };
 
-use crate::wayland::surface::WaylandSurfaceHandles;
+use crate::{
+    WallpaperAlphaMode, WallpaperVisibility,
+    redraw::WallpaperDamage,
+    surface_format::SurfacePreferences,
+    surface_recovery::{SharedSurfaceRecovery, SurfaceBackoff},
+    wayland::{frame_pacing::FramePacing, surface::WaylandSurfaceHandles},
+};
 
-pub(crate) const WAYLAND_SURFACE_FORMAT: TextureFormat = TextureFormat::Bgra8UnormSrgb;
+/// Format of the render target until a surface format has been negotiated.
+pub(crate) const DEFAULT_WAYLAND_SURFACE_FORMAT: TextureFormat = TextureFormat::Bgra8UnormSrgb;
 
-pub(crate) fn create_wayland_image(images: &mut Assets<Image>) -> Handle<Image> {
+pub(crate) fn create_wayland_image(
+    images: &mut Assets<Image>,
+    alpha_mode: WallpaperAlphaMode,
+) -> Handle<Image> {
     let size = Extent3d {
         width: 1,
         height: 1,
         depth_or_array_layers: 1,
     };
+    let alpha = match alpha_mode {
+        WallpaperAlphaMode::Opaque => 255,
+        WallpaperAlphaMode::Transparent => 0,
+    };
     let mut image = Image::new_fill(
         size,
         TextureDimension::D2,
-        &[0, 0, 0, 255],
-        WAYLAND_SURFACE_FORMAT,
+        &[0, 0, 0, alpha],
+        DEFAULT_WAYLAND_SURFACE_FORMAT,
         RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
     );
     image.texture_descriptor.usage =
@@ -64,6 +81,7 @@ impl WaylandSurfaceDescriptor {
             entry.height = config.height;
             entry.offset_x = config.offset_x;
             entry.offset_y = config.offset_y;
+            entry.scale = config.scale;
         } else {
             self.surfaces.push(SurfaceDescriptorEntry {
                 output: config.output,
@@ -72,6 +90,7 @@ impl WaylandSurfaceDescriptor {
                 height: config.height,
                 offset_x: config.offset_x,
                 offset_y: config.offset_y,
+                scale: config.scale,
             });
         }
     }
@@ -98,6 +117,22 @@ impl WaylandSurfaceDescriptor {
         Some((min_x, min_y, width, height))
     }
 
+    /// Physical pixels per logical pixel of the render target: the largest
+    /// scale among the configured outputs, so none of them is upscaled.
+    ///
+    /// Every surface uses this one scale, since frames are copied out of the
+    /// shared target without resampling. An output with a lower scale is
+    /// rendered at this one and left to the compositor to downsample through
+    /// the buffer scale. Only integer `wl_output` scales are known;
+    /// `wp_fractional_scale_v1` isn't used, so a 1.5x output renders at 2x.
+    pub(crate) fn render_scale(&self) -> f64 {
+        self.surfaces
+            .iter()
+            .filter(|s| s.handles.is_some())
+            .map(|s| s.scale)
+            .fold(1.0, f64::max)
+    }
+
     pub(crate) fn bump_generation(&mut self) {
         self.generation = self.generation.wrapping_add(1);
     }
@@ -111,6 +146,12 @@ pub(crate) struct SurfaceDescriptorEntry {
     pub height: u32,
     pub offset_x: i32,
     pub offset_y: i32,
+    pub scale: f64,
+}
+
+/// Converts a logical length to physical pixels at `scale`.
+pub(crate) fn to_physical(logical: u32, scale: f64) -> u32 {
+    (f64::from(logical) * scale).round() as u32
 }
 
 #[derive(Resource, ExtractResource, Clone, Debug)]
@@ -133,16 +174,57 @@ pub(crate) struct WaylandGpuSurfaceState {
     pub surfaces: HashMap<u32, WaylandGpuPerSurface>,
 }
 
+/// Outputs with a configured wgpu surface, shared with the main world for
+/// [`crate::WallpaperWaylandDiagnostics`].
+#[derive(Resource, Clone, Default)]
+pub(crate) struct ConfiguredWaylandSurfaces(Arc<Mutex<HashSet<u32>>>);
+
+impl ConfiguredWaylandSurfaces {
+    pub(crate) fn contains(&self, output: u32) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .contains(&output)
+    }
+}
+
+/// Publishes which outputs ended the frame with a configured surface, and
+/// their recovery counters.
+pub(crate) fn publish_configured_surfaces(
+    state: Res<WaylandGpuSurfaceState>,
+    configured: Res<ConfiguredWaylandSurfaces>,
+    recovery: Res<SharedSurfaceRecovery>,
+) {
+    let outputs = state
+        .surfaces
+        .iter()
+        .filter(|(_, entry)| entry.config.is_some())
+        .map(|(output, _)| *output)
+        .collect();
+    *configured.0.lock().unwrap_or_else(|err| err.into_inner()) = outputs;
+    recovery.publish(
+        state
+            .surfaces
+            .iter()
+            .map(|(output, entry)| (*output, entry.backoff.info())),
+    );
+}
+
 #[derive(Default)]
 pub(crate) struct WaylandGpuPerSurface {
     pub surface: Option<wgpu::Surface<'static>>,
     pub config: Option<SurfaceConfiguration>,
     pub last_applied_generation: u64,
+    /// Present even if nothing changed, e.g. right after (re)configuring.
+    pub needs_present: bool,
+    pub backoff: SurfaceBackoff,
 }
 
 pub(crate) fn prepare_wayland_surface(
     descriptor: Res<WaylandSurfaceDescriptor>,
     mut state: ResMut<WaylandGpuSurfaceState>,
+    wallpaper_alpha: Option<Res<WallpaperAlphaMode>>,
+    preferences: SurfacePreferences,
     render_instance: Res<RenderInstance>,
     render_adapter: Res<RenderAdapter>,
     render_device: Res<RenderDevice>,
@@ -152,8 +234,12 @@ pub(crate) fn prepare_wayland_surface(
         .surfaces
         .retain(|output, _| valid_outputs.contains(output));
 
+    let scale = descriptor.render_scale();
     for surf_desc in descriptor.surfaces.iter().filter(|s| s.handles.is_some()) {
         let entry = state.surfaces.entry(surf_desc.output).or_default();
+        if (entry.surface.is_none() || entry.config.is_none()) && entry.backoff.waiting() {
+            continue;
+        }
 
         let needs_recreate =
             entry.surface.is_none() || entry.last_applied_generation != descriptor.generation;
@@ -178,8 +264,8 @@ pub(crate) fn prepare_wayland_surface(
             continue;
         };
 
-        let width = surf_desc.width.max(1);
-        let height = surf_desc.height.max(1);
+        let width = to_physical(surf_desc.width, scale).max(1);
+        let height = to_physical(surf_desc.height, scale).max(1);
 
         let needs_reconfigure = entry
             .config
@@ -197,33 +283,58 @@ pub(crate) fn prepare_wayland_surface(
                 continue;
             }
 
-            let format = capabilities
-                .formats
-                .iter()
-                .copied()
-                .find(|fmt| *fmt == WAYLAND_SURFACE_FORMAT)
-                .or_else(|| capabilities.formats.first().copied())
+            let format = preferences
+                .choose_format(&capabilities.formats, DEFAULT_WAYLAND_SURFACE_FORMAT)
                 .expect("Wayland surface has no supported formats");
 
-            let present_mode = capabilities
-                .present_modes
-                .iter()
-                .copied()
-                .find(|mode| matches!(mode, PresentMode::Fifo))
-                .or_else(|| capabilities.present_modes.first().copied())
-                .expect("Wayland surface has no supported present mode");
-
-            let alpha_mode = capabilities
-                .alpha_modes
+            let auto_present_modes: &[PresentMode] = if preferences.allow_tearing() {
+                &[PresentMode::Immediate, PresentMode::Fifo]
+            } else {
+                &[PresentMode::Fifo]
+            };
+            let present_mode =
+                preferences.present_mode(&capabilities.present_modes, auto_present_modes);
+
+            let preferred_alpha: &[CompositeAlphaMode] = match wallpaper_alpha.as_deref() {
+                Some(WallpaperAlphaMode::Transparent) => &[
+                    CompositeAlphaMode::PreMultiplied,
+                    CompositeAlphaMode::PostMultiplied,
+                ],
+                _ => &[CompositeAlphaMode::Opaque],
+            };
+            let alpha_mode = preferred_alpha
                 .iter()
                 .copied()
-                .find(|mode| matches!(mode, CompositeAlphaMode::Opaque))
-                .unwrap_or(capabilities.alpha_modes[0]);
-
-            let mut usage = TextureUsages::RENDER_ATTACHMENT;
-            if capabilities.usages.contains(TextureUsages::COPY_DST) {
-                usage |= TextureUsages::COPY_DST;
+                .find(|mode| capabilities.alpha_modes.contains(mode))
+                .unwrap_or_else(|| {
+                    if wallpaper_alpha
+                        .as_deref()
+                        .is_some_and(|mode| *mode == WallpaperAlphaMode::Transparent)
+                    {
+                        warn!(
+                            "Wayland surface supports no transparent alpha mode ({:?}); falling back",
+                            capabilities.alpha_modes
+                        );
+                    }
+                    capabilities.alpha_modes[0]
+                });
+
+            // Frames are copied into the surface texture on present.
+            if !capabilities.usages.contains(TextureUsages::COPY_DST) {
+                entry.backoff.record_failure();
+                if entry.backoff.info().consecutive_failures == 1 {
+                    warn!(
+                        "Wayland surface for output {} doesn't support COPY_DST usage; \
+                         it can't be presented to",
+                        surf_desc.output
+                    );
+                }
+                entry.surface = None;
+                entry.config = None;
+                entry.last_applied_generation = 0;
+                continue;
             }
+            let usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_DST;
 
             let config = SurfaceConfiguration {
                 usage,
@@ -233,20 +344,41 @@ pub(crate) fn prepare_wayland_surface(
                 present_mode,
                 alpha_mode,
                 view_formats: vec![],
-                desired_maximum_frame_latency: 1,
+                desired_maximum_frame_latency: preferences.frame_latency(),
             };
 
             render_device.configure_surface(surface, &config);
 
             entry.config = Some(config);
+            entry.needs_present = true;
         }
 
         entry.last_applied_generation = descriptor.generation;
     }
 }
 
+/// Decides which outputs get a new frame this tick.
+#[derive(SystemParam)]
+pub(crate) struct WaylandPresentFilter<'w> {
+    damage: Option<Res<'w, WallpaperDamage>>,
+    visibility: Option<Res<'w, WallpaperVisibility>>,
+    pacing: Res<'w, FramePacing>,
+}
+
+impl WaylandPresentFilter<'_> {
+    fn should_present(&self, output: u32, needs_present: bool) -> bool {
+        let visible = self
+            .visibility
+            .as_ref()
+            .is_none_or(|visibility| visibility.should_present(output));
+        let dirty = self.damage.as_ref().is_none_or(|damage| damage.dirty);
+        visible && (dirty || needs_present)
+    }
+}
+
 pub(crate) fn present_wayland_surface(
     mut state: ResMut<WaylandGpuSurfaceState>,
+    filter: WaylandPresentFilter,
     target: Option<Res<WaylandRenderTarget>>,
     images: Res<RenderAssets<GpuImage>>,
     render_device: Res<RenderDevice>,
@@ -262,14 +394,30 @@ pub(crate) fn present_wayland_surface(
     let Some((min_x, min_y, _, _)) = descriptor.overall_bounds() else {
         return;
     };
+    let scale = descriptor.render_scale();
+    let now = Instant::now();
 
     for (output, entry) in state.surfaces.iter_mut() {
+        if !filter.should_present(*output, entry.needs_present) {
+            continue;
+        }
+        if !filter.pacing.ready(*output, now) {
+            // Keep the frame owed to this output until it's ready for one.
+            entry.needs_present = true;
+            continue;
+        }
         let Some(surface) = entry.surface.as_ref() else {
             continue;
         };
         let Some(config) = entry.config.as_ref() else {
             continue;
         };
+        if config.format.remove_srgb_suffix()
+            != gpu_image.texture_descriptor.format.remove_srgb_suffix()
+        {
+            // The render target hasn't switched to the negotiated format yet.
+            continue;
+        }
 
         let Some(desc_entry) = descriptor
             .surfaces
@@ -289,6 +437,7 @@ pub(crate) fn present_wayland_surface(
             CurrentSurfaceTexture::Success(texture)
             | CurrentSurfaceTexture::Suboptimal(texture) => texture,
             CurrentSurfaceTexture::Outdated => {
+                entry.backoff.record_failure();
                 debug!(
                     "Wayland surface for output {} outdated; scheduling reconfigure",
                     output
@@ -298,10 +447,20 @@ pub(crate) fn present_wayland_surface(
                 continue;
             }
             CurrentSurfaceTexture::Lost => {
-                warn!(
-                    "Wayland surface for output {} lost; scheduling recreate",
-                    output
-                );
+                entry.backoff.record_failure();
+                let info = entry.backoff.info();
+                if info.consecutive_failures == 1 {
+                    warn!(
+                        "Wayland surface for output {} lost; scheduling recreate",
+                        output
+                    );
+                } else {
+                    debug!(
+                        "Wayland surface for output {} lost {} times in a row; \
+                         recreating in {} frames",
+                        output, info.consecutive_failures, info.backoff_frames
+                    );
+                }
                 entry.surface = None;
                 entry.config = None;
                 entry.last_applied_generation = 0;
@@ -322,8 +481,8 @@ pub(crate) fn present_wayland_surface(
         });
 
         let src_origin = Origin3d {
-            x: (desc_entry.offset_x - min_x).max(0) as u32,
-            y: (desc_entry.offset_y - min_y).max(0) as u32,
+            x: to_physical((desc_entry.offset_x - min_x).max(0) as u32, scale),
+            y: to_physical((desc_entry.offset_y - min_y).max(0) as u32, scale),
             z: 0,
         };
 
@@ -340,6 +499,9 @@ pub(crate) fn present_wayland_surface(
         encoder.copy_texture_to_texture(src, dst, extent);
 
         render_queue.submit(Some(encoder.finish()));
+        filter.pacing.request_frame(*output);
         surface_texture.present();
+        entry.needs_present = false;
+        entry.backoff.reset();
     }
 }
@@ -0,0 +1,188 @@
+//! Lock screen surfaces via `ext_session_lock_v1`.
+
+use bevy::prelude::*;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle, protocol::wl_surface};
+use wayland_protocols::ext::session_lock::v1::client::{
+    ext_session_lock_manager_v1, ext_session_lock_surface_v1, ext_session_lock_v1,
+};
+
+use super::{WaylandAppState, WaylandSurfaceConfig, surface::WaylandSurfaceHandles};
+use crate::WallpaperLockEvent;
+
+pub(crate) struct LockSurface {
+    pub surface: wl_surface::WlSurface,
+    pub lock_surface: ext_session_lock_surface_v1::ExtSessionLockSurfaceV1,
+    /// Buffer scale last set on the surface.
+    pub buffer_scale: i32,
+}
+
+impl LockSurface {
+    fn destroy(self) {
+        self.lock_surface.destroy();
+        self.surface.destroy();
+    }
+}
+
+impl WaylandAppState {
+    /// Locks or unlocks the session to match `locked`, keeping a lock surface
+    /// on every output while a lock is held.
+    pub(crate) fn sync_session_lock(&mut self, locked: bool, qh: &QueueHandle<Self>) {
+        if !locked {
+            if self.session_lock.is_some() {
+                self.end_session_lock();
+                self.pending_lock_events.push(WallpaperLockEvent::Unlocked);
+            }
+            return;
+        }
+
+        if self.session_lock.is_none() {
+            let Some(manager) = self.session_lock_manager.as_ref() else {
+                return;
+            };
+            info!("Locking the session");
+            self.session_lock = Some(manager.lock(qh, ()));
+            self.session_locked = false;
+        }
+
+        let outputs: Vec<u32> = self.outputs.keys().copied().collect();
+        for output in outputs {
+            self.create_lock_surface(output, qh);
+        }
+    }
+
+    fn create_lock_surface(&mut self, output: u32, qh: &QueueHandle<Self>) {
+        if self.lock_surfaces.contains_key(&output) {
+            return;
+        }
+        let (Some(lock), Some((compositor, _)), Some(wl_output)) = (
+            self.session_lock.as_ref(),
+            self.compositor.as_ref(),
+            self.outputs.get(&output),
+        ) else {
+            return;
+        };
+        let surface = compositor.create_surface(qh, ());
+        let lock_surface = lock.get_lock_surface(&surface, wl_output, qh, output);
+        self.surface_to_output
+            .insert(surface.id().protocol_id(), output);
+        self.lock_surfaces.insert(
+            output,
+            LockSurface {
+                surface,
+                lock_surface,
+                buffer_scale: 1,
+            },
+        );
+    }
+
+    /// Destroys the lock surface of `output`, e.g. when the output goes away.
+    pub(crate) fn release_lock_surface(&mut self, output: u32) {
+        if let Some(lock_surface) = self.lock_surfaces.remove(&output) {
+            self.surface_to_output
+                .remove(&lock_surface.surface.id().protocol_id());
+            lock_surface.destroy();
+        }
+    }
+
+    /// Releases the lock and hands the outputs back to the layer surfaces.
+    fn end_session_lock(&mut self) {
+        if let Some(lock) = self.session_lock.take() {
+            if self.session_locked {
+                info!("Unlocking the session");
+                lock.unlock_and_destroy();
+            } else {
+                lock.destroy();
+            }
+        }
+        self.session_locked = false;
+
+        let outputs: Vec<u32> = self.lock_surfaces.keys().copied().collect();
+        for output in outputs {
+            self.release_lock_surface(output);
+        }
+        let configs: Vec<WaylandSurfaceConfig> = self.layer_configs.values().copied().collect();
+        for config in configs {
+            self.queue_surface_config(config);
+        }
+    }
+}
+
+impl Dispatch<ext_session_lock_manager_v1::ExtSessionLockManagerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &ext_session_lock_manager_v1::ExtSessionLockManagerV1,
+        _event: ext_session_lock_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<ext_session_lock_v1::ExtSessionLockV1, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        _lock: &ext_session_lock_v1::ExtSessionLockV1,
+        event: ext_session_lock_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_session_lock_v1::Event::Locked => {
+                info!("Session locked");
+                state.session_locked = true;
+                state.pending_lock_events.push(WallpaperLockEvent::Locked);
+            }
+            ext_session_lock_v1::Event::Finished => {
+                warn!("The compositor ended the session lock");
+                state.end_session_lock();
+                state.pending_lock_events.push(WallpaperLockEvent::Denied);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ext_session_lock_surface_v1::ExtSessionLockSurfaceV1, u32> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        lock_surface: &ext_session_lock_surface_v1::ExtSessionLockSurfaceV1,
+        event: ext_session_lock_surface_v1::Event,
+        output: &u32,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let ext_session_lock_surface_v1::Event::Configure {
+            serial,
+            width,
+            height,
+        } = event
+        {
+            debug!(
+                "Lock surface configured (output {}): {}x{}",
+                output, width, height
+            );
+            lock_surface.ack_configure(serial);
+            let Some(entry) = state.lock_surfaces.get(output) else {
+                return;
+            };
+            let (offset_x, offset_y) = state
+                .output_info
+                .get(output)
+                .map(|info| (info.x, info.y))
+                .unwrap_or((0, 0));
+            let config = WaylandSurfaceConfig {
+                output: *output,
+                handles: WaylandSurfaceHandles::new(&state.display, &entry.surface),
+                width: width.max(1),
+                height: height.max(1),
+                offset_x,
+                offset_y,
+                scale: state.output_scale(*output),
+            };
+            state.queue_surface_config(config);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+//! Tablet / stylus input via `zwp_tablet_manager_v2`.
+
+use bevy::prelude::*;
+use wayland_client::{
+    Connection, Dispatch, Proxy, QueueHandle, WEnum, event_created_child, protocol::wl_seat,
+};
+use wayland_protocols::wp::tablet::zv2::client::{
+    zwp_tablet_manager_v2, zwp_tablet_pad_dial_v2, zwp_tablet_pad_group_v2, zwp_tablet_pad_ring_v2,
+    zwp_tablet_pad_strip_v2, zwp_tablet_pad_v2, zwp_tablet_seat_v2, zwp_tablet_tool_v2,
+    zwp_tablet_v2,
+};
+
+use super::{PendingPointerEvent, PendingPointerEventKind, WaylandAppState};
+use crate::StylusTool;
+
+/// Highest protocol version handled here.
+pub(crate) const TABLET_MANAGER_VERSION: u32 = 2;
+
+/// A tool announced on one of the tablet seats.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct TabletTool {
+    /// wl_seat protocol id of the tablet seat that announced the tool.
+    pub seat: Option<u32>,
+    pub kind: Option<StylusTool>,
+}
+
+/// Output and surface-local position of a tool in proximity.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct StylusFocus {
+    pub output: u32,
+    /// `None` until the first motion: `proximity_in` carries no position.
+    pub position: Option<Vec2>,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct PendingStylusEvent {
+    pub output: u32,
+    pub position: Option<Vec2>,
+    pub offset: Vec2,
+    pub kind: PendingStylusEventKind,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum PendingStylusEventKind {
+    ProximityIn(Option<StylusTool>),
+    ProximityOut,
+    Motion,
+    Down,
+    Up,
+    Pressure(f32),
+    Tilt(Vec2),
+    Button { button: u32, pressed: bool },
+    Frame,
+}
+
+impl WaylandAppState {
+    /// Requests the tablet seat for `seat` if the manager is bound.
+    pub(crate) fn bind_tablet_seat(&mut self, seat: &wl_seat::WlSeat, qh: &QueueHandle<Self>) {
+        let Some((manager, _)) = self.tablet_manager.as_ref() else {
+            return;
+        };
+        self.tablet_seats
+            .entry(seat.id().protocol_id())
+            .or_insert_with(|| manager.get_tablet_seat(seat, qh, ()));
+    }
+
+    fn push_stylus_event(&mut self, focus: StylusFocus, kind: PendingStylusEventKind) {
+        let offset = self.surface_origin(focus.output).as_vec2();
+        self.pending_stylus_events.push(PendingStylusEvent {
+            output: focus.output,
+            position: focus.position,
+            offset,
+            kind,
+        });
+    }
+
+    /// Mirrors a tool event onto the pointer of the tool's seat, so wallpapers
+    /// that only read [`crate::WallpaperPointerState`] can still be drawn on;
+    /// the tip acts as the left button. Nothing is mirrored before the tool's
+    /// first motion, which is what enters the pointer.
+    fn push_emulated_pointer_event(
+        &mut self,
+        tool_id: u32,
+        focus: StylusFocus,
+        kind: PendingPointerEventKind,
+    ) {
+        let Some(seat) = self.tablet_tools.get(&tool_id).and_then(|tool| tool.seat) else {
+            return;
+        };
+        let Some(position) = focus.position else {
+            return;
+        };
+        let offset = self.surface_origin(focus.output).as_vec2();
+        self.pending_pointer_events.push(PendingPointerEvent {
+            seat,
+            output: focus.output,
+            position,
+            offset,
+            kind,
+        });
+    }
+}
+
+impl Dispatch<zwp_tablet_manager_v2::ZwpTabletManagerV2, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &zwp_tablet_manager_v2::ZwpTabletManagerV2,
+        _event: zwp_tablet_manager_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<zwp_tablet_seat_v2::ZwpTabletSeatV2, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        tablet_seat: &zwp_tablet_seat_v2::ZwpTabletSeatV2,
+        event: zwp_tablet_seat_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_tablet_seat_v2::Event::TabletAdded { id } => {
+                debug!("Tablet added: {}", id.id());
+            }
+            zwp_tablet_seat_v2::Event::ToolAdded { id } => {
+                debug!("Tablet tool added: {}", id.id());
+                let seat = state
+                    .tablet_seats
+                    .iter()
+                    .find(|(_, known)| *known == tablet_seat)
+                    .map(|(seat, _)| *seat);
+                state
+                    .tablet_tools
+                    .insert(id.id().protocol_id(), TabletTool { seat, kind: None });
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(WaylandAppState, zwp_tablet_seat_v2::ZwpTabletSeatV2, [
+        zwp_tablet_seat_v2::EVT_TABLET_ADDED_OPCODE => (zwp_tablet_v2::ZwpTabletV2, ()),
+        zwp_tablet_seat_v2::EVT_TOOL_ADDED_OPCODE => (zwp_tablet_tool_v2::ZwpTabletToolV2, ()),
+        zwp_tablet_seat_v2::EVT_PAD_ADDED_OPCODE => (zwp_tablet_pad_v2::ZwpTabletPadV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_v2::ZwpTabletV2, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        tablet: &zwp_tablet_v2::ZwpTabletV2,
+        event: zwp_tablet_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwp_tablet_v2::Event::Name { name } => debug!("Tablet name: {name}"),
+            zwp_tablet_v2::Event::Removed => tablet.destroy(),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_tool_v2::ZwpTabletToolV2, ()> for WaylandAppState {
+    fn event(
+        state: &mut Self,
+        tool: &zwp_tablet_tool_v2::ZwpTabletToolV2,
+        event: zwp_tablet_tool_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let tool_id = tool.id().protocol_id();
+
+        match event {
+            zwp_tablet_tool_v2::Event::Type { tool_type } => {
+                let kind = match tool_type {
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Pen) => StylusTool::Pen,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Eraser) => StylusTool::Eraser,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Brush) => StylusTool::Brush,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Pencil) => StylusTool::Pencil,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Airbrush) => StylusTool::Airbrush,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Finger) => StylusTool::Finger,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Mouse) => StylusTool::Mouse,
+                    WEnum::Value(zwp_tablet_tool_v2::Type::Lens) => StylusTool::Lens,
+                    _ => return,
+                };
+                state.tablet_tools.entry(tool_id).or_default().kind = Some(kind);
+            }
+            zwp_tablet_tool_v2::Event::ProximityIn { surface, .. } => {
+                let Some(output) = state
+                    .surface_to_output
+                    .get(&surface.id().protocol_id())
+                    .copied()
+                else {
+                    return;
+                };
+                let focus = StylusFocus {
+                    output,
+                    position: None,
+                };
+                state.stylus_focus.insert(tool_id, focus);
+                let kind = state.tablet_tools.get(&tool_id).and_then(|tool| tool.kind);
+                state.push_stylus_event(focus, PendingStylusEventKind::ProximityIn(kind));
+            }
+            zwp_tablet_tool_v2::Event::ProximityOut => {
+                if let Some(focus) = state.stylus_focus.remove(&tool_id) {
+                    state.push_stylus_event(focus, PendingStylusEventKind::ProximityOut);
+                    state.push_emulated_pointer_event(
+                        tool_id,
+                        focus,
+                        PendingPointerEventKind::Leave,
+                    );
+                }
+            }
+            zwp_tablet_tool_v2::Event::Motion { x, y } => {
+                if let Some(focus) = state.stylus_focus.get_mut(&tool_id) {
+                    let entered = focus.position.is_some();
+                    focus.position = Some(Vec2::new(x as f32, y as f32));
+                    let focus = *focus;
+                    state.push_stylus_event(focus, PendingStylusEventKind::Motion);
+                    let kind = if entered {
+                        PendingPointerEventKind::Motion
+                    } else {
+                        PendingPointerEventKind::Enter
+                    };
+                    state.push_emulated_pointer_event(tool_id, focus, kind);
+                }
+            }
+            zwp_tablet_tool_v2::Event::Down { .. } => {
+                if let Some(focus) = state.stylus_focus.get(&tool_id).copied() {
+                    state.push_stylus_event(focus, PendingStylusEventKind::Down);
+                    state.push_emulated_pointer_event(
+                        tool_id,
+                        focus,
+                        PendingPointerEventKind::Button {
+                            button: Some(MouseButton::Left),
+                            pressed: true,
+                        },
+                    );
+                }
+            }
+            zwp_tablet_tool_v2::Event::Up => {
+                if let Some(focus) = state.stylus_focus.get(&tool_id).copied() {
+                    state.push_stylus_event(focus, PendingStylusEventKind::Up);
+                    state.push_emulated_pointer_event(
+                        tool_id,
+                        focus,
+                        PendingPointerEventKind::Button {
+                            button: Some(MouseButton::Left),
+                            pressed: false,
+                        },
+                    );
+                }
+            }
+            zwp_tablet_tool_v2::Event::Pressure { pressure } => {
+                if let Some(focus) = state.stylus_focus.get(&tool_id).copied() {
+                    // Pressure is normalized to 0..=65535 by the protocol.
+                    let pressure = pressure as f32 / 65535.0;
+                    state.push_stylus_event(focus, PendingStylusEventKind::Pressure(pressure));
+                }
+            }
+            zwp_tablet_tool_v2::Event::Tilt { tilt_x, tilt_y } => {
+                if let Some(focus) = state.stylus_focus.get(&tool_id).copied() {
+                    let tilt = Vec2::new(tilt_x as f32, tilt_y as f32);
+                    state.push_stylus_event(focus, PendingStylusEventKind::Tilt(tilt));
+                }
+            }
+            zwp_tablet_tool_v2::Event::Button {
+                button,
+                state: button_state,
+                ..
+            } => {
+                if let Some(focus) = state.stylus_focus.get(&tool_id).copied() {
+                    let pressed = matches!(
+                        button_state,
+                        WEnum::Value(zwp_tablet_tool_v2::ButtonState::Pressed)
+                    );
+                    state.push_stylus_event(
+                        focus,
+                        PendingStylusEventKind::Button { button, pressed },
+                    );
+                }
+            }
+            zwp_tablet_tool_v2::Event::Frame { .. } => {
+                if let Some(focus) = state.stylus_focus.get(&tool_id).copied() {
+                    state.push_stylus_event(focus, PendingStylusEventKind::Frame);
+                }
+            }
+            zwp_tablet_tool_v2::Event::Removed => {
+                state.stylus_focus.remove(&tool_id);
+                state.tablet_tools.remove(&tool_id);
+                tool.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<zwp_tablet_pad_v2::ZwpTabletPadV2, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        pad: &zwp_tablet_pad_v2::ZwpTabletPadV2,
+        event: zwp_tablet_pad_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Pads (buttons/rings/strips on the tablet) aren't exposed.
+        if let zwp_tablet_pad_v2::Event::Removed = event {
+            pad.destroy();
+        }
+    }
+
+    event_created_child!(WaylandAppState, zwp_tablet_pad_v2::ZwpTabletPadV2, [
+        zwp_tablet_pad_v2::EVT_GROUP_OPCODE => (zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _group: &zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2,
+        _event: zwp_tablet_pad_group_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: pad groups are ignored.
+    }
+
+    event_created_child!(WaylandAppState, zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2, [
+        zwp_tablet_pad_group_v2::EVT_RING_OPCODE => (zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2, ()),
+        zwp_tablet_pad_group_v2::EVT_STRIP_OPCODE => (zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2, ()),
+        zwp_tablet_pad_group_v2::EVT_DIAL_OPCODE => (zwp_tablet_pad_dial_v2::ZwpTabletPadDialV2, ()),
+    ]);
+}
+
+impl Dispatch<zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _ring: &zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2,
+        _event: zwp_tablet_pad_ring_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: pad rings are ignored.
+    }
+}
+
+impl Dispatch<zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _strip: &zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2,
+        _event: zwp_tablet_pad_strip_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: pad strips are ignored.
+    }
+}
+
+impl Dispatch<zwp_tablet_pad_dial_v2::ZwpTabletPadDialV2, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _dial: &zwp_tablet_pad_dial_v2::ZwpTabletPadDialV2,
+        _event: zwp_tablet_pad_dial_v2::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: pad dials are ignored.
+    }
+}
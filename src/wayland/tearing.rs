@@ -0,0 +1,70 @@
+//! Async presentation hints via `wp_tearing_control_v1`.
+
+use std::collections::HashSet;
+
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::wp::tearing_control::v1::client::{
+    wp_tearing_control_manager_v1, wp_tearing_control_v1,
+};
+
+use super::WaylandAppState;
+
+impl WaylandAppState {
+    /// Sets the async hint on every wallpaper surface when `allow` is set and
+    /// reverts it to vsync otherwise. The hint takes effect on the next commit.
+    pub(crate) fn sync_tearing_hints(&mut self, allow: bool, qh: &QueueHandle<Self>) {
+        let Some(manager) = self.tearing_manager.as_ref() else {
+            return;
+        };
+        for surface in self.surfaces.values_mut() {
+            match (surface.tearing.is_some(), allow) {
+                (false, true) => {
+                    let control = manager.get_tearing_control(&surface.surface, qh, ());
+                    control.set_presentation_hint(wp_tearing_control_v1::PresentationHint::Async);
+                    surface.tearing = Some(control);
+                }
+                (true, false) => {
+                    if let Some(control) = surface.tearing.take() {
+                        control.destroy();
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Outputs whose surface carries the async hint.
+    pub(crate) fn tearing_outputs(&self) -> HashSet<u32> {
+        self.surfaces
+            .iter()
+            .filter(|(_, surface)| surface.tearing.is_some())
+            .map(|(output, _)| *output)
+            .collect()
+    }
+}
+
+impl Dispatch<wp_tearing_control_manager_v1::WpTearingControlManagerV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _manager: &wp_tearing_control_manager_v1::WpTearingControlManagerV1,
+        _event: wp_tearing_control_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the manager has no events.
+    }
+}
+
+impl Dispatch<wp_tearing_control_v1::WpTearingControlV1, ()> for WaylandAppState {
+    fn event(
+        _state: &mut Self,
+        _control: &wp_tearing_control_v1::WpTearingControlV1,
+        _event: wp_tearing_control_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Do nothing: the tearing control object has no events.
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+/// (Wayland only) What the compositor advertised and what the backend made of
+/// it, for bug reports about the wallpaper not showing up.
+///
+/// Refreshed every frame by the Wayland backend and logged once after the
+/// connection is established; dump it with `{:#?}` instead of running
+/// `wayland-info`. Other backends leave it at the default.
+#[derive(Resource, Clone, Debug, Default, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct WallpaperWaylandDiagnostics {
+    /// Every global the registry advertised, ordered by registry name.
+    pub globals: Vec<WaylandGlobalInfo>,
+    /// Bound `wl_compositor` version.
+    pub compositor_version: Option<u32>,
+    /// Bound `zwlr_layer_shell_v1` version; `None` means no wallpaper surfaces.
+    pub layer_shell_version: Option<u32>,
+    /// Whether `zxdg_output_manager_v1` is bound for logical output geometry.
+    pub xdg_output: bool,
+    /// Whether `zwlr_output_manager_v1` is bound for head state.
+    pub output_management: bool,
+    pub seat_count: usize,
+    pub outputs: Vec<WaylandOutputDiagnostics>,
+}
+
+/// A global announced by the Wayland registry.
+#[derive(Clone, Debug, PartialEq, Eq, Reflect)]
+pub struct WaylandGlobalInfo {
+    /// Registry name of the global.
+    pub name: u32,
+    pub interface: String,
+    /// Version advertised by the compositor (the backend may bind lower).
+    pub version: u32,
+}
+
+/// State of one `wl_output` as seen by the backend.
+#[derive(Clone, Debug, PartialEq, Eq, Reflect)]
+pub struct WaylandOutputDiagnostics {
+    /// `wl_output` global name, as in [`crate::PointerSample::output`].
+    pub output: u32,
+    /// Connector name (`wl_output` v4, or xdg-output on older compositors);
+    /// what [`crate::WallpaperTargetMonitor::Name`] matches.
+    pub name: Option<String>,
+    /// Monitor description, e.g. make and model.
+    pub description: Option<String>,
+    /// Identification from `zwlr_output_manager_v1`, when bound.
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    /// `false` when the head is disabled but its `wl_output` remains; such
+    /// outputs get no surface.
+    pub enabled: bool,
+    /// Current mode in physical pixels, if reported.
+    pub mode: Option<UVec2>,
+    pub scale: i32,
+    /// Whether a layer surface exists for the output.
+    pub layer_surface: bool,
+    /// Whether the render world configured a wgpu surface for the output.
+    pub gpu_surface: bool,
+    /// Whether the output is the one [`crate::WallpaperTargetMonitor::Primary`]
+    /// picks.
+    pub primary: bool,
+}
@@ -1,11 +1,18 @@
 use bevy::{
     ecs::system::SystemParam,
-    input::{ButtonState, mouse::MouseButtonInput},
+    input::{
+        ButtonState,
+        mouse::{MouseScrollUnit, MouseWheel},
+    },
     prelude::*,
-    window::{CursorMoved, PrimaryWindow, WindowMoved, WindowResized},
+    window::{PrimaryWindow, WindowEvent, WindowMoved, WindowResized},
 };
 
-use crate::{PointerButton, PointerSample, WallpaperPointerState, WallpaperSurfaceInfo};
+use crate::{
+    PointerButton, PointerSample, WallpaperMemoryBudget, WallpaperPointerEvent,
+    WallpaperPointerEventKind, WallpaperPointerState, WallpaperScrollEvent, WallpaperSurfaceInfo,
+    plugin::windowed_mode_active,
+};
 
 /// Backend that keeps wallpaper APIs working when rendering into a normal window.
 pub(crate) struct WindowedBackendPlugin;
@@ -13,7 +20,7 @@ pub(crate) struct WindowedBackendPlugin;
 impl Plugin for WindowedBackendPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<WindowedBackendState>()
-            .add_systems(Update, windowed_backend_system);
+            .add_systems(Update, windowed_backend_system.run_if(windowed_mode_active));
     }
 }
 
@@ -26,16 +33,21 @@ struct WindowedBackendState {
 #[derive(SystemParam)]
 struct WindowedBackendParams<'w, 's> {
     windows: Query<'w, 's, (Entity, &'static Window), With<PrimaryWindow>>,
-    cursor_moved_events: MessageReader<'w, 's, CursorMoved>,
-    mouse_button_events: MessageReader<'w, 's, MouseButtonInput>,
+    /// Pointer enter/move/button/leave, read from the combined stream so
+    /// their order within the frame is kept.
+    window_events: MessageReader<'w, 's, WindowEvent>,
+    mouse_wheel_events: MessageReader<'w, 's, MouseWheel>,
     window_resized_events: MessageReader<'w, 's, WindowResized>,
     window_moved_events: MessageReader<'w, 's, WindowMoved>,
+    pointer_events: MessageWriter<'w, WallpaperPointerEvent>,
+    scroll_events: MessageWriter<'w, WallpaperScrollEvent>,
 }
 
 fn windowed_backend_system(
     mut state: ResMut<WindowedBackendState>,
     mut pointer_state: ResMut<WallpaperPointerState>,
     mut surface_info: ResMut<WallpaperSurfaceInfo>,
+    memory_budget: Res<WallpaperMemoryBudget>,
     mut params: WindowedBackendParams,
 ) {
     let Some((window_entity, window)) = params.windows.iter().next() else {
@@ -72,77 +84,154 @@ fn windowed_backend_system(
         latest_width.max(1.0) as u32,
         latest_height.max(1.0) as u32,
     );
+    surface_info.scale_factor = Some(window.scale_factor() * memory_budget.resolution_scale);
 
     let mut saw_cursor_event = false;
     let mut saw_button_event = false;
 
-    // Track cursor movement in logical coordinates relative to desktop by adding offset.
-    for evt in params.cursor_moved_events.read() {
-        if evt.window != window_entity {
-            continue;
-        }
-        saw_cursor_event = true;
-
-        let global_position = evt.position + state.logical_offset;
-        let prev_position = pointer_state
-            .last
-            .as_ref()
-            .map(|p| p.position)
-            .unwrap_or(global_position);
-
-        let pressed = pointer_state
-            .last
-            .as_ref()
-            .map(|p| p.pressed.clone())
-            .unwrap_or_default();
-
-        pointer_state.last = Some(PointerSample {
-            output: None,
-            position: global_position,
-            delta: global_position - prev_position,
-            last_button: None,
-            pressed,
-        });
-    }
+    for window_event in params.window_events.read() {
+        match window_event {
+            WindowEvent::CursorEntered(evt) if evt.window == window_entity => {
+                let position = pointer_state
+                    .last
+                    .as_ref()
+                    .map(|p| p.position)
+                    .unwrap_or(state.logical_offset);
+                params.pointer_events.write(WallpaperPointerEvent {
+                    output: None,
+                    position,
+                    kind: WallpaperPointerEventKind::Entered,
+                });
+            }
+            WindowEvent::CursorMoved(evt) if evt.window == window_entity => {
+                // Later events of the same frame add to its delta and keep
+                // its last button, as on Wayland.
+                let updated = saw_cursor_event || saw_button_event;
+                saw_cursor_event = true;
 
-    // Mouse buttons are handled separately so clicks without movement still update state.
-    for evt in params.mouse_button_events.read() {
-        if evt.window != window_entity {
-            continue;
-        }
-        saw_button_event = true;
+                let global_position = evt.position + state.logical_offset;
+                let prev_position = pointer_state
+                    .last
+                    .as_ref()
+                    .map(|p| p.position)
+                    .unwrap_or(global_position);
 
-        let mut pressed = pointer_state
-            .last
-            .as_ref()
-            .map(|p| p.pressed.clone())
-            .unwrap_or_default();
+                let prev = pointer_state.last.as_ref().filter(|_| updated);
+                let delta = prev.map_or(Vec2::ZERO, |p| p.delta) + global_position - prev_position;
+                let last_button = prev.and_then(|p| p.last_button);
+                let pressed = pointer_state
+                    .last
+                    .as_ref()
+                    .map(|p| p.pressed.clone())
+                    .unwrap_or_default();
 
-        match evt.state {
-            ButtonState::Pressed => {
-                pressed.insert(evt.button);
+                pointer_state.last = Some(PointerSample {
+                    seat: None,
+                    output: None,
+                    position: global_position,
+                    delta,
+                    entered_output: false,
+                    last_button,
+                    pressed,
+                    scroll_discrete: Vec2::ZERO,
+                    scroll_smooth: Vec2::ZERO,
+                });
+                params.pointer_events.write(WallpaperPointerEvent {
+                    output: None,
+                    position: global_position,
+                    kind: WallpaperPointerEventKind::Moved {
+                        delta: global_position - prev_position,
+                    },
+                });
             }
-            ButtonState::Released => {
-                pressed.remove(&evt.button);
+            WindowEvent::MouseButtonInput(evt) if evt.window == window_entity => {
+                let updated = saw_cursor_event || saw_button_event;
+                saw_button_event = true;
+
+                let mut pressed = pointer_state
+                    .last
+                    .as_ref()
+                    .map(|p| p.pressed.clone())
+                    .unwrap_or_default();
+
+                match evt.state {
+                    ButtonState::Pressed => {
+                        pressed.insert(evt.button);
+                    }
+                    ButtonState::Released => {
+                        pressed.remove(&evt.button);
+                    }
+                }
+
+                let position = pointer_state
+                    .last
+                    .as_ref()
+                    .map(|p| p.position)
+                    .unwrap_or(state.logical_offset);
+
+                pointer_state.last = Some(PointerSample {
+                    seat: None,
+                    output: None,
+                    position,
+                    delta: pointer_state
+                        .last
+                        .as_ref()
+                        .filter(|_| updated)
+                        .map_or(Vec2::ZERO, |p| p.delta),
+                    entered_output: false,
+                    last_button: Some(PointerButton {
+                        button: Some(evt.button),
+                        pressed: evt.state == ButtonState::Pressed,
+                    }),
+                    pressed,
+                    scroll_discrete: Vec2::ZERO,
+                    scroll_smooth: Vec2::ZERO,
+                });
+                params.pointer_events.write(WallpaperPointerEvent {
+                    output: None,
+                    position,
+                    kind: WallpaperPointerEventKind::Button {
+                        button: Some(evt.button),
+                        pressed: evt.state == ButtonState::Pressed,
+                    },
+                });
+            }
+            WindowEvent::CursorLeft(evt) if evt.window == window_entity => {
+                let position = pointer_state
+                    .last
+                    .as_ref()
+                    .map(|p| p.position)
+                    .unwrap_or(state.logical_offset);
+                params.pointer_events.write(WallpaperPointerEvent {
+                    output: None,
+                    position,
+                    kind: WallpaperPointerEventKind::Left,
+                });
             }
+            _ => {}
         }
+    }
 
-        let position = pointer_state
-            .last
-            .as_ref()
-            .map(|p| p.position)
-            .unwrap_or(state.logical_offset);
-
-        pointer_state.last = Some(PointerSample {
-            output: None,
-            position,
-            delta: Vec2::ZERO,
-            last_button: Some(PointerButton {
-                button: Some(evt.button),
-                pressed: evt.state == ButtonState::Pressed,
-            }),
-            pressed,
-        });
+    // Bevy's wheel deltas point the way content moves; flip them to the
+    // scroll direction Wayland uses.
+    let mut scroll_discrete = Vec2::ZERO;
+    let mut scroll_smooth = Vec2::ZERO;
+    for evt in params.mouse_wheel_events.read() {
+        if evt.window != window_entity {
+            continue;
+        }
+        let delta = -Vec2::new(evt.x, evt.y);
+        match evt.unit {
+            MouseScrollUnit::Line => scroll_discrete += delta,
+            MouseScrollUnit::Pixel => scroll_smooth += delta / scale_factor,
+        }
+    }
+    if let Some(sample) = pointer_state.last.as_mut() {
+        sample.scroll_discrete = scroll_discrete;
+        sample.scroll_smooth = scroll_smooth;
+        params
+            .scroll_events
+            .write_batch(WallpaperScrollEvent::from_sample(sample));
     }
 
     if !saw_cursor_event
@@ -153,3 +242,90 @@ fn windowed_backend_system(
         sample.last_button = None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::mouse::MouseButtonInput;
+    use bevy::window::{CursorEntered, CursorLeft, CursorMoved};
+
+    use super::*;
+
+    fn windowed_app() -> (App, Entity) {
+        let mut app = App::new();
+        app.init_resource::<WallpaperPointerState>()
+            .init_resource::<WallpaperSurfaceInfo>()
+            .init_resource::<WallpaperMemoryBudget>()
+            .add_message::<WindowEvent>()
+            .add_message::<MouseWheel>()
+            .add_message::<WindowResized>()
+            .add_message::<WindowMoved>()
+            .add_message::<WallpaperPointerEvent>()
+            .add_message::<WallpaperScrollEvent>()
+            .insert_resource(crate::WallpaperDisplayMode::Windowed)
+            .add_plugins(WindowedBackendPlugin);
+        let window = app
+            .world_mut()
+            .spawn((Window::default(), PrimaryWindow))
+            .id();
+        (app, window)
+    }
+
+    fn moved(window: Entity, x: f32) -> WindowEvent {
+        WindowEvent::CursorMoved(CursorMoved {
+            window,
+            position: Vec2::new(x, 0.0),
+            delta: None,
+        })
+    }
+
+    fn button(window: Entity, state: ButtonState) -> WindowEvent {
+        WindowEvent::MouseButtonInput(MouseButtonInput {
+            button: MouseButton::Left,
+            state,
+            window,
+        })
+    }
+
+    fn kinds(app: &App) -> Vec<WallpaperPointerEventKind> {
+        let messages = app.world().resource::<Messages<WallpaperPointerEvent>>();
+        messages
+            .iter_current_update_messages()
+            .map(|evt| evt.kind)
+            .collect()
+    }
+
+    #[test]
+    fn pointer_events_keep_arrival_order() {
+        let (mut app, window) = windowed_app();
+        app.world_mut()
+            .write_message_batch([
+                moved(window, 10.0),
+                WindowEvent::CursorLeft(CursorLeft { window }),
+                WindowEvent::CursorEntered(CursorEntered { window }),
+                button(window, ButtonState::Pressed),
+                moved(window, 14.0),
+            ])
+            .unwrap();
+        app.update();
+
+        assert_eq!(
+            kinds(&app),
+            [
+                WallpaperPointerEventKind::Moved { delta: Vec2::ZERO },
+                WallpaperPointerEventKind::Left,
+                WallpaperPointerEventKind::Entered,
+                WallpaperPointerEventKind::Button {
+                    button: Some(MouseButton::Left),
+                    pressed: true,
+                },
+                WallpaperPointerEventKind::Moved {
+                    delta: Vec2::new(4.0, 0.0),
+                },
+            ]
+        );
+        let pointer_state = app.world().resource::<WallpaperPointerState>();
+        let sample = pointer_state.last.as_ref().unwrap();
+        assert_eq!(sample.delta, Vec2::new(4.0, 0.0));
+        assert_eq!(sample.last_button.map(|button| button.pressed), Some(true));
+    }
+}
@@ -1,78 +1,387 @@
 use crate::{
-    PointerButton, PointerSample, WallpaperPointerState, WallpaperSurfaceInfo,
+    PointerSample, WallpaperAlphaMode, WallpaperBackend, WallpaperDisplayMode,
+    WallpaperMonitorConnected, WallpaperPointerEvent, WallpaperPointerState, WallpaperSurfaceInfo,
     WallpaperTargetMonitor,
+    input::{CaptureInput, detect_last_button, pointer_events_between},
+    monitor_events::MonitorMessages,
+    plugin::wallpaper_mode_active,
+    screen_lock::LockSignal,
 };
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bevy::window::{Monitor, PrimaryMonitor, RawHandleWrapper};
+use bevy::window::{Monitor, PrimaryMonitor, RawHandleWrapper, WindowPosition};
 use raw_window_handle::RawWindowHandle;
+use std::cell::RefCell;
 use std::collections::HashSet;
-use windows::Win32::Foundation::POINT;
-use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use std::sync::{OnceLock, mpsc};
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{
+    GlobalFree, HANDLE, HGLOBAL, HWND, LPARAM, LRESULT, RPC_E_CHANGED_MODE, WPARAM,
+};
+use windows::Win32::Foundation::{POINT, RECT};
+use windows::Win32::Graphics::Gdi::{
+    DISPLAY_DEVICEW, EnumDisplayDevicesW, EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR,
+    MONITOR_DEFAULTTONULL, MONITORINFO, MONITORINFOEXW, MonitorFromPoint,
+};
+use windows::Win32::System::Com::{
+    CLSCTX_ALL, COINIT_APARTMENTTHREADED, CoCreateInstance, CoInitializeEx, CoUninitialize,
+};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Memory::{
+    GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock,
+};
+use windows::Win32::System::Ole::{CF_DIB, CF_UNICODETEXT, CLIPBOARD_FORMAT};
+use windows::Win32::System::RemoteDesktop::{
+    NOTIFY_FOR_THIS_SESSION, WTSRegisterSessionNotification,
+};
+use windows::Win32::UI::HiDpi::{
+    DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, GetDpiForMonitor, MDT_EFFECTIVE_DPI,
+    SetProcessDpiAwarenessContext,
+};
 use windows::Win32::UI::Input::KeyboardAndMouse::{
-    GetAsyncKeyState, VK_LBUTTON, VK_MBUTTON, VK_RBUTTON,
+    GetAsyncKeyState, VK_LBUTTON, VK_MBUTTON, VK_RBUTTON, VK_XBUTTON1, VK_XBUTTON2,
 };
+use windows::Win32::UI::Shell::{IVirtualDesktopManager, VirtualDesktopManager};
 use windows::Win32::UI::WindowsAndMessaging::{
-    EnumChildWindows, EnumWindows, FindWindowExW, FindWindowW, GWL_EXSTYLE, GWL_STYLE,
-    GetClassNameW, GetCursorPos, GetWindowLongW, PostMessageW, SEND_MESSAGE_TIMEOUT_FLAGS,
-    SendMessageTimeoutW, SetParent, SetWindowLongW, WM_CLOSE, WS_CHILD, WS_EX_APPWINDOW,
-    WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_OVERLAPPED, WS_POPUP,
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, EnumChildWindows, EnumWindows,
+    FindWindowExW, FindWindowW, GWL_EXSTYLE, GWL_STYLE, GetClassNameW, GetCursorPos, GetMessageW,
+    GetWindowLongW, HWND_MESSAGE, MSG, PostMessageW, RegisterClassW, SEND_MESSAGE_TIMEOUT_FLAGS,
+    SWP_FRAMECHANGED, SWP_NOMOVE, SWP_NOSIZE, SWP_NOZORDER, SendMessageTimeoutW, SetParent,
+    SetWindowLongW, SetWindowPos, WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLOSE, WM_WTSSESSION_CHANGE,
+    WNDCLASSW, WS_CHILD, WS_EX_APPWINDOW, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_OVERLAPPEDWINDOW,
+    WS_POPUP, WTS_SESSION_LOCK, WTS_SESSION_UNLOCK,
 };
-use windows::core::{BOOL, PCWSTR};
+use windows::core::{BOOL, PCWSTR, w};
 
 #[derive(Default)]
 pub(crate) struct WallpaperWindowsPlugin;
 
 impl Plugin for WallpaperWindowsPlugin {
     fn build(&self, app: &mut App) {
-        let workerw = find_workerw().expect("workerw not found.");
-        app.add_systems(Startup, attach_wallpaper_windows_system)
-            .add_systems(
-                Update,
-                (
-                    update_window_position_and_size_system
-                        .run_if(resource_changed::<WallpaperTargetMonitor>),
-                    update_pointer_and_surface_info_system,
-                )
-                    .chain(),
+        app.insert_resource(WallpaperBackend::Windows);
+        enable_per_monitor_dpi_awareness();
+        if app
+            .world()
+            .get_resource::<WallpaperAlphaMode>()
+            .is_some_and(|mode| *mode == WallpaperAlphaMode::Transparent)
+        {
+            warn!("WallpaperAlphaMode::Transparent is not supported on Windows; rendering opaque");
+        }
+
+        let workerw = find_workerw();
+        if workerw.is_none() {
+            warn!("WorkerW not found; retrying in the background");
+        }
+        app.add_systems(
+            Startup,
+            attach_wallpaper_windows_system.run_if(wallpaper_mode_active),
+        )
+        .add_systems(
+            Update,
+            (
+                retry_find_workerw_system,
+                switch_display_mode_system.run_if(resource_changed::<WallpaperDisplayMode>),
+                // Not in windowed mode, where it would undo the windowed size.
+                update_window_position_and_size_system.run_if(
+                    wallpaper_mode_active.and_then(
+                        resource_changed::<WallpaperTargetMonitor>
+                            .or_else(resource_changed::<WallpaperDisplayMode>)
+                            .or_else(any_match_filter::<Changed<Monitor>>)
+                            .or_else(any_component_removed::<Monitor>),
+                    ),
+                ),
+                update_pointer_and_surface_info_system,
             )
-            .insert_non_send(workerw);
+                .chain()
+                .run_if(wallpaper_mode_active.or_else(resource_changed::<WallpaperDisplayMode>)),
+        )
+        .add_systems(
+            Update,
+            write_monitor_messages_system.after(update_window_position_and_size_system),
+        )
+        .init_resource::<WorkerWRetry>()
+        .insert_non_send(workerw)
+        .insert_non_send(WindowsMonitorInfo::default());
+    }
+}
+
+/// Initial delay before searching for WorkerW again.
+const WORKERW_RETRY_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// Upper bound for the WorkerW retry backoff.
+const WORKERW_RETRY_MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Schedules WorkerW lookups while Explorer hasn't created one yet (e.g. right
+/// after login or an Explorer restart).
+#[derive(Resource)]
+struct WorkerWRetry {
+    backoff: Duration,
+    next_attempt: Instant,
+}
+
+impl Default for WorkerWRetry {
+    fn default() -> Self {
+        Self {
+            backoff: WORKERW_RETRY_INITIAL_BACKOFF,
+            next_attempt: Instant::now() + WORKERW_RETRY_INITIAL_BACKOFF,
+        }
     }
 }
 
+fn retry_find_workerw_system(
+    mut workerw: NonSendMut<Option<HWND>>,
+    mut retry: ResMut<WorkerWRetry>,
+    mut target_monitor: ResMut<WallpaperTargetMonitor>,
+    handle_wrappers: Query<&RawHandleWrapper, With<Window>>,
+) {
+    if workerw.is_some() {
+        return;
+    }
+    let now = Instant::now();
+    if now < retry.next_attempt {
+        return;
+    }
+
+    let Some(found) = find_workerw() else {
+        retry.next_attempt = now + retry.backoff;
+        retry.backoff = (retry.backoff * 2).min(WORKERW_RETRY_MAX_BACKOFF);
+        return;
+    };
+    info!("WorkerW found; attaching wallpaper windows");
+    *workerw = Some(found);
+    for handle_wrapper in &handle_wrappers {
+        if let Some(hwnd) = window_hwnd(handle_wrapper) {
+            close_duplicate_instances(found, hwnd);
+            attach_to_workerw(found, hwnd);
+        }
+    }
+    // Window positions are relative to WorkerW once attached; lay out again.
+    target_monitor.set_changed();
+}
+
 fn attach_wallpaper_windows_system(
-    workerw: NonSend<HWND>,
+    workerw: NonSend<Option<HWND>>,
     handle_wrappers: Query<&RawHandleWrapper, With<Window>>,
 ) {
+    let Some(workerw) = *workerw else {
+        warn!("WorkerW not available yet; the wallpaper window stays detached for now");
+        return;
+    };
     for handle_wrapper in handle_wrappers {
-        let raw_handle = handle_wrapper.get_window_handle();
+        if let Some(hwnd) = window_hwnd(handle_wrapper) {
+            close_duplicate_instances(workerw, hwnd);
+            attach_to_workerw(workerw, hwnd);
+        }
+    }
+}
 
-        if let RawWindowHandle::Win32(win32_handle) = raw_handle {
-            let hwnd = win32_handle.hwnd.get() as *mut std::ffi::c_void;
+/// Re-parents the window when [`WallpaperDisplayMode`] changes at runtime.
+fn switch_display_mode_system(
+    mode: Res<WallpaperDisplayMode>,
+    workerw: NonSend<Option<HWND>>,
+    mut windows: Query<(&RawHandleWrapper, &mut Window)>,
+) {
+    if mode.is_added() {
+        return;
+    }
 
-            close_duplicate_instances(*workerw, HWND(hwnd));
+    for (handle_wrapper, mut window) in &mut windows {
+        let Some(hwnd) = window_hwnd(handle_wrapper) else {
+            continue;
+        };
+        match *mode {
+            WallpaperDisplayMode::Wallpaper => match *workerw {
+                Some(workerw) => attach_to_workerw(workerw, hwnd),
+                None => warn!("WorkerW not available yet; cannot attach the wallpaper window"),
+            },
+            WallpaperDisplayMode::Windowed => {
+                detach_from_workerw(hwnd);
+                window.position = WindowPosition::Centered(MonitorSelection::Primary);
+                window.resolution.set(1280.0, 720.0);
+            }
+        }
+    }
+}
 
-            unsafe {
-                let current_style = GetWindowLongW(HWND(hwnd), GWL_STYLE) as u32;
-                let new_style = (current_style & !(WS_POPUP.0 | WS_OVERLAPPED.0)) | WS_CHILD.0;
-                SetWindowLongW(HWND(hwnd), GWL_STYLE, new_style as i32);
+fn window_hwnd(handle_wrapper: &RawHandleWrapper) -> Option<HWND> {
+    match handle_wrapper.get_window_handle() {
+        RawWindowHandle::Win32(win32_handle) => {
+            Some(HWND(win32_handle.hwnd.get() as *mut std::ffi::c_void))
+        }
+        _ => None,
+    }
+}
 
-                let current_ex_style = GetWindowLongW(HWND(hwnd), GWL_EXSTYLE) as u32;
-                let cleared = current_ex_style & !WS_EX_APPWINDOW.0;
-                let ex_style = cleared | WS_EX_NOACTIVATE.0 | WS_EX_TOOLWINDOW.0;
-                SetWindowLongW(HWND(hwnd), GWL_EXSTYLE, ex_style as i32);
+fn attach_to_workerw(workerw: HWND, hwnd: HWND) {
+    unsafe {
+        let current_style = GetWindowLongW(hwnd, GWL_STYLE) as u32;
+        let new_style = (current_style & !(WS_POPUP.0 | WS_OVERLAPPEDWINDOW.0)) | WS_CHILD.0;
+        SetWindowLongW(hwnd, GWL_STYLE, new_style as i32);
 
-                SetParent(HWND(hwnd), Some(*workerw)).expect("Failed to set parent");
-            };
+        let current_ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
+        let cleared = current_ex_style & !WS_EX_APPWINDOW.0;
+        let ex_style = cleared | WS_EX_NOACTIVATE.0 | WS_EX_TOOLWINDOW.0;
+        SetWindowLongW(hwnd, GWL_EXSTYLE, ex_style as i32);
+
+        if let Err(err) = SetParent(hwnd, Some(workerw)) {
+            warn!("Failed to attach window to WorkerW: {err}");
+        }
+    }
+}
+
+/// Undoes [`attach_to_workerw`], turning the window back into a normal
+/// top-level window with a title bar and resizable frame.
+fn detach_from_workerw(hwnd: HWND) {
+    unsafe {
+        if let Err(err) = SetParent(hwnd, None) {
+            warn!("Failed to detach window from WorkerW: {err}");
+        }
+
+        let current_style = GetWindowLongW(hwnd, GWL_STYLE) as u32;
+        let new_style = (current_style & !(WS_CHILD.0 | WS_POPUP.0)) | WS_OVERLAPPEDWINDOW.0;
+        SetWindowLongW(hwnd, GWL_STYLE, new_style as i32);
+
+        let current_ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
+        let cleared = current_ex_style & !(WS_EX_NOACTIVATE.0 | WS_EX_TOOLWINDOW.0);
+        SetWindowLongW(hwnd, GWL_EXSTYLE, (cleared | WS_EX_APPWINDOW.0) as i32);
+
+        // Frame style changes only show up once the frame is recalculated.
+        let flags = SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER;
+        if let Err(err) = SetWindowPos(hwnd, None, 0, 0, 0, 0, flags) {
+            warn!("Failed to refresh the window frame: {err}");
         }
     }
 }
 
+/// Device and friendly names of the monitors, which Bevy's [`Monitor`]
+/// doesn't carry. Refreshed whenever the monitor list changes.
+#[derive(Default)]
+pub(crate) struct WindowsMonitorInfo {
+    /// Handle, device name (e.g. `\\.\DISPLAY1`) and friendly name (the
+    /// monitor model) of each monitor.
+    pub monitors: Vec<(HMONITOR, String, String)>,
+}
+
+impl WindowsMonitorInfo {
+    fn enumerate() -> Self {
+        let mut handles: Vec<HMONITOR> = Vec::new();
+        unsafe {
+            _ = EnumDisplayMonitors(
+                None,
+                None,
+                Some(enum_monitors_proc),
+                LPARAM(&mut handles as *mut _ as isize),
+            );
+        }
+        let monitors = handles
+            .into_iter()
+            .filter_map(|handle| {
+                let (device, friendly) = monitor_names(handle)?;
+                Some((handle, device, friendly))
+            })
+            .collect();
+        Self { monitors }
+    }
+
+    /// Device and friendly name of `monitor`.
+    fn names(&self, monitor: &Monitor) -> Option<(&str, &str)> {
+        let center = monitor.physical_position
+            + ivec2(
+                monitor.physical_width as i32,
+                monitor.physical_height as i32,
+            ) / 2;
+        let handle = monitor_at(center)?;
+        self.monitors
+            .iter()
+            .find(|(h, _, _)| *h == handle)
+            .map(|(_, device, friendly)| (device.as_str(), friendly.as_str()))
+    }
+
+    /// Whether [`WallpaperTargetMonitor::Name`] picks `monitor`, by its
+    /// device name, its friendly name or the name Bevy reports.
+    fn matches(&self, target: &WallpaperTargetMonitor, monitor: &Monitor) -> bool {
+        self.names(monitor)
+            .is_some_and(|(device, friendly)| target.matches_name(Some(device), Some(friendly)))
+            || target.matches_name(monitor.name.as_deref(), None)
+    }
+}
+
+unsafe extern "system" fn enum_monitors_proc(
+    monitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let handles = unsafe { &mut *(lparam.0 as *mut Vec<HMONITOR>) };
+    handles.push(monitor);
+    BOOL(1)
+}
+
+/// Device name and friendly name (from `EnumDisplayDevicesW`) of `monitor`.
+fn monitor_names(monitor: HMONITOR) -> Option<(String, String)> {
+    unsafe {
+        let mut info = MONITORINFOEXW {
+            monitorInfo: MONITORINFO {
+                cbSize: size_of::<MONITORINFOEXW>() as u32,
+                ..default()
+            },
+            ..default()
+        };
+        GetMonitorInfoW(monitor, &mut info.monitorInfo).ok().ok()?;
+        let device = from_wide_null(&info.szDevice);
+
+        let mut display_device = DISPLAY_DEVICEW {
+            cb: size_of::<DISPLAY_DEVICEW>() as u32,
+            ..default()
+        };
+        // Index 0 of the adapter's devices is the monitor attached to it.
+        let found = EnumDisplayDevicesW(PCWSTR(info.szDevice.as_ptr()), 0, &mut display_device, 0);
+        let friendly = if found.as_bool() {
+            from_wide_null(&display_device.DeviceString)
+        } else {
+            String::new()
+        };
+        Some((device, friendly))
+    }
+}
+
+fn from_wide_null(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
+
+/// Reports Bevy's monitor entities coming and going.
+fn write_monitor_messages_system(
+    monitors: Query<(Entity, &Monitor)>,
+    monitor_info: NonSend<WindowsMonitorInfo>,
+    mut messages: MonitorMessages,
+) {
+    let current = monitors
+        .iter()
+        .map(|(entity, monitor)| WallpaperMonitorConnected {
+            output: entity.index_u32(),
+            name: monitor_info
+                .names(monitor)
+                .map(|(device, _)| device.to_owned())
+                .or_else(|| monitor.name.clone()),
+            size: UVec2::new(monitor.physical_width, monitor.physical_height),
+            position: monitor.physical_position,
+        })
+        .collect();
+    messages.update(current);
+}
+
 fn update_window_position_and_size_system(
     target_monitor: Res<WallpaperTargetMonitor>,
     monitors: Query<&Monitor>,
     primary_monitor: Single<&Monitor, With<PrimaryMonitor>>,
+    mut monitor_info: NonSendMut<WindowsMonitorInfo>,
     mut window: Single<&mut Window>,
 ) {
+    *monitor_info = WindowsMonitorInfo::enumerate();
+
     let Some((offset_x, offset_y)) = monitors
         .into_iter()
         .map(|m| (-m.physical_position.x, -m.physical_position.y))
@@ -80,14 +389,9 @@ fn update_window_position_and_size_system(
     else {
         return;
     };
-    let Some(scale) = monitors
-        .into_iter()
-        .map(|m| m.scale_factor as f32)
-        .reduce(f32::max)
-    else {
-        return;
-    };
 
+    // Sized in physical pixels: with monitors at different DPIs there is no
+    // single scale factor the whole window could be expressed in.
     let (pos_x, pos_y, width, height) = if let WallpaperTargetMonitor::All = *target_monitor {
         let Some((max_x, max_y)) = monitors
             .into_iter()
@@ -101,16 +405,14 @@ fn update_window_position_and_size_system(
         else {
             return;
         };
-        (
-            0,
-            0,
-            (max_x + offset_x) as f32 / scale,
-            (max_y + offset_y) as f32 / scale,
-        )
+        (0, 0, (max_x + offset_x) as u32, (max_y + offset_y) as u32)
     } else {
-        let Some(m) = (match *target_monitor {
+        let Some(m) = (match &*target_monitor {
             WallpaperTargetMonitor::Primary => Some(*primary_monitor),
-            WallpaperTargetMonitor::Index(n) => monitors.iter().nth(n),
+            WallpaperTargetMonitor::Index(n) => monitors.iter().nth(*n),
+            WallpaperTargetMonitor::Name(_) => monitors
+                .iter()
+                .find(|m| monitor_info.matches(&target_monitor, m)),
             WallpaperTargetMonitor::All => None,
         }) else {
             return;
@@ -120,109 +422,193 @@ fn update_window_position_and_size_system(
         (
             pos.x + offset_x,
             pos.y + offset_y,
-            m.physical_width as f32 / scale,
-            m.physical_height as f32 / scale,
+            m.physical_width,
+            m.physical_height,
         )
     };
 
     window.position.set(ivec2(pos_x, pos_y));
-    window.resolution.set(width, height);
+    window.resolution.set_physical_resolution(width, height);
+}
+
+/// A monitor's physical rectangle and its own DPI scale.
+///
+/// Logical coordinates are per monitor: a monitor keeps its physical origin
+/// (relative to the top-left of the virtual desktop) and distances within it
+/// are divided by its scale. Monitors at different DPIs therefore never
+/// overlap, at the cost of gaps between them in logical space.
+struct MonitorLayout {
+    position: IVec2,
+    size: IVec2,
+    /// `dpi / 96`.
+    scale: f32,
+}
+
+impl MonitorLayout {
+    fn new(monitor: &Monitor) -> Self {
+        let position = monitor.physical_position;
+        let size = ivec2(
+            monitor.physical_width as i32,
+            monitor.physical_height as i32,
+        );
+        let scale = monitor_dpi(position + size / 2)
+            .map(|dpi| dpi as f32 / USER_DEFAULT_SCREEN_DPI)
+            .unwrap_or(monitor.scale_factor as f32);
+        Self {
+            position,
+            size,
+            scale: if scale > 0.0 { scale } else { 1.0 },
+        }
+    }
+
+    fn contains(&self, point: IVec2) -> bool {
+        point.cmpge(self.position).all() && point.cmplt(self.position + self.size).all()
+    }
+
+    /// Logical position of the monitor's top-left corner.
+    fn logical_origin(&self, desktop_origin: IVec2) -> Vec2 {
+        (self.position - desktop_origin).as_vec2()
+    }
+
+    fn logical_size(&self) -> Vec2 {
+        self.size.as_vec2() / self.scale
+    }
+
+    /// Converts a physical point on this monitor to logical coordinates.
+    fn to_logical(&self, point: IVec2, desktop_origin: IVec2) -> Vec2 {
+        self.logical_origin(desktop_origin) + (point - self.position).as_vec2() / self.scale
+    }
+}
+
+/// DPI Windows reports as 100% scaling.
+const USER_DEFAULT_SCREEN_DPI: f32 = 96.0;
+
+/// Monitor containing `point` (physical coordinates).
+fn monitor_at(point: IVec2) -> Option<HMONITOR> {
+    let monitor = unsafe {
+        MonitorFromPoint(
+            POINT {
+                x: point.x,
+                y: point.y,
+            },
+            MONITOR_DEFAULTTONULL,
+        )
+    };
+    (!monitor.is_invalid()).then_some(monitor)
+}
+
+/// Effective DPI of the monitor containing `point` (physical coordinates).
+fn monitor_dpi(point: IVec2) -> Option<u32> {
+    let monitor = monitor_at(point)?;
+    unsafe {
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y).ok()?;
+        (dpi_x > 0).then_some(dpi_x)
+    }
+}
+
+/// Opts the process into per-monitor (v2) DPI awareness so physical monitor
+/// and cursor coordinates aren't virtualized on scaled displays.
+fn enable_per_monitor_dpi_awareness() {
+    // Fails if the awareness was already set (e.g. by winit or the manifest).
+    if let Err(err) =
+        unsafe { SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2) }
+    {
+        debug!("SetProcessDpiAwarenessContext failed: {err}");
+    }
+}
+
+#[derive(SystemParam)]
+struct WindowsPointerParams<'w> {
+    capture: Res<'w, CaptureInput>,
+    state: ResMut<'w, WallpaperPointerState>,
+    events: MessageWriter<'w, WallpaperPointerEvent>,
 }
 
 fn update_pointer_and_surface_info_system(
     target_monitor: Res<WallpaperTargetMonitor>,
     monitors_query: Query<&Monitor>,
     primary_monitor: Single<&Monitor, With<PrimaryMonitor>>,
-    mut pointer_state: ResMut<WallpaperPointerState>,
+    mut pointer: WindowsPointerParams,
     mut surface_info: ResMut<WallpaperSurfaceInfo>,
+    monitor_info: NonSend<WindowsMonitorInfo>,
 ) {
     let monitors: Vec<&Monitor> = monitors_query.iter().collect();
-    if monitors.is_empty() {
-        return;
-    }
-
-    let Some(min_x) = monitors.iter().map(|m| m.physical_position.x).min() else {
-        return;
-    };
-    let Some(min_y) = monitors.iter().map(|m| m.physical_position.y).min() else {
-        return;
-    };
-    let Some(max_x) = monitors
-        .iter()
-        .map(|m| m.physical_position.x + m.physical_width as i32)
-        .max()
-    else {
-        return;
-    };
-    let Some(max_y) = monitors
-        .iter()
-        .map(|m| m.physical_position.y + m.physical_height as i32)
-        .max()
-    else {
-        return;
-    };
-    let Some(max_scale) = monitors
-        .iter()
-        .map(|m| m.scale_factor as f32)
-        .reduce(f32::max)
-    else {
+    let layouts: Vec<MonitorLayout> = monitors.iter().map(|m| MonitorLayout::new(m)).collect();
+    let Some(desktop_origin) = layouts.iter().map(|l| l.position).reduce(IVec2::min) else {
         return;
     };
-    if max_scale <= 0.0 {
-        return;
-    }
 
-    let target_monitor_ref = match *target_monitor {
-        WallpaperTargetMonitor::Primary => Some(*primary_monitor),
-        WallpaperTargetMonitor::Index(n) => monitors.get(n).copied(),
+    let target_layout = match &*target_monitor {
+        WallpaperTargetMonitor::Primary => Some(MonitorLayout::new(*primary_monitor)),
+        WallpaperTargetMonitor::Index(n) => monitors.get(*n).map(|m| MonitorLayout::new(m)),
+        WallpaperTargetMonitor::Name(_) => monitors
+            .iter()
+            .find(|m| monitor_info.matches(&target_monitor, m))
+            .map(|m| MonitorLayout::new(m)),
         WallpaperTargetMonitor::All => None,
     };
 
-    let (origin_x, origin_y, logical_width, logical_height) = if let Some(m) = target_monitor_ref {
-        let width = ((m.physical_width as f32) / max_scale).ceil().max(1.0) as u32;
-        let height = ((m.physical_height as f32) / max_scale).ceil().max(1.0) as u32;
-        (m.physical_position.x, m.physical_position.y, width, height)
+    let (logical_offset, logical_size) = if let Some(layout) = &target_layout {
+        (layout.logical_origin(desktop_origin), layout.logical_size())
     } else {
-        let width = ((max_x - min_x) as f32 / max_scale).ceil().max(1.0) as u32;
-        let height = ((max_y - min_y) as f32 / max_scale).ceil().max(1.0) as u32;
-        (min_x, min_y, width, height)
+        let Some(extent) = layouts
+            .iter()
+            .map(|l| l.logical_origin(desktop_origin) + l.logical_size())
+            .reduce(Vec2::max)
+        else {
+            return;
+        };
+        (Vec2::ZERO, extent)
     };
 
-    let logical_offset_x = ((origin_x - min_x) as f32 / max_scale).floor() as i32;
-    let logical_offset_y = ((origin_y - min_y) as f32 / max_scale).floor() as i32;
     surface_info.set(
-        logical_offset_x,
-        logical_offset_y,
-        logical_width,
-        logical_height,
+        logical_offset.x.floor() as i32,
+        logical_offset.y.floor() as i32,
+        logical_size.x.ceil().max(1.0) as u32,
+        logical_size.y.ceil().max(1.0) as u32,
     );
 
+    if !pointer.capture.0 {
+        return;
+    }
     let Some((cursor_x, cursor_y)) = current_cursor_position() else {
         return;
     };
+    let cursor = ivec2(cursor_x, cursor_y);
 
-    let logical_position = Vec2::new(
-        (cursor_x - min_x) as f32 / max_scale,
-        (cursor_y - min_y) as f32 / max_scale,
-    );
+    let output = output_for_position(&layouts, cursor);
+    let logical_position = match output {
+        Some(idx) => layouts[idx as usize].to_logical(cursor, desktop_origin),
+        // Between monitors; keep physical distances.
+        None => (cursor - desktop_origin).as_vec2(),
+    };
 
     let pressed = pressed_buttons();
-    let last_button = detect_last_button(pointer_state.last.as_ref().map(|s| &s.pressed), &pressed);
-    let prev_position = pointer_state
+    let last_button = detect_last_button(pointer.state.last.as_ref().map(|s| &s.pressed), &pressed);
+    let prev_position = pointer
+        .state
         .last
         .as_ref()
         .map(|s| s.position)
         .unwrap_or(logical_position);
 
-    let output = output_for_position(&monitors, cursor_x, cursor_y);
-
-    pointer_state.last = Some(PointerSample {
+    let mut sample = PointerSample {
+        seat: None,
         output,
         position: logical_position,
         delta: logical_position - prev_position,
+        entered_output: false,
         last_button,
         pressed,
-    });
+        scroll_discrete: Vec2::ZERO,
+        scroll_smooth: Vec2::ZERO,
+    };
+    sample.reset_on_output_change(pointer.state.last.as_ref());
+    pointer
+        .events
+        .write_batch(pointer_events_between(pointer.state.last.as_ref(), &sample));
+    pointer.state.last = Some(sample);
 }
 
 fn current_cursor_position() -> Option<(i32, i32)> {
@@ -236,17 +622,12 @@ fn current_cursor_position() -> Option<(i32, i32)> {
     }
 }
 
-fn output_for_position(monitors: &[&Monitor], x: i32, y: i32) -> Option<u32> {
-    monitors
+/// Index of the monitor containing the physical point `position`.
+fn output_for_position(layouts: &[MonitorLayout], position: IVec2) -> Option<u32> {
+    layouts
         .iter()
-        .enumerate()
-        .find(|(_, monitor)| {
-            let pos = monitor.physical_position;
-            let width = monitor.physical_width as i32;
-            let height = monitor.physical_height as i32;
-            x >= pos.x && x < pos.x + width && y >= pos.y && y < pos.y + height
-        })
-        .map(|(idx, _)| idx as u32)
+        .position(|layout| layout.contains(position))
+        .map(|idx| idx as u32)
 }
 
 fn pressed_buttons() -> HashSet<MouseButton> {
@@ -261,46 +642,14 @@ fn pressed_buttons() -> HashSet<MouseButton> {
         if GetAsyncKeyState(VK_MBUTTON.0 as i32) < 0 {
             set.insert(MouseButton::Middle);
         }
-    }
-    set
-}
-
-fn detect_last_button(
-    prev: Option<&HashSet<MouseButton>>,
-    current: &HashSet<MouseButton>,
-) -> Option<PointerButton> {
-    let empty = HashSet::new();
-    let prev = prev.unwrap_or(&empty);
-
-    let mut newly_pressed: Vec<MouseButton> = current.difference(prev).copied().collect();
-    if let Some(btn) = prioritize_button(&mut newly_pressed) {
-        return Some(PointerButton {
-            button: Some(btn),
-            pressed: true,
-        });
-    }
-
-    let mut released: Vec<MouseButton> = prev.difference(current).copied().collect();
-    if let Some(btn) = prioritize_button(&mut released) {
-        return Some(PointerButton {
-            button: Some(btn),
-            pressed: false,
-        });
-    }
-
-    None
-}
-
-fn prioritize_button(buttons: &mut Vec<MouseButton>) -> Option<MouseButton> {
-    let priority = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
-
-    for p in priority {
-        if let Some(pos) = buttons.iter().position(|b| *b == p) {
-            return Some(buttons.swap_remove(pos));
+        if GetAsyncKeyState(VK_XBUTTON1.0 as i32) < 0 {
+            set.insert(MouseButton::Back);
+        }
+        if GetAsyncKeyState(VK_XBUTTON2.0 as i32) < 0 {
+            set.insert(MouseButton::Forward);
         }
     }
-
-    buttons.pop()
+    set
 }
 
 fn to_wide_null(s: &str) -> Vec<u16> {
@@ -321,11 +670,90 @@ fn find_workerw() -> Option<HWND> {
         );
     }
 
-    find_workerw_for_progman(progman).or_else(find_workerw_from_desktop)
+    // Newer Explorer builds put WorkerW under Progman, older ones next to the
+    // top-level window hosting SHELLDLL_DefView. Some shells expose several.
+    let mut candidates = find_workerw_for_progman(progman);
+    for candidate in find_workerw_from_desktop() {
+        if !candidates.iter().any(|c| c.worker == candidate.worker) {
+            candidates.push(candidate);
+        }
+    }
+
+    let preferred = virtual_desktop_manager().and_then(|manager| {
+        candidates.iter().find(|candidate| unsafe {
+            manager
+                .IsWindowOnCurrentVirtualDesktop(candidate.shell_host)
+                .is_ok_and(|on_current| on_current.as_bool())
+        })
+    });
+    preferred.or(candidates.first()).map(|c| c.worker)
 }
 
-fn find_workerw_for_progman(progman: HWND) -> Option<HWND> {
-    let mut state = WorkerFinder::default();
+/// A WorkerW window and the top-level window holding the desktop icons.
+#[derive(Clone, Copy)]
+struct WorkerCandidate {
+    worker: HWND,
+    shell_host: HWND,
+}
+
+fn virtual_desktop_manager() -> Option<IVirtualDesktopManager> {
+    if !COM_APARTMENT.with(|com| com.usable) {
+        return None;
+    }
+    unsafe { CoCreateInstance(&VirtualDesktopManager, None, CLSCTX_ALL).ok() }
+}
+
+thread_local! {
+    static COM_APARTMENT: ComApartment = ComApartment::init();
+}
+
+/// COM initialization of the current thread, done once on first use and
+/// undone when the thread exits.
+struct ComApartment {
+    usable: bool,
+    /// Whether this initialization needs a matching `CoUninitialize`.
+    owned: bool,
+}
+
+impl ComApartment {
+    fn init() -> Self {
+        let result = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) };
+        if result == RPC_E_CHANGED_MODE {
+            // Already initialized as multithreaded, which works just as well;
+            // that initialization isn't ours to undo.
+            Self {
+                usable: true,
+                owned: false,
+            }
+        } else if result.is_ok() {
+            // S_FALSE (already initialized, e.g. by winit) also needs undoing.
+            Self {
+                usable: true,
+                owned: true,
+            }
+        } else {
+            warn!("Failed to initialize COM: {result}");
+            Self {
+                usable: false,
+                owned: false,
+            }
+        }
+    }
+}
+
+impl Drop for ComApartment {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
+
+fn find_workerw_for_progman(progman: HWND) -> Vec<WorkerCandidate> {
+    let mut state = WorkerFinder {
+        shell_host: Some(progman),
+        ..default()
+    };
     unsafe {
         _ = EnumChildWindows(
             Some(progman),
@@ -333,10 +761,10 @@ fn find_workerw_for_progman(progman: HWND) -> Option<HWND> {
             LPARAM(&mut state as *mut _ as isize),
         );
     }
-    state.worker
+    state.candidates
 }
 
-fn find_workerw_from_desktop() -> Option<HWND> {
+fn find_workerw_from_desktop() -> Vec<WorkerCandidate> {
     let mut state = WorkerFinder::default();
     unsafe {
         _ = EnumWindows(
@@ -344,31 +772,30 @@ fn find_workerw_from_desktop() -> Option<HWND> {
             LPARAM(&mut state as *mut _ as isize),
         );
     }
-    state.worker
+    state.candidates
 }
 
 #[derive(Default)]
 struct WorkerFinder {
-    worker: Option<HWND>,
+    shell_host: Option<HWND>,
+    candidates: Vec<WorkerCandidate>,
 }
 
 unsafe extern "system" fn enum_child_worker_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
     let state = unsafe { &mut *(lparam.0 as *mut WorkerFinder) };
-    if state.worker.is_some() {
-        return BOOL(0);
-    }
-    if is_class(hwnd, "WorkerW") {
-        state.worker = Some(hwnd);
-        return BOOL(0);
+    if is_class(hwnd, "WorkerW")
+        && let Some(shell_host) = state.shell_host
+    {
+        state.candidates.push(WorkerCandidate {
+            worker: hwnd,
+            shell_host,
+        });
     }
     BOOL(1)
 }
 
 unsafe extern "system" fn enum_windows_worker_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
     let state = unsafe { &mut *(lparam.0 as *mut WorkerFinder) };
-    if state.worker.is_some() {
-        return BOOL(0);
-    }
 
     let shell = unsafe {
         FindWindowExW(
@@ -391,8 +818,10 @@ unsafe extern "system" fn enum_windows_worker_proc(hwnd: HWND, lparam: LPARAM) -
         )
     };
     if let Ok(worker) = worker {
-        state.worker = Some(worker);
-        return BOOL(0);
+        state.candidates.push(WorkerCandidate {
+            worker,
+            shell_host: hwnd,
+        });
     }
 
     BOOL(1)
@@ -452,3 +881,204 @@ unsafe extern "system" fn enum_duplicate_cleanup_proc(hwnd: HWND, lparam: LPARAM
     }
     BOOL(1)
 }
+
+/// Runs `f` with the clipboard open; it can only be open in one place at a time.
+fn with_clipboard<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let owner = clipboard_owner()?;
+    unsafe { OpenClipboard(Some(owner)) }
+        .map_err(|err| format!("Failed to open the clipboard: {err}"))?;
+    let result = f();
+    _ = unsafe { CloseClipboard() };
+    result
+}
+
+/// Message-only window the clipboard is opened with, created once on its own
+/// thread. `SetClipboardData` fails unless the clipboard has an owner window.
+fn clipboard_owner() -> Result<HWND, String> {
+    // Kept as an integer so it can be shared between threads.
+    static OWNER: OnceLock<Result<isize, String>> = OnceLock::new();
+    let owner = OWNER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("wallpaper-clipboard".into())
+            .spawn(move || run_clipboard_window(&sender))
+            .map_err(|err| format!("Failed to spawn the clipboard thread: {err}"))?;
+        receiver
+            .recv()
+            .map_err(|_| "The clipboard thread exited early".to_owned())?
+    });
+    owner
+        .clone()
+        .map(|hwnd| HWND(hwnd as *mut std::ffi::c_void))
+}
+
+fn run_clipboard_window(sender: &mpsc::Sender<Result<isize, String>>) {
+    let created = create_message_window(w!("BevyLiveWallpaperClipboard"), clipboard_window_proc);
+    let hwnd = match created {
+        Ok(hwnd) => hwnd,
+        Err(err) => {
+            let _ = sender.send(Err(format!("Failed to create the clipboard window: {err}")));
+            return;
+        }
+    };
+    let _ = sender.send(Ok(hwnd.0 as isize));
+    // Clipboard notifications are sent to the owner, so keep it responsive.
+    pump_messages();
+}
+
+unsafe extern "system" fn clipboard_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+}
+
+/// Copies the bytes of a clipboard handle; the clipboard must be open.
+unsafe fn clipboard_bytes(format: CLIPBOARD_FORMAT) -> Result<Vec<u8>, String> {
+    unsafe {
+        let handle = GetClipboardData(u32::from(format.0))
+            .map_err(|err| format!("No {format:?} data on the clipboard: {err}"))?;
+        let memory = HGLOBAL(handle.0);
+        let ptr = GlobalLock(memory).cast::<u8>();
+        if ptr.is_null() {
+            return Err("Failed to lock the clipboard data".into());
+        }
+        let bytes = std::slice::from_raw_parts(ptr, GlobalSize(memory)).to_vec();
+        _ = GlobalUnlock(memory);
+        Ok(bytes)
+    }
+}
+
+pub(crate) fn clipboard_text() -> Option<String> {
+    let bytes = with_clipboard(|| unsafe { clipboard_bytes(CF_UNICODETEXT) }).ok()?;
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&wide))
+}
+
+pub(crate) fn set_clipboard_text(text: &str) -> Result<(), String> {
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    with_clipboard(|| unsafe {
+        EmptyClipboard().map_err(|err| format!("Failed to empty the clipboard: {err}"))?;
+        let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * size_of::<u16>())
+            .map_err(|err| format!("Failed to allocate clipboard memory: {err}"))?;
+        let ptr = GlobalLock(memory).cast::<u16>();
+        if ptr.is_null() {
+            _ = GlobalFree(Some(memory));
+            return Err("Failed to lock clipboard memory".into());
+        }
+        std::ptr::copy_nonoverlapping(wide.as_ptr(), ptr, wide.len());
+        _ = GlobalUnlock(memory);
+        // The clipboard owns the memory once this succeeds.
+        SetClipboardData(u32::from(CF_UNICODETEXT.0), Some(HANDLE(memory.0))).map_err(|err| {
+            _ = GlobalFree(Some(memory));
+            format!("Failed to set the clipboard text: {err}")
+        })?;
+        Ok(())
+    })
+}
+
+pub(crate) fn clipboard_image() -> Option<Image> {
+    let bytes = with_clipboard(|| unsafe { clipboard_bytes(CF_DIB) }).ok()?;
+    crate::clipboard::decode_dib(&bytes)
+}
+
+thread_local! {
+    static SESSION_LOCK_SENDER: RefCell<Option<mpsc::Sender<LockSignal>>> =
+        const { RefCell::new(None) };
+}
+
+/// Forwards session lock/unlock notifications from a message-only window on
+/// its own thread.
+pub(crate) fn spawn_session_lock_watcher(sender: mpsc::Sender<LockSignal>) {
+    let spawned = std::thread::Builder::new()
+        .name("wallpaper-session-lock".into())
+        .spawn(move || {
+            SESSION_LOCK_SENDER.with_borrow_mut(|slot| *slot = Some(sender));
+            if let Err(err) = run_session_lock_window() {
+                warn!("Not watching session lock notifications: {err}");
+            }
+        });
+    if let Err(err) = spawned {
+        warn!("Failed to spawn the session lock watcher thread: {err}");
+    }
+}
+
+fn run_session_lock_window() -> windows::core::Result<()> {
+    let hwnd = create_message_window(w!("BevyLiveWallpaperSessionLock"), session_lock_window_proc)?;
+    unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION)? };
+    pump_messages();
+    Ok(())
+}
+
+/// Creates a message-only window of a new class on the current thread.
+fn create_message_window(
+    class_name: PCWSTR,
+    window_proc: unsafe extern "system" fn(HWND, u32, WPARAM, LPARAM) -> LRESULT,
+) -> windows::core::Result<HWND> {
+    unsafe {
+        let instance = GetModuleHandleW(None)?;
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: instance.into(),
+            lpszClassName: class_name,
+            ..Default::default()
+        };
+        if RegisterClassW(&class) == 0 {
+            return Err(windows::core::Error::from_thread());
+        }
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class_name,
+            PCWSTR::null(),
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            Some(HWND_MESSAGE),
+            None,
+            Some(instance.into()),
+            None,
+        )
+    }
+}
+
+/// Dispatches the current thread's messages until `WM_QUIT`.
+fn pump_messages() {
+    let mut msg = MSG::default();
+    unsafe {
+        while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+unsafe extern "system" fn session_lock_window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if msg == WM_WTSSESSION_CHANGE {
+        let locked = match wparam.0 as u32 {
+            WTS_SESSION_LOCK => Some(true),
+            WTS_SESSION_UNLOCK => Some(false),
+            _ => None,
+        };
+        if let Some(locked) = locked {
+            SESSION_LOCK_SENDER.with_borrow(|sender| {
+                if let Some(sender) = sender {
+                    let _ = sender.send(("WTSSessionChange", locked));
+                }
+            });
+        }
+        return LRESULT(0);
+    }
+    unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+}
@@ -1,5 +1,6 @@
 use bevy::{
     camera::RenderTarget,
+    ecs::system::SystemParam,
     prelude::*,
     render::{
         Render, RenderApp, RenderSystems, extract_resource::ExtractResourcePlugin,
@@ -8,14 +9,26 @@ use bevy::{
 };
 
 use crate::{
-    LiveWallpaperCamera, WallpaperPointerState, WallpaperSurfaceInfo, WallpaperTargetMonitor,
+    LiveWallpaperCamera, PlayState, WallpaperAlphaMode, WallpaperBackend, WallpaperBackendClosed,
+    WallpaperBackendStatus, WallpaperCursor, WallpaperDisplayMode, WallpaperFrameLatency,
+    WallpaperMemoryBudget, WallpaperPaused, WallpaperPlayState, WallpaperPointerEvent,
+    WallpaperPointerState, WallpaperPreferredFormats, WallpaperPresentMode, WallpaperScrollEvent,
+    WallpaperSurface, WallpaperSurfaceFormat, WallpaperSurfaceHandles, WallpaperSurfaceInfo,
+    WallpaperSurfaces, WallpaperSystemSet, WallpaperTargetMonitor, WallpaperX11Config,
+    WallpaperX11Info,
+    input::{CaptureInput, pointer_events_between},
+    monitor_events::MonitorMessages,
+    plugin::wallpaper_mode_active,
+    surface_format::NegotiatedSurfaceFormat,
+    surface_recovery::{SharedSurfaceRecovery, sync_surface_recovery},
+    surfaces::monitor_dpi,
 };
 
 use super::{
-    X11AppState,
+    X11AppState, X11Display, X11DpmsConfig, X11WindowIdentity,
     render::{
         X11GpuSurfaceState, X11RenderTarget, X11SurfaceDescriptor, create_x11_image,
-        prepare_x11_surface, present_x11_surface,
+        prepare_x11_surface, present_x11_surface, publish_x11_surface_recovery,
     },
 };
 
@@ -24,31 +37,99 @@ pub(crate) struct X11BackendPlugin;
 
 impl Plugin for X11BackendPlugin {
     fn build(&self, app: &mut App) {
+        app.insert_resource(WallpaperBackend::X11);
         let target_monitor = app
             .world()
             .get_resource::<WallpaperTargetMonitor>()
+            .cloned()
+            .unwrap_or_default();
+
+        let transparent = app
+            .world()
+            .get_resource::<WallpaperAlphaMode>()
+            .is_some_and(|mode| *mode == WallpaperAlphaMode::Transparent);
+
+        let identity = app
+            .world()
+            .get_resource::<X11WindowIdentity>()
+            .cloned()
+            .unwrap_or_default();
+        let display = app
+            .world()
+            .get_resource::<X11Display>()
+            .and_then(|display| display.0.clone());
+        let dpms = app
+            .world()
+            .get_resource::<X11DpmsConfig>()
             .copied()
             .unwrap_or_default();
+        let mut app_state = match X11AppState::connect(
+            display.as_deref(),
+            target_monitor.clone(),
+            identity,
+            transparent,
+        ) {
+            Ok(connected) => connected,
+            Err(err) => {
+                error!("Failed to initialize X11 wallpaper backend: {err}");
+                app.insert_resource(WallpaperBackendStatus::Failed(err));
+                return;
+            }
+        };
 
-        let (app_state, initial_config) = X11AppState::connect(target_monitor)
-            .expect("failed to initialize X11 wallpaper backend");
+        if transparent && !app_state.argb_visual() {
+            // Keep the surfaces opaque to match the windows.
+            app.insert_resource(WallpaperAlphaMode::Opaque);
+        }
 
-        info!(
-            "Connected to X11 wallpaper window: {}x{} (target: {:?})",
-            initial_config.width, initial_config.height, target_monitor
-        );
+        if dpms.disable
+            && let Err(err) = app_state.disable_dpms()
+        {
+            warn!("Failed to disable DPMS: {err}");
+        }
+        if dpms.inhibit {
+            app.add_systems(
+                PostUpdate,
+                inhibit_x11_screen_saver.run_if(wallpaper_mode_active),
+            );
+        }
+        if dpms.disable {
+            app.add_systems(Last, restore_x11_dpms.run_if(on_message::<AppExit>));
+        }
+
+        app.insert_resource(WallpaperX11Info {
+            composite_available: app_state.composite_available(),
+        });
+
+        if let Some((_, _, width, height)) = app_state.current_bounds() {
+            info!(
+                "Connected to X11 wallpaper windows: {}x{} (target: {:?})",
+                width, height, target_monitor
+            );
+        }
 
         let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
         };
 
+        let negotiated_format = NegotiatedSurfaceFormat::default();
+        let surface_recovery = SharedSurfaceRecovery::default();
         render_app
+            .insert_resource(negotiated_format.clone())
+            .insert_resource(surface_recovery.clone())
             .init_resource::<X11GpuSurfaceState>()
             .add_systems(
                 Render,
                 prepare_x11_surface.in_set(RenderSystems::PrepareResources),
             )
-            .add_systems(Render, present_x11_surface.in_set(RenderSystems::Cleanup));
+            .add_systems(
+                Render,
+                (
+                    present_x11_surface,
+                    publish_x11_surface_recovery.after(present_x11_surface),
+                )
+                    .in_set(RenderSystems::Cleanup),
+            );
 
         let target_image = {
             let mut images = app.world_mut().resource_mut::<Assets<Image>>();
@@ -60,25 +141,96 @@ impl Plugin for X11BackendPlugin {
             .add_plugins((
                 ExtractResourcePlugin::<X11SurfaceDescriptor>::default(),
                 ExtractResourcePlugin::<X11RenderTarget>::default(),
+                ExtractResourcePlugin::<WallpaperAlphaMode>::default(),
+                ExtractResourcePlugin::<WallpaperPreferredFormats>::default(),
+                ExtractResourcePlugin::<WallpaperSurfaceFormat>::default(),
+                ExtractResourcePlugin::<WallpaperPresentMode>::default(),
+                ExtractResourcePlugin::<WallpaperFrameLatency>::default(),
             ))
-            .add_systems(PostUpdate, x11_event_system)
+            .insert_resource(negotiated_format)
+            .insert_resource(surface_recovery)
+            .add_systems(
+                PostUpdate,
+                (
+                    reconfigure_surfaces
+                        .before(WallpaperSystemSet::BackendEvents)
+                        .run_if(
+                            resource_changed::<WallpaperPreferredFormats>
+                                .or_else(resource_changed::<WallpaperSurfaceFormat>)
+                                .or_else(resource_changed::<WallpaperPresentMode>)
+                                .or_else(resource_changed::<WallpaperFrameLatency>),
+                        ),
+                    x11_event_system.in_set(WallpaperSystemSet::BackendEvents),
+                ),
+            )
             .add_systems(
                 PostUpdate,
                 (
-                    sync_x11_render_target_image.after(x11_event_system),
-                    assign_x11_camera_target.after(sync_x11_render_target_image),
+                    sync_x11_render_target_image.in_set(WallpaperSystemSet::SyncRenderTarget),
+                    assign_x11_camera_target
+                        .in_set(WallpaperSystemSet::AssignCamera)
+                        .run_if(wallpaper_mode_active),
+                    sync_wallpaper_surfaces
+                        .after(WallpaperSystemSet::BackendEvents)
+                        .run_if(resource_changed::<X11SurfaceDescriptor>),
+                    sync_x11_cursor
+                        .after(WallpaperSystemSet::BackendEvents)
+                        .run_if(resource_changed::<WallpaperCursor>),
+                    sync_x11_config
+                        .before(WallpaperSystemSet::BackendEvents)
+                        .run_if(resource_changed::<WallpaperX11Config>),
+                    write_monitor_messages.after(WallpaperSystemSet::BackendEvents),
+                    sync_surface_recovery,
                 ),
             )
             .insert_non_send(app_state);
     }
 }
 
+#[derive(SystemParam)]
+struct X11PointerParams<'w> {
+    capture: Res<'w, CaptureInput>,
+    state: ResMut<'w, WallpaperPointerState>,
+    events: MessageWriter<'w, WallpaperPointerEvent>,
+    scroll_events: MessageWriter<'w, WallpaperScrollEvent>,
+}
+
+#[derive(SystemParam)]
+struct X11SurfaceInfoParams<'w> {
+    info: ResMut<'w, WallpaperSurfaceInfo>,
+    memory_budget: Res<'w, WallpaperMemoryBudget>,
+}
+
+#[derive(SystemParam)]
+struct X11LifecycleParams<'w> {
+    status: ResMut<'w, WallpaperBackendStatus>,
+    closed_events: MessageWriter<'w, WallpaperBackendClosed>,
+}
+
+/// Applies [`WallpaperCursor`] to the wallpaper windows.
+fn sync_x11_cursor(mut app_state: NonSendMut<X11AppState>, cursor: Res<WallpaperCursor>) {
+    if let Err(err) = app_state.set_cursor(*cursor) {
+        warn!("Failed to set the wallpaper cursor: {err}");
+    }
+}
+
+/// Reports monitors coming and going after RandR refreshes.
+fn write_monitor_messages(app_state: NonSend<X11AppState>, mut messages: MonitorMessages) {
+    messages.update(app_state.connected_monitors());
+}
+
+fn sync_x11_config(mut app_state: NonSendMut<X11AppState>, config: Res<WallpaperX11Config>) {
+    app_state.set_randr_debounce(config.randr_debounce);
+}
+
 fn x11_event_system(
     mut app_state: NonSendMut<X11AppState>,
     mut surface_descriptor: ResMut<X11SurfaceDescriptor>,
     target_monitor: Res<WallpaperTargetMonitor>,
-    mut pointer_state: ResMut<WallpaperPointerState>,
-    mut surface_info: ResMut<WallpaperSurfaceInfo>,
+    display_mode: Res<WallpaperDisplayMode>,
+    mut pointer: X11PointerParams,
+    mut surface_info: X11SurfaceInfoParams,
+    mut lifecycle: X11LifecycleParams,
 ) {
     if !app_state.is_running() {
         return;
@@ -86,49 +238,164 @@ fn x11_event_system(
 
     app_state.poll_events();
 
+    if !app_state.is_running() {
+        warn!("X11 connection lost; closing wallpaper backend");
+        surface_descriptor.surfaces.clear();
+        surface_descriptor.bump_generation();
+        *lifecycle.status = WallpaperBackendStatus::Closed;
+        lifecycle.closed_events.write(WallpaperBackendClosed);
+        return;
+    }
+
+    if display_mode.is_changed() {
+        let visible = *display_mode == WallpaperDisplayMode::Wallpaper;
+        if let Err(err) = app_state.set_visible(visible) {
+            warn!("Failed to update wallpaper window visibility: {err}");
+        }
+        if visible {
+            if let Err(err) = app_state.apply_target(&target_monitor) {
+                warn!("Failed to apply target monitor: {err}");
+            }
+        } else {
+            // Let the render world drop its wgpu surfaces while windowed.
+            surface_descriptor.surfaces.clear();
+            surface_descriptor.bump_generation();
+        }
+    }
+
+    if *display_mode == WallpaperDisplayMode::Windowed {
+        // The windowed backend owns pointer and surface info in this mode.
+        return;
+    }
+
     if target_monitor.is_changed()
-        && let Err(err) = app_state.apply_target(*target_monitor)
+        && !display_mode.is_changed()
+        && let Err(err) = app_state.apply_target(&target_monitor)
     {
         warn!("Failed to apply target monitor change: {err}");
     }
 
     if let Some((x, y, w, h)) = app_state.current_bounds() {
-        surface_info.set(x, y, w, h);
+        surface_info.info.set(x, y, w, h);
+        // X11 has no output scaling; logical and physical pixels match.
+        let scale_factor = Some(surface_info.memory_budget.resolution_scale);
+        if surface_info.info.scale_factor != scale_factor {
+            surface_info.info.scale_factor = scale_factor;
+        }
     }
 
-    if let Some(sample) = app_state.poll_pointer(pointer_state.last.as_ref()) {
-        pointer_state.last = Some(sample);
+    if pointer.capture.0 {
+        for sample in app_state.poll_pointer(pointer.state.last.as_ref()) {
+            pointer
+                .events
+                .write_batch(pointer_events_between(pointer.state.last.as_ref(), &sample));
+            pointer
+                .scroll_events
+                .write_batch(WallpaperScrollEvent::from_sample(&sample));
+            pointer.state.last = Some(sample);
+        }
     }
 
-    if let Some(surface_config) = app_state.take_surface_config() {
-        info!(
-            "X11 surface configured: {}x{}",
-            surface_config.width, surface_config.height
-        );
-        surface_descriptor.handles = Some(surface_config.handles);
-        surface_descriptor.width = surface_config.width;
-        surface_descriptor.height = surface_config.height;
+    if let Some(surface_configs) = app_state.take_surface_config() {
+        for config in &surface_configs {
+            info!(
+                "X11 surface configured (window {}): {}x{}",
+                config.window, config.width, config.height
+            );
+        }
+        surface_descriptor.surfaces = surface_configs.into_iter().map(Into::into).collect();
         surface_descriptor.bump_generation();
     }
 }
 
+/// Keeps the screen awake while the wallpaper is playing.
+fn inhibit_x11_screen_saver(
+    mut app_state: NonSendMut<X11AppState>,
+    play_state: Res<WallpaperPlayState>,
+    paused: Option<Res<WallpaperPaused>>,
+) {
+    if play_state.state != PlayState::Paused && paused.is_none() {
+        app_state.reset_screen_saver();
+    }
+}
+
+fn restore_x11_dpms(mut app_state: NonSendMut<X11AppState>) {
+    app_state.restore_dpms();
+}
+
+/// Forgets the negotiated format and recreates the surfaces so changed
+/// surface preferences (formats, present mode) take effect.
+fn reconfigure_surfaces(
+    negotiated: Res<NegotiatedSurfaceFormat>,
+    mut descriptor: ResMut<X11SurfaceDescriptor>,
+) {
+    negotiated.set(None);
+    descriptor.bump_generation();
+}
+
+/// Mirrors the wallpaper windows into the public [`WallpaperSurfaces`] resource.
+fn sync_wallpaper_surfaces(
+    descriptor: Res<X11SurfaceDescriptor>,
+    mut surfaces: ResMut<WallpaperSurfaces>,
+) {
+    let live = descriptor
+        .surfaces
+        .iter()
+        .map(|entry| {
+            let surface = WallpaperSurface {
+                handles: WallpaperSurfaceHandles::X11(entry.handles),
+                size: UVec2::new(entry.width, entry.height),
+                scale: 1.0,
+                refresh_hz: entry.refresh_hz,
+                physical_size_mm: entry.physical_size_mm,
+                // X11 windows are sized in physical pixels.
+                dpi: monitor_dpi(
+                    UVec2::new(entry.width, entry.height),
+                    entry.physical_size_mm,
+                ),
+                primary: entry.primary,
+                name: (!entry.name.is_empty()).then(|| entry.name.clone()),
+            };
+            (entry.window, surface)
+        })
+        .collect();
+    surfaces.set_if_neq(WallpaperSurfaces { surfaces: live });
+}
+
 fn sync_x11_render_target_image(
     descriptor: Res<X11SurfaceDescriptor>,
+    negotiated: Res<NegotiatedSurfaceFormat>,
+    mut surface_info: ResMut<WallpaperSurfaceInfo>,
     mut target: ResMut<X11RenderTarget>,
     mut images: ResMut<Assets<Image>>,
 ) {
-    if descriptor.width == 0 || descriptor.height == 0 {
-        return;
+    // Copies to the windows need the render target in the surface format.
+    if let Some(format) = negotiated.get() {
+        let outdated = images
+            .get(&target.image)
+            .is_some_and(|image| image.texture_descriptor.format != format);
+        if outdated && let Some(mut image) = images.get_mut(&target.image) {
+            info!("X11 surface format: {:?}", format);
+            image.texture_descriptor.format = format;
+            image.data = None;
+        }
+        if surface_info.format != format {
+            surface_info.format = format;
+        }
     }
 
+    let Some((_, _, width, height)) = descriptor.overall_bounds() else {
+        return;
+    };
+
     if target.last_applied_generation == descriptor.generation {
         return;
     }
 
     if let Some(mut image) = images.get_mut(&target.image) {
         let size = Extent3d {
-            width: descriptor.width,
-            height: descriptor.height,
+            width,
+            height,
             depth_or_array_layers: 1,
         };
 
@@ -0,0 +1,90 @@
+//! Keeps the X server from blanking the screen while the wallpaper plays.
+//!
+//! Resetting the screen saver also restarts the DPMS timeouts, which count
+//! from the same idle time. Turning DPMS off is server-wide and outlives the
+//! connection, so it is switched back on when the app exits.
+
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use x11rb::{
+    connection::{Connection, RequestConnection},
+    protocol::{
+        dpms::{self, ConnectionExt as DpmsConnectionExt},
+        xproto::{ConnectionExt, ScreenSaver},
+    },
+};
+
+use super::X11AppState;
+
+/// Interval between screen saver resets; well below the usual timeouts.
+const SCREEN_SAVER_RESET_INTERVAL: Duration = Duration::from_secs(30);
+
+/// From [`crate::LiveWallpaperPlugin::inhibit_dpms`] and
+/// [`crate::LiveWallpaperPlugin::disable_dpms`].
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub(crate) struct X11DpmsConfig {
+    pub inhibit: bool,
+    pub disable: bool,
+}
+
+impl X11AppState {
+    /// Turns DPMS off if the server has it on; [`Self::restore_dpms`] turns
+    /// it back on.
+    pub(crate) fn disable_dpms(&mut self) -> Result<(), String> {
+        if self
+            .connection
+            .extension_information(dpms::X11_EXTENSION_NAME)
+            .ok()
+            .flatten()
+            .is_none()
+        {
+            return Err("the DPMS extension is unavailable".into());
+        }
+        let info = self
+            .connection
+            .dpms_info()
+            .map_err(|err| format!("{err:?}"))?
+            .reply()
+            .map_err(|err| format!("{err:?}"))?;
+        if !info.state {
+            return Ok(());
+        }
+        self.connection
+            .dpms_disable()
+            .map_err(|err| format!("{err:?}"))?
+            .check()
+            .map_err(|err| format!("{err:?}"))?;
+        self.dpms_disabled = true;
+        debug!("DPMS disabled while the wallpaper runs");
+        Ok(())
+    }
+
+    /// Turns DPMS back on if [`Self::disable_dpms`] turned it off.
+    pub(crate) fn restore_dpms(&mut self) {
+        if !std::mem::take(&mut self.dpms_disabled) {
+            return;
+        }
+        match self.connection.dpms_enable().map(|cookie| cookie.check()) {
+            Ok(Ok(())) => debug!("DPMS re-enabled"),
+            Ok(Err(err)) => warn!("Failed to re-enable DPMS: {err:?}"),
+            Err(err) => warn!("Failed to re-enable DPMS: {err:?}"),
+        }
+    }
+
+    /// Resets the screen saver timer, at most once per
+    /// [`SCREEN_SAVER_RESET_INTERVAL`].
+    pub(crate) fn reset_screen_saver(&mut self) {
+        if self
+            .screen_saver_reset_at
+            .is_some_and(|at| at.elapsed() < SCREEN_SAVER_RESET_INTERVAL)
+        {
+            return;
+        }
+        self.screen_saver_reset_at = Some(Instant::now());
+        if let Err(err) = self.connection.force_screen_saver(ScreenSaver::RESET) {
+            warn!("Failed to reset the screen saver: {err:?}");
+        }
+        let _ = self.connection.flush();
+    }
+}
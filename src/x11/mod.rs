@@ -1,47 +1,125 @@
 pub mod backend;
+mod dpms;
 pub mod render;
 pub mod surface;
 
 use std::{
     collections::HashSet,
-    ffi::{c_int, c_void},
+    ffi::{CString, c_int, c_void},
     ptr::NonNull,
+    time::{Duration, Instant},
 };
 
 use as_raw_xcb_connection::AsRawXcbConnection;
-use bevy::prelude::*;
+use bevy::{prelude::*, window::SystemCursorIcon};
 use x11rb::COPY_DEPTH_FROM_PARENT;
 use x11rb::protocol::randr::{self, ConnectionExt as RandrConnectionExt, MonitorInfo};
+use x11rb::protocol::xinput::{self, ConnectionExt as XinputConnectionExt};
 use x11rb::{
-    connection::Connection,
+    connection::{Connection, RequestConnection},
     protocol::{
         Event,
-        xproto::{ChangeWindowAttributesAux, ConnectionExt, EventMask},
+        xproto::{
+            AtomEnum, ChangeWindowAttributesAux, ColormapAlloc, ConnectionExt, CreateGCAux,
+            EventMask, PropMode, Rectangle, Screen, VisualClass,
+        },
     },
+    wrapper::ConnectionExt as WrapperConnectionExt,
     xcb_ffi::XCBConnection,
 };
 
+pub(crate) use self::dpms::X11DpmsConfig;
 use self::surface::X11SurfaceHandles;
 
-use crate::{PointerButton, PointerSample, WallpaperTargetMonitor};
+use crate::WallpaperMonitorConnected;
+use crate::input::detect_last_button;
+use crate::{PointerButton, PointerSample, WallpaperCursor, WallpaperTargetMonitor};
+
+/// `WM_NAME` / `_NET_WM_NAME` and `WM_CLASS` of the wallpaper windows, from
+/// [`crate::LiveWallpaperPlugin::window_title`] and
+/// [`crate::LiveWallpaperPlugin::window_class`], and whether the window
+/// manager manages them ([`crate::LiveWallpaperPlugin::x11_managed_window`]).
+#[derive(Resource, Clone, Debug)]
+pub(crate) struct X11WindowIdentity {
+    pub title: String,
+    pub class: String,
+    pub managed: bool,
+}
+
+impl Default for X11WindowIdentity {
+    fn default() -> Self {
+        Self {
+            title: "bevy_live_wallpaper".into(),
+            class: "bevy_live_wallpaper".into(),
+            managed: false,
+        }
+    }
+}
+
+/// X display from [`crate::LiveWallpaperPlugin::x11_display`]; `None` uses
+/// `DISPLAY`.
+#[derive(Resource, Clone, Debug, Default)]
+pub(crate) struct X11Display(pub Option<String>);
 
 pub(crate) struct X11AppState {
     connection: XCBConnection,
     root_window: u32,
-    wallpaper_window: u32,
+    /// Visual, depth and colormap the wallpaper windows are created with:
+    /// the root's, or a 32-bit ARGB visual for a transparent wallpaper.
+    visual: u32,
+    depth: u8,
+    colormap: u32,
     screen: c_int,
     closed: bool,
+    visible: bool,
     target: WallpaperTargetMonitor,
     monitors: Vec<MonitorRect>,
-    monitors_dirty: bool,
-    pending_surface_config: Option<X11SurfaceConfig>,
+    /// When RandR first reported a change that hasn't been applied yet.
+    monitors_dirty_since: Option<Instant>,
+    /// How long RandR changes settle before the monitors are re-read.
+    randr_debounce: Duration,
+    /// One wallpaper window per selected monitor.
+    windows: Vec<(MonitorRect, u32)>,
+    pending_surface_config: Option<Vec<X11SurfaceConfig>>,
+    /// Whether XInput2 raw motion events were selected.
+    raw_motion: bool,
+    /// Whether the server has the Composite extension.
+    composite_available: bool,
+    /// DPMS was turned off by [`Self::disable_dpms`] and needs restoring.
+    dpms_disabled: bool,
+    screen_saver_reset_at: Option<Instant>,
+    /// Raw pointer motion since the last [`Self::poll_pointer`].
+    raw_delta_accumulator: Vec2,
+    /// Wheel clicks (buttons 4-7) since the last [`Self::poll_pointer`].
+    raw_scroll_accumulator: Vec2,
+    /// Back/forward buttons (8/9) held down; the core pointer mask only
+    /// covers buttons 1-5, so they are tracked from raw XInput2 events.
+    side_buttons: HashSet<MouseButton>,
+    /// Presses and releases from raw XInput2 events since the last
+    /// [`Self::poll_pointer`], in order, so clicks shorter than a frame
+    /// aren't lost between two pointer queries.
+    button_transitions: Vec<PointerButton>,
+    /// Cursor defined on the wallpaper windows; `NONE` inherits the root's.
+    cursor: u32,
+    identity: X11WindowIdentity,
+    /// `_NET_WM_NAME` and `UTF8_STRING`, interned at connect.
+    net_wm_name: u32,
+    utf8_string: u32,
+    ewmh: EwmhAtoms,
 }
 
 impl X11AppState {
     pub(crate) fn connect(
+        display: Option<&str>,
         target: WallpaperTargetMonitor,
-    ) -> Result<(Self, X11SurfaceConfig), String> {
-        let (connection, screen_index) = XCBConnection::connect(None)
+        identity: X11WindowIdentity,
+        transparent: bool,
+    ) -> Result<Self, String> {
+        let display = display
+            .map(CString::new)
+            .transpose()
+            .map_err(|err| format!("Invalid X11 display name: {err}"))?;
+        let (connection, screen_index) = XCBConnection::connect(display.as_deref())
             .map_err(|err| format!("Failed to connect to X11: {err}"))?;
 
         let screen = connection
@@ -50,9 +128,30 @@ impl X11AppState {
             .get(screen_index)
             .ok_or_else(|| format!("Invalid X11 screen index {screen_index}"))?;
         let root_window = screen.root;
-        let screen_width = u32::from(screen.width_in_pixels);
-        let screen_height = u32::from(screen.height_in_pixels);
-        let root_visual = screen.root_visual;
+        let argb_visual = if transparent {
+            let visual = argb_visual(screen);
+            if visual.is_none() {
+                warn!("No 32-bit ARGB visual on this X screen; rendering opaque");
+            }
+            visual
+        } else {
+            None
+        };
+        let (visual, depth, colormap) = match argb_visual {
+            Some(visual) => {
+                // A window with a non-root visual needs a colormap of its own.
+                let colormap = connection
+                    .generate_id()
+                    .map_err(|err| format!("Failed to generate colormap id: {err:?}"))?;
+                connection
+                    .create_colormap(ColormapAlloc::NONE, colormap, root_window, visual)
+                    .map_err(|err| format!("Failed to create ARGB colormap: {err:?}"))?
+                    .check()
+                    .map_err(|err| format!("Failed to create ARGB colormap: {err:?}"))?;
+                (visual, 32, colormap)
+            }
+            None => (screen.root_visual, COPY_DEPTH_FROM_PARENT, x11rb::NONE),
+        };
         let screen_id = screen_index as c_int;
 
         connection
@@ -74,6 +173,31 @@ impl X11AppState {
             )
             .map_err(|err| format!("Failed to select RandR input: {err:?}"))?;
 
+        let raw_motion = match select_raw_motion(&connection, root_window) {
+            Ok(()) => true,
+            Err(err) => {
+                info!(
+                    "XInput2 raw motion unavailable ({err}); deriving pointer deltas from positions"
+                );
+                false
+            }
+        };
+
+        let composite_available = connection
+            .extension_information("Composite")
+            .map_err(|err| format!("Failed to query the Composite extension: {err:?}"))?
+            .is_some();
+        if !composite_available {
+            warn!(
+                "X11 Composite extension not available; the wallpaper is only stacked \
+                 via override_redirect and won't be composited"
+            );
+        }
+
+        let net_wm_name = intern_atom(&connection, b"_NET_WM_NAME")?;
+        let utf8_string = intern_atom(&connection, b"UTF8_STRING")?;
+        let ewmh = EwmhAtoms::intern(&connection)?;
+
         connection
             .flush()
             .map_err(|err| format!("Failed to flush X11 connection: {err:?}"))?;
@@ -81,68 +205,93 @@ impl X11AppState {
         let mut state = Self {
             connection,
             root_window,
-            wallpaper_window: 0,
+            visual,
+            depth,
+            colormap,
             screen: screen_id,
             closed: false,
+            visible: true,
             target,
             monitors: Vec::new(),
-            monitors_dirty: true,
+            monitors_dirty_since: None,
+            randr_debounce: Duration::ZERO,
+            windows: Vec::new(),
             pending_surface_config: None,
+            raw_motion,
+            composite_available,
+            dpms_disabled: false,
+            screen_saver_reset_at: None,
+            raw_delta_accumulator: Vec2::ZERO,
+            raw_scroll_accumulator: Vec2::ZERO,
+            side_buttons: HashSet::new(),
+            button_transitions: Vec::new(),
+            cursor: x11rb::NONE,
+            identity,
+            net_wm_name,
+            utf8_string,
+            ewmh,
         };
 
         state.refresh_monitors()?;
-        state.create_or_update_wallpaper_window(root_visual)?;
-        state.monitors_dirty = false;
-
-        // Initial surface config uses current wallpaper window size.
-        let config = Self::create_surface_config(
-            &state.connection,
-            state.wallpaper_window,
-            screen_id,
-            state.current_width().unwrap_or(screen_width),
-            state.current_height().unwrap_or(screen_height),
-        );
-
-        state.pending_surface_config = Some(config);
-
-        Ok((state, config))
-    }
-
-    fn create_surface_config(
-        connection: &XCBConnection,
-        window: u32,
-        screen: c_int,
-        width: u32,
-        height: u32,
-    ) -> X11SurfaceConfig {
-        let ptr = NonNull::new(connection.as_raw_xcb_connection().cast::<c_void>())
+        if state.monitors.is_empty() {
+            return Err("No monitors reported by RandR; cannot create wallpaper window".into());
+        }
+
+        // Fall back to the first monitor if the requested one doesn't exist yet.
+        let mut rects = state.monitors_for(&state.target);
+        if rects.is_empty() {
+            rects.push(state.monitors[0].clone());
+        }
+        state.sync_windows(&rects)?;
+
+        Ok(state)
+    }
+
+    fn create_surface_config(&self, rect: &MonitorRect, window: u32) -> X11SurfaceConfig {
+        let ptr = NonNull::new(self.connection.as_raw_xcb_connection().cast::<c_void>())
             .expect("xcb connection pointer should be valid");
-        let handles = X11SurfaceHandles::new(ptr, screen, window);
+        let handles = X11SurfaceHandles::new(ptr, self.screen, window);
 
         X11SurfaceConfig {
+            window,
             handles,
-            width,
-            height,
+            width: u32::from(rect.width.max(1)),
+            height: u32::from(rect.height.max(1)),
+            offset_x: i32::from(rect.x),
+            offset_y: i32::from(rect.y),
+            refresh_hz: rect.refresh_hz,
+            physical_size_mm: rect.physical_size_mm,
+            primary: rect.primary,
+            name: rect.name.clone(),
         }
     }
 
-    fn current_width(&self) -> Option<u32> {
-        self.monitor_for(self.target).map(|rect| rect.width as u32)
+    pub(crate) fn composite_available(&self) -> bool {
+        self.composite_available
     }
 
-    fn current_height(&self) -> Option<u32> {
-        self.monitor_for(self.target).map(|rect| rect.height as u32)
+    /// Whether the windows use a 32-bit ARGB visual, so alpha reaches the
+    /// compositor.
+    pub(crate) fn argb_visual(&self) -> bool {
+        self.colormap != x11rb::NONE
     }
 
     pub(crate) fn is_running(&self) -> bool {
         !self.closed
     }
 
-    pub(crate) fn queue_surface_config(&mut self, config: X11SurfaceConfig) {
-        self.pending_surface_config = Some(config);
+    /// Queues the configuration of every wallpaper window.
+    fn queue_surface_configs(&mut self) {
+        let configs = self
+            .windows
+            .iter()
+            .map(|(rect, window)| self.create_surface_config(rect, *window))
+            .collect();
+        self.pending_surface_config = Some(configs);
     }
 
-    pub(crate) fn take_surface_config(&mut self) -> Option<X11SurfaceConfig> {
+    /// Returns the full set of window configurations if anything changed.
+    pub(crate) fn take_surface_config(&mut self) -> Option<Vec<X11SurfaceConfig>> {
         self.pending_surface_config.take()
     }
 
@@ -150,21 +299,41 @@ impl X11AppState {
         loop {
             match self.connection.poll_for_event() {
                 Ok(Some(Event::ConfigureNotify(event))) => {
-                    if event.window == self.wallpaper_window {
-                        let width = u32::from(event.width.max(1));
-                        let height = u32::from(event.height.max(1));
-                        let config = Self::create_surface_config(
-                            &self.connection,
-                            self.wallpaper_window,
-                            self.screen,
-                            width,
-                            height,
-                        );
-                        self.queue_surface_config(config);
-                    }
+                    let Some((rect, _)) = self
+                        .windows
+                        .iter_mut()
+                        .find(|(_, window)| *window == event.window)
+                    else {
+                        continue;
+                    };
+                    rect.x = event.x;
+                    rect.y = event.y;
+                    rect.width = event.width.max(1);
+                    rect.height = event.height.max(1);
+                    self.queue_surface_configs();
                 }
                 Ok(Some(Event::RandrNotify(_))) | Ok(Some(Event::RandrScreenChangeNotify(_))) => {
-                    self.monitors_dirty = true;
+                    self.monitors_dirty_since.get_or_insert_with(Instant::now);
+                }
+                Ok(Some(Event::XinputRawMotion(event))) => {
+                    if self.visible {
+                        self.raw_delta_accumulator += raw_motion_delta(&event);
+                    }
+                }
+                Ok(Some(Event::XinputRawButtonPress(event))) => {
+                    if self.visible {
+                        self.raw_scroll_accumulator += wheel_clicks(event.detail);
+                    }
+                    if let Some(button) = side_button(event.detail) {
+                        self.side_buttons.insert(button);
+                    }
+                    self.record_button_transition(event.detail, true);
+                }
+                Ok(Some(Event::XinputRawButtonRelease(event))) => {
+                    if let Some(button) = side_button(event.detail) {
+                        self.side_buttons.remove(&button);
+                    }
+                    self.record_button_transition(event.detail, false);
                 }
                 Ok(Some(_)) => {}
                 Ok(None) => break,
@@ -176,41 +345,143 @@ impl X11AppState {
             }
         }
 
-        if self.monitors_dirty && !self.closed {
+        // Mode switches arrive as a burst of notifies; apply them once settled.
+        if self
+            .monitors_dirty_since
+            .is_some_and(|since| since.elapsed() >= self.randr_debounce)
+            && !self.closed
+        {
             if let Err(err) = self.refresh_monitors() {
                 warn!("Failed to refresh RandR monitors: {err}");
-            } else if let Err(err) = self.apply_target(self.target) {
+            } else if let Err(err) = self.apply_target(&self.target.clone()) {
                 warn!("Failed to apply target monitor after RandR change: {err}");
             }
-            self.monitors_dirty = false;
+            self.monitors_dirty_since = None;
         }
     }
 
-    /// Returns a snapshot of the current pointer (root) position and buttons.
-    pub(crate) fn poll_pointer(&self, prev: Option<&PointerSample>) -> Option<PointerSample> {
-        let reply = self
+    pub(crate) fn set_randr_debounce(&mut self, debounce: Duration) {
+        self.randr_debounce = debounce;
+    }
+
+    fn record_button_transition(&mut self, detail: u32, pressed: bool) {
+        if !self.visible {
+            return;
+        }
+        if let Some(button) = core_button(detail).or_else(|| side_button(detail)) {
+            self.button_transitions.push(PointerButton {
+                button: Some(button),
+                pressed,
+            });
+        }
+    }
+
+    /// Returns the pointer samples since `prev`, ending with the current
+    /// (root) position and buttons.
+    ///
+    /// With XInput2 the delta is the raw motion accumulated since the last
+    /// call, which keeps moves faster than one frame (or against a screen
+    /// edge) intact; otherwise it's the difference between positions. Each
+    /// raw button event also yields a sample of its own, so a press and
+    /// release within one frame both come through. Raw events carry no
+    /// position, so the pointer is still queried, but only after something
+    /// happened.
+    ///
+    /// Wheel clicks only count while the pointer is over the wallpaper (or
+    /// the bare root window), not over other windows.
+    pub(crate) fn poll_pointer(&mut self, prev: Option<&PointerSample>) -> Vec<PointerSample> {
+        let raw_delta = std::mem::take(&mut self.raw_delta_accumulator);
+        let raw_scroll = std::mem::take(&mut self.raw_scroll_accumulator);
+        let transitions = std::mem::take(&mut self.button_transitions);
+
+        if self.raw_motion
+            && let Some(prev) = prev
+            && raw_delta == Vec2::ZERO
+            && raw_scroll == Vec2::ZERO
+            && transitions.is_empty()
+        {
+            return vec![PointerSample {
+                delta: Vec2::ZERO,
+                entered_output: false,
+                last_button: None,
+                scroll_discrete: Vec2::ZERO,
+                scroll_smooth: Vec2::ZERO,
+                ..prev.clone()
+            }];
+        }
+
+        let Some(reply) = self
             .connection
             .query_pointer(self.root_window)
-            .ok()?
-            .reply()
-            .ok()?;
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+        else {
+            return Vec::new();
+        };
 
         let position = Vec2::new(f32::from(reply.root_x), f32::from(reply.root_y));
         let prev_position = prev.map(|p| p.position).unwrap_or(position);
-        let delta = position - prev_position;
-
-        let pressed = pressed_buttons(reply.mask.bits());
-        let last_button = detect_last_button(prev.map(|p| &p.pressed), &pressed);
+        let delta = match prev {
+            Some(_) if self.raw_motion => raw_delta,
+            _ => position - prev_position,
+        };
 
         let output = self.output_for_position(position);
+        let mut samples: Vec<PointerSample> = Vec::with_capacity(transitions.len() + 1);
+        let mut held = prev.map(|p| p.pressed.clone()).unwrap_or_default();
+        for transition in transitions {
+            let Some(button) = transition.button else {
+                continue;
+            };
+            if transition.pressed {
+                held.insert(button);
+            } else {
+                held.remove(&button);
+            }
+            let mut sample = PointerSample {
+                seat: None,
+                output,
+                position,
+                delta: Vec2::ZERO,
+                entered_output: false,
+                pressed: held.clone(),
+                last_button: Some(transition),
+                scroll_discrete: Vec2::ZERO,
+                scroll_smooth: Vec2::ZERO,
+            };
+            sample.reset_on_output_change(samples.last().or(prev));
+            samples.push(sample);
+        }
+
+        let mut pressed = pressed_buttons(reply.mask.bits());
+        pressed.extend(self.side_buttons.iter().copied());
+        let last = samples.last().or(prev);
+        let last_button = detect_last_button(last.map(|p| &p.pressed), &pressed);
 
-        Some(PointerSample {
+        let over_wallpaper = reply.child == x11rb::NONE
+            || self
+                .windows
+                .iter()
+                .any(|(_, window)| *window == reply.child);
+
+        let mut sample = PointerSample {
+            seat: None,
             output,
             position,
             delta,
+            entered_output: false,
             pressed,
             last_button,
-        })
+            scroll_discrete: if over_wallpaper {
+                raw_scroll
+            } else {
+                Vec2::ZERO
+            },
+            scroll_smooth: Vec2::ZERO,
+        };
+        sample.reset_on_output_change(last);
+        samples.push(sample);
+        samples
     }
 
     fn output_for_position(&self, position: Vec2) -> Option<u32> {
@@ -228,37 +499,176 @@ impl X11AppState {
             .map(|(idx, _)| idx as u32)
     }
 
-    pub(crate) fn apply_target(&mut self, target: WallpaperTargetMonitor) -> Result<(), String> {
-        let Some(rect) = self.monitor_for(target) else {
-            return Err("No monitors available for selected target".into());
+    /// Maps or unmaps the wallpaper windows. Callers should re-apply the target
+    /// after showing them so fresh surface configs are queued.
+    pub(crate) fn set_visible(&mut self, visible: bool) -> Result<(), String> {
+        self.visible = visible;
+        for (_, window) in &self.windows {
+            if visible {
+                self.connection
+                    .map_window(*window)
+                    .map_err(|err| format!("Failed to map wallpaper window: {err:?}"))?
+                    .check()
+                    .map_err(|err| format!("Failed to map wallpaper window: {err:?}"))?;
+            } else {
+                self.connection
+                    .unmap_window(*window)
+                    .map_err(|err| format!("Failed to unmap wallpaper window: {err:?}"))?
+                    .check()
+                    .map_err(|err| format!("Failed to unmap wallpaper window: {err:?}"))?;
+            }
+        }
+        if !visible {
+            self.pending_surface_config = None;
+        }
+        Ok(())
+    }
+
+    /// Defines `cursor` on the wallpaper windows, replacing the previous one.
+    pub(crate) fn set_cursor(&mut self, cursor: WallpaperCursor) -> Result<(), String> {
+        let new = match cursor {
+            WallpaperCursor::Default => x11rb::NONE,
+            WallpaperCursor::Hidden => self.create_blank_cursor()?,
+            WallpaperCursor::Shape(icon) => self.create_glyph_cursor(cursor_glyph(icon))?,
         };
+        for (_, window) in &self.windows {
+            self.connection
+                .change_window_attributes(*window, &ChangeWindowAttributesAux::new().cursor(new))
+                .map_err(|err| format!("Failed to set wallpaper cursor: {err:?}"))?;
+        }
+        if self.cursor != x11rb::NONE {
+            // The windows keep their own reference; freeing only drops ours.
+            let _ = self.connection.free_cursor(self.cursor);
+        }
+        self.cursor = new;
+        self.connection
+            .flush()
+            .map_err(|err| format!("Failed to flush wallpaper cursor: {err:?}"))?;
+        Ok(())
+    }
 
-        self.target = target;
+    /// Creates an invisible cursor from an empty 1x1 bitmap.
+    fn create_blank_cursor(&self) -> Result<u32, String> {
+        let map_err =
+            |err: x11rb::errors::ConnectionError| format!("Failed to create blank cursor: {err:?}");
+        let pixmap = self
+            .connection
+            .generate_id()
+            .map_err(|err| format!("{err:?}"))?;
+        let gc = self
+            .connection
+            .generate_id()
+            .map_err(|err| format!("{err:?}"))?;
+        let cursor = self
+            .connection
+            .generate_id()
+            .map_err(|err| format!("{err:?}"))?;
+        self.connection
+            .create_pixmap(1, pixmap, self.root_window, 1, 1)
+            .map_err(map_err)?;
+        // Pixmap contents start undefined; clear the mask so nothing is drawn.
+        self.connection
+            .create_gc(gc, pixmap, &CreateGCAux::new().foreground(0))
+            .map_err(map_err)?;
+        self.connection
+            .poly_fill_rectangle(
+                pixmap,
+                gc,
+                &[Rectangle {
+                    x: 0,
+                    y: 0,
+                    width: 1,
+                    height: 1,
+                }],
+            )
+            .map_err(map_err)?;
+        self.connection
+            .create_cursor(cursor, pixmap, pixmap, 0, 0, 0, 0, 0, 0, 0, 0)
+            .map_err(map_err)?;
+        self.connection.free_gc(gc).map_err(map_err)?;
+        self.connection.free_pixmap(pixmap).map_err(map_err)?;
+        Ok(cursor)
+    }
 
-        // Move/resize wallpaper window to selected monitor bounds.
-        let aux = x11rb::protocol::xproto::ConfigureWindowAux::new()
-            .x(i32::from(rect.x))
-            .y(i32::from(rect.y))
-            .width(rect.width as u32)
-            .height(rect.height as u32)
-            .stack_mode(x11rb::protocol::xproto::StackMode::BELOW);
+    /// Creates a cursor from the core `cursor` font.
+    fn create_glyph_cursor(&self, glyph: u16) -> Result<u32, String> {
+        let map_err = |err: x11rb::errors::ConnectionError| {
+            format!("Failed to create cursor glyph {glyph}: {err:?}")
+        };
+        let font = self
+            .connection
+            .generate_id()
+            .map_err(|err| format!("{err:?}"))?;
+        let cursor = self
+            .connection
+            .generate_id()
+            .map_err(|err| format!("{err:?}"))?;
         self.connection
-            .configure_window(self.wallpaper_window, &aux)
-            .map_err(|err| format!("Failed to configure wallpaper window: {err:?}"))?
-            .check()
-            .map_err(|err| format!("Failed to configure wallpaper window: {err:?}"))?;
+            .open_font(font, b"cursor")
+            .map_err(map_err)?;
+        self.connection
+            .create_glyph_cursor(
+                cursor,
+                font,
+                font,
+                glyph,
+                glyph + 1,
+                0,
+                0,
+                0,
+                u16::MAX,
+                u16::MAX,
+                u16::MAX,
+            )
+            .map_err(map_err)?;
+        self.connection.close_font(font).map_err(map_err)?;
+        Ok(cursor)
+    }
+
+    pub(crate) fn apply_target(&mut self, target: &WallpaperTargetMonitor) -> Result<(), String> {
+        let rects = self.monitors_for(target);
+        if rects.is_empty() {
+            return Err("No monitors available for selected target".into());
+        }
+
+        self.target = target.clone();
+        self.sync_windows(&rects)
+    }
+
+    /// Moves existing windows onto `rects`, creating or destroying windows so
+    /// there is exactly one per rect, then queues the new configuration.
+    fn sync_windows(&mut self, rects: &[MonitorRect]) -> Result<(), String> {
+        for (index, rect) in rects.iter().enumerate() {
+            if let Some((current, window)) = self.windows.get_mut(index) {
+                let aux = x11rb::protocol::xproto::ConfigureWindowAux::new()
+                    .x(i32::from(rect.x))
+                    .y(i32::from(rect.y))
+                    .width(u32::from(rect.width))
+                    .height(u32::from(rect.height))
+                    .stack_mode(x11rb::protocol::xproto::StackMode::BELOW);
+                self.connection
+                    .configure_window(*window, &aux)
+                    .map_err(|err| format!("Failed to configure wallpaper window: {err:?}"))?
+                    .check()
+                    .map_err(|err| format!("Failed to configure wallpaper window: {err:?}"))?;
+                *current = rect.clone();
+            } else {
+                let window = self.create_wallpaper_window(rect)?;
+                self.windows.push((rect.clone(), window));
+            }
+        }
+
+        for (_, window) in self.windows.drain(rects.len()..) {
+            if let Err(err) = self.connection.destroy_window(window) {
+                warn!("Failed to destroy wallpaper window {window}: {err:?}");
+            }
+        }
+
         self.connection
             .flush()
             .map_err(|err| format!("Failed to flush wallpaper configure: {err:?}"))?;
 
-        let config = Self::create_surface_config(
-            &self.connection,
-            self.wallpaper_window,
-            self.screen,
-            rect.width as u32,
-            rect.height as u32,
-        );
-        self.queue_surface_config(config);
+        self.queue_surface_configs();
         Ok(())
     }
 
@@ -270,25 +680,114 @@ impl X11AppState {
             .reply()
             .map_err(|err| format!("Failed to read RandR monitors reply: {err:?}"))?;
 
-        self.monitors = reply.monitors.into_iter().map(MonitorRect::from).collect();
+        let resources = self
+            .connection
+            .randr_get_screen_resources_current(self.root_window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok());
+        self.monitors = reply
+            .monitors
+            .into_iter()
+            .map(|monitor| {
+                // Disabled monitors have no outputs to ask about.
+                let output_info = monitor
+                    .outputs
+                    .first()
+                    .and_then(|output| self.output_info(*output, resources.as_ref()));
+                let refresh_hz = resources
+                    .as_ref()
+                    .zip(output_info.as_ref())
+                    .and_then(|(resources, info)| self.output_refresh_hz(info, resources));
+                MonitorRect {
+                    refresh_hz,
+                    name: output_info
+                        .map(|info| String::from_utf8_lossy(&info.name).into_owned())
+                        .unwrap_or_default(),
+                    ..MonitorRect::from(monitor)
+                }
+            })
+            .collect();
         Ok(())
     }
 
-    fn monitor_for(&self, target: WallpaperTargetMonitor) -> Option<MonitorRect> {
+    /// Connector name and CRTC of a RandR output.
+    fn output_info(
+        &self,
+        output: randr::Output,
+        resources: Option<&randr::GetScreenResourcesCurrentReply>,
+    ) -> Option<randr::GetOutputInfoReply> {
+        let timestamp =
+            resources.map_or(x11rb::CURRENT_TIME, |resources| resources.config_timestamp);
+        self.connection
+            .randr_get_output_info(output, timestamp)
+            .ok()?
+            .reply()
+            .ok()
+    }
+
+    /// Refresh rate of the CRTC driving a monitor's first output.
+    fn output_refresh_hz(
+        &self,
+        output_info: &randr::GetOutputInfoReply,
+        resources: &randr::GetScreenResourcesCurrentReply,
+    ) -> Option<f64> {
+        if output_info.crtc == x11rb::NONE {
+            return None;
+        }
+        let crtc = self
+            .connection
+            .randr_get_crtc_info(output_info.crtc, resources.config_timestamp)
+            .ok()?
+            .reply()
+            .ok()?;
+        resources
+            .modes
+            .iter()
+            .find(|mode| mode.id == crtc.mode)
+            .and_then(mode_refresh_hz)
+    }
+
+    /// Monitors covered by `target`, one wallpaper window each.
+    fn monitors_for(&self, target: &WallpaperTargetMonitor) -> Vec<MonitorRect> {
         match target {
-            WallpaperTargetMonitor::All => MonitorRect::bounding(&self.monitors),
+            WallpaperTargetMonitor::All => self.monitors.clone(),
             WallpaperTargetMonitor::Primary => self
                 .monitors
                 .iter()
                 .find(|m| m.primary)
                 .or_else(|| self.monitors.first())
-                .copied(),
-            WallpaperTargetMonitor::Index(n) => self.monitors.get(n).copied(),
+                .cloned()
+                .into_iter()
+                .collect(),
+            WallpaperTargetMonitor::Index(n) => {
+                self.monitors.get(*n).cloned().into_iter().collect()
+            }
+            WallpaperTargetMonitor::Name(_) => self
+                .monitors
+                .iter()
+                .filter(|m| target.matches_name(Some(&m.name), None))
+                .cloned()
+                .collect(),
         }
     }
 
+    /// Monitors with an output, for [`WallpaperMonitorConnected`].
+    pub(crate) fn connected_monitors(&self) -> Vec<WallpaperMonitorConnected> {
+        self.monitors
+            .iter()
+            .filter(|m| m.output != x11rb::NONE)
+            .map(|m| WallpaperMonitorConnected {
+                output: m.output,
+                name: (!m.name.is_empty()).then(|| m.name.clone()),
+                size: UVec2::new(m.width.into(), m.height.into()),
+                position: IVec2::new(m.x.into(), m.y.into()),
+            })
+            .collect()
+    }
+
     pub(crate) fn current_bounds(&self) -> Option<(i32, i32, u32, u32)> {
-        self.monitor_for(self.target).map(|rect| {
+        let rects: Vec<MonitorRect> = self.windows.iter().map(|(rect, _)| rect.clone()).collect();
+        MonitorRect::bounding(&rects).map(|rect| {
             (
                 rect.x as i32,
                 rect.y as i32,
@@ -298,89 +797,207 @@ impl X11AppState {
         })
     }
 
-    fn create_or_update_wallpaper_window(&mut self, visual: u32) -> Result<(), String> {
-        if self.monitors.is_empty() {
-            return Err("No monitors reported by RandR; cannot create wallpaper window".into());
+    fn create_wallpaper_window(&self, rect: &MonitorRect) -> Result<u32, String> {
+        let window = self
+            .connection
+            .generate_id()
+            .map_err(|err| format!("Failed to generate window id: {err:?}"))?;
+
+        let mut aux = x11rb::protocol::xproto::CreateWindowAux::new()
+            .event_mask(EventMask::STRUCTURE_NOTIFY)
+            .override_redirect(u32::from(!self.identity.managed))
+            .background_pixel(0)
+            .border_pixel(0)
+            .cursor(self.cursor);
+        if self.colormap != x11rb::NONE {
+            aux = aux.colormap(self.colormap);
         }
 
-        let rect = self
-            .monitor_for(self.target)
-            .unwrap_or_else(|| self.monitors[0]);
-
-        if self.wallpaper_window == 0 {
-            let window = self
-                .connection
-                .generate_id()
-                .map_err(|err| format!("Failed to generate window id: {err:?}"))?;
-
-            let aux = x11rb::protocol::xproto::CreateWindowAux::new()
-                .event_mask(EventMask::STRUCTURE_NOTIFY)
-                .override_redirect(1)
-                .background_pixel(0)
-                .border_pixel(0);
-
-            self.connection
-                .create_window(
-                    COPY_DEPTH_FROM_PARENT,
-                    window,
-                    self.root_window,
-                    rect.x,
-                    rect.y,
-                    rect.width,
-                    rect.height,
-                    0,
-                    x11rb::protocol::xproto::WindowClass::INPUT_OUTPUT,
-                    visual,
-                    &aux,
-                )
-                .map_err(|err| format!("Failed to create wallpaper window: {err:?}"))?
-                .check()
-                .map_err(|err| format!("Failed to create wallpaper window: {err:?}"))?;
+        self.connection
+            .create_window(
+                self.depth,
+                window,
+                self.root_window,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                0,
+                x11rb::protocol::xproto::WindowClass::INPUT_OUTPUT,
+                self.visual,
+                &aux,
+            )
+            .map_err(|err| format!("Failed to create wallpaper window: {err:?}"))?
+            .check()
+            .map_err(|err| format!("Failed to create wallpaper window: {err:?}"))?;
+        self.set_window_identity(window)?;
 
-            // Place behind other windows.
-            let config_aux = x11rb::protocol::xproto::ConfigureWindowAux::new()
-                .stack_mode(x11rb::protocol::xproto::StackMode::BELOW);
-            self.connection
-                .configure_window(window, &config_aux)
-                .map_err(|err| format!("Failed to lower wallpaper window: {err:?}"))?
-                .check()
-                .map_err(|err| format!("Failed to lower wallpaper window: {err:?}"))?;
+        // Place behind other windows.
+        let config_aux = x11rb::protocol::xproto::ConfigureWindowAux::new()
+            .stack_mode(x11rb::protocol::xproto::StackMode::BELOW);
+        self.connection
+            .configure_window(window, &config_aux)
+            .map_err(|err| format!("Failed to lower wallpaper window: {err:?}"))?
+            .check()
+            .map_err(|err| format!("Failed to lower wallpaper window: {err:?}"))?;
 
+        if self.visible {
             self.connection
                 .map_window(window)
                 .map_err(|err| format!("Failed to map wallpaper window: {err:?}"))?
                 .check()
                 .map_err(|err| format!("Failed to map wallpaper window: {err:?}"))?;
-
-            self.wallpaper_window = window;
-        } else {
-            self.apply_target(self.target)?;
         }
 
+        Ok(window)
+    }
+
+    /// Names the window so it can be told apart in `xprop`, `xdotool` and
+    /// compositor window rules, and marks it as the desktop for window
+    /// managers and compositors that read EWMH hints.
+    fn set_window_identity(&self, window: u32) -> Result<(), String> {
+        let X11WindowIdentity { title, class, .. } = &self.identity;
+        // `WM_NAME` is Latin-1; anything outside it only survives in `_NET_WM_NAME`.
+        let latin1_title: Vec<u8> = title
+            .chars()
+            .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+            .collect();
+        // Instance and class name, each NUL-terminated.
+        let wm_class = [class.as_bytes(), b"\0", class.as_bytes(), b"\0"].concat();
+
+        let map_err = |err| format!("Failed to set wallpaper window properties: {err:?}");
+        self.connection
+            .change_property8(
+                PropMode::REPLACE,
+                window,
+                AtomEnum::WM_NAME,
+                AtomEnum::STRING,
+                &latin1_title,
+            )
+            .map_err(map_err)?;
+        self.connection
+            .change_property8(
+                PropMode::REPLACE,
+                window,
+                AtomEnum::WM_CLASS,
+                AtomEnum::STRING,
+                &wm_class,
+            )
+            .map_err(map_err)?;
+        self.connection
+            .change_property8(
+                PropMode::REPLACE,
+                window,
+                self.net_wm_name,
+                self.utf8_string,
+                title.as_bytes(),
+            )
+            .map_err(map_err)?;
+        self.connection
+            .change_property32(
+                PropMode::REPLACE,
+                window,
+                self.ewmh.window_type,
+                AtomEnum::ATOM,
+                &[self.ewmh.window_type_desktop],
+            )
+            .map_err(map_err)?;
+        self.connection
+            .change_property32(
+                PropMode::REPLACE,
+                window,
+                self.ewmh.state,
+                AtomEnum::ATOM,
+                &self.ewmh.states,
+            )
+            .map_err(map_err)?;
         Ok(())
     }
 }
 
-#[derive(Clone, Copy)]
+/// EWMH atoms that mark the wallpaper windows as the desktop.
+struct EwmhAtoms {
+    window_type: u32,
+    window_type_desktop: u32,
+    state: u32,
+    /// Below other windows, on every workspace, out of taskbars and pagers.
+    states: [u32; 4],
+}
+
+impl EwmhAtoms {
+    fn intern(connection: &XCBConnection) -> Result<Self, String> {
+        Ok(Self {
+            window_type: intern_atom(connection, b"_NET_WM_WINDOW_TYPE")?,
+            window_type_desktop: intern_atom(connection, b"_NET_WM_WINDOW_TYPE_DESKTOP")?,
+            state: intern_atom(connection, b"_NET_WM_STATE")?,
+            states: [
+                intern_atom(connection, b"_NET_WM_STATE_BELOW")?,
+                intern_atom(connection, b"_NET_WM_STATE_STICKY")?,
+                intern_atom(connection, b"_NET_WM_STATE_SKIP_TASKBAR")?,
+                intern_atom(connection, b"_NET_WM_STATE_SKIP_PAGER")?,
+            ],
+        })
+    }
+}
+
+/// A 32-bit true-color visual, which carries an alpha channel.
+fn argb_visual(screen: &Screen) -> Option<u32> {
+    screen
+        .allowed_depths
+        .iter()
+        .filter(|depth| depth.depth == 32)
+        .flat_map(|depth| &depth.visuals)
+        .find(|visual| visual.class == VisualClass::TRUE_COLOR && visual.bits_per_rgb_value == 8)
+        .map(|visual| visual.visual_id)
+}
+
+fn intern_atom(connection: &XCBConnection, name: &[u8]) -> Result<u32, String> {
+    connection
+        .intern_atom(false, name)
+        .map_err(|err| format!("{err:?}"))
+        .and_then(|cookie| cookie.reply().map_err(|err| format!("{err:?}")))
+        .map(|reply| reply.atom)
+        .map_err(|err| format!("Failed to intern {}: {err}", String::from_utf8_lossy(name)))
+}
+
+#[derive(Clone)]
 pub(crate) struct X11SurfaceConfig {
+    pub window: u32,
     pub handles: X11SurfaceHandles,
     pub width: u32,
     pub height: u32,
+    /// Position of the window on the X screen.
+    pub offset_x: i32,
+    pub offset_y: i32,
+    /// Refresh rate of the monitor's current mode.
+    pub refresh_hz: Option<f64>,
+    /// Physical size of the monitor, if RandR reports a non-zero one.
+    pub physical_size_mm: Option<UVec2>,
+    /// Whether the monitor is the RandR primary.
+    pub primary: bool,
+    /// Connector name of the monitor's first output; empty if it has none.
+    pub name: String,
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Debug, Default)]
 struct MonitorRect {
     x: i16,
     y: i16,
     width: u16,
     height: u16,
     primary: bool,
+    refresh_hz: Option<f64>,
+    physical_size_mm: Option<UVec2>,
+    /// Connector name (e.g. `DP-1`) from `randr_get_output_info`.
+    name: String,
+    /// First RandR output of the monitor; `NONE` if it has none.
+    output: randr::Output,
 }
 
 impl MonitorRect {
     fn bounding(monitors: &[Self]) -> Option<Self> {
         let mut iter = monitors.iter();
-        let first = iter.next().copied()?;
+        let first = iter.next()?;
 
         let mut min_x = first.x as i32;
         let mut min_y = first.y as i32;
@@ -399,7 +1016,8 @@ impl MonitorRect {
             y: min_y as i16,
             width: (max_x - min_x) as u16,
             height: (max_y - min_y) as u16,
-            primary: false,
+            primary: monitors.iter().any(|m| m.primary),
+            ..default()
         })
     }
 }
@@ -412,7 +1030,153 @@ impl From<MonitorInfo> for MonitorRect {
             width: m.width,
             height: m.height,
             primary: m.primary,
+            refresh_hz: None,
+            name: String::new(),
+            output: m.outputs.first().copied().unwrap_or(x11rb::NONE),
+            physical_size_mm: (m.width_in_millimeters > 0 && m.height_in_millimeters > 0)
+                .then(|| UVec2::new(m.width_in_millimeters, m.height_in_millimeters)),
+        }
+    }
+}
+
+/// Vertical refresh rate of a RandR mode.
+fn mode_refresh_hz(mode: &randr::ModeInfo) -> Option<f64> {
+    let mut vtotal = f64::from(mode.vtotal);
+    if mode.mode_flags.contains(randr::ModeFlag::DOUBLE_SCAN) {
+        vtotal *= 2.0;
+    }
+    if mode.mode_flags.contains(randr::ModeFlag::INTERLACE) {
+        vtotal /= 2.0;
+    }
+    let total = f64::from(mode.htotal) * vtotal;
+    (total > 0.0).then(|| f64::from(mode.dot_clock) / total)
+}
+
+/// Selects XInput2 raw motion and button events from all master pointers on
+/// `root`.
+fn select_raw_motion(connection: &XCBConnection, root: u32) -> Result<(), String> {
+    connection
+        .extension_information(xinput::X11_EXTENSION_NAME)
+        .map_err(|err| format!("{err:?}"))?
+        .ok_or("XInputExtension not present")?;
+    let version = connection
+        .xinput_xi_query_version(2, 0)
+        .map_err(|err| format!("{err:?}"))?
+        .reply()
+        .map_err(|err| format!("{err:?}"))?;
+    if version.major_version < 2 {
+        return Err(format!(
+            "server supports XInput {}.{}",
+            version.major_version, version.minor_version
+        ));
+    }
+    connection
+        .xinput_xi_select_events(
+            root,
+            &[xinput::EventMask {
+                deviceid: xinput::Device::ALL_MASTER.into(),
+                mask: vec![
+                    xinput::XIEventMask::RAW_MOTION
+                        | xinput::XIEventMask::RAW_BUTTON_PRESS
+                        | xinput::XIEventMask::RAW_BUTTON_RELEASE,
+                ],
+            }],
+        )
+        .map_err(|err| format!("{err:?}"))?
+        .check()
+        .map_err(|err| format!("{err:?}"))
+}
+
+/// Motion along valuators 0 (x) and 1 (y) of a raw event, after acceleration.
+fn raw_motion_delta(event: &xinput::RawMotionEvent) -> Vec2 {
+    let mut delta = Vec2::ZERO;
+    let mut values = event.axisvalues.iter();
+    for axis in 0..2 {
+        let set = event
+            .valuator_mask
+            .get(axis / 32)
+            .is_some_and(|bits| bits & (1 << (axis % 32)) != 0);
+        if !set {
+            continue;
         }
+        let Some(value) = values.next() else { break };
+        delta[axis] = (f64::from(value.integral) + f64::from(value.frac) / 4_294_967_296.0) as f32;
+    }
+    delta
+}
+
+/// Closest glyph of the core cursor font (`X11/cursorfont.h`).
+fn cursor_glyph(icon: SystemCursorIcon) -> u16 {
+    const XC_X_CURSOR: u16 = 0;
+    const XC_BOTTOM_LEFT_CORNER: u16 = 12;
+    const XC_BOTTOM_RIGHT_CORNER: u16 = 14;
+    const XC_BOTTOM_SIDE: u16 = 16;
+    const XC_CROSSHAIR: u16 = 34;
+    const XC_FLEUR: u16 = 52;
+    const XC_HAND2: u16 = 60;
+    const XC_LEFT_PTR: u16 = 68;
+    const XC_LEFT_SIDE: u16 = 70;
+    const XC_PLUS: u16 = 90;
+    const XC_QUESTION_ARROW: u16 = 92;
+    const XC_RIGHT_SIDE: u16 = 96;
+    const XC_SB_H_DOUBLE_ARROW: u16 = 108;
+    const XC_SB_V_DOUBLE_ARROW: u16 = 116;
+    const XC_TOP_LEFT_CORNER: u16 = 134;
+    const XC_TOP_RIGHT_CORNER: u16 = 136;
+    const XC_TOP_SIDE: u16 = 138;
+    const XC_WATCH: u16 = 150;
+    const XC_XTERM: u16 = 152;
+
+    match icon {
+        SystemCursorIcon::Help => XC_QUESTION_ARROW,
+        SystemCursorIcon::Pointer | SystemCursorIcon::Grab | SystemCursorIcon::Grabbing => XC_HAND2,
+        SystemCursorIcon::Progress | SystemCursorIcon::Wait => XC_WATCH,
+        SystemCursorIcon::Cell | SystemCursorIcon::ZoomIn | SystemCursorIcon::ZoomOut => XC_PLUS,
+        SystemCursorIcon::Crosshair => XC_CROSSHAIR,
+        SystemCursorIcon::Text | SystemCursorIcon::VerticalText => XC_XTERM,
+        SystemCursorIcon::Move | SystemCursorIcon::AllScroll => XC_FLEUR,
+        SystemCursorIcon::NoDrop | SystemCursorIcon::NotAllowed => XC_X_CURSOR,
+        SystemCursorIcon::EResize => XC_RIGHT_SIDE,
+        SystemCursorIcon::NResize => XC_TOP_SIDE,
+        SystemCursorIcon::NeResize => XC_TOP_RIGHT_CORNER,
+        SystemCursorIcon::NwResize => XC_TOP_LEFT_CORNER,
+        SystemCursorIcon::SResize => XC_BOTTOM_SIDE,
+        SystemCursorIcon::SeResize => XC_BOTTOM_RIGHT_CORNER,
+        SystemCursorIcon::SwResize => XC_BOTTOM_LEFT_CORNER,
+        SystemCursorIcon::WResize => XC_LEFT_SIDE,
+        SystemCursorIcon::EwResize | SystemCursorIcon::ColResize => XC_SB_H_DOUBLE_ARROW,
+        SystemCursorIcon::NsResize | SystemCursorIcon::RowResize => XC_SB_V_DOUBLE_ARROW,
+        SystemCursorIcon::NeswResize => XC_BOTTOM_LEFT_CORNER,
+        SystemCursorIcon::NwseResize => XC_BOTTOM_RIGHT_CORNER,
+        _ => XC_LEFT_PTR,
+    }
+}
+
+/// Scroll of a core wheel button (4/5 vertical, 6/7 horizontal).
+fn wheel_clicks(button: u32) -> Vec2 {
+    match button {
+        4 => Vec2::NEG_Y,
+        5 => Vec2::Y,
+        6 => Vec2::NEG_X,
+        7 => Vec2::X,
+        _ => Vec2::ZERO,
+    }
+}
+
+fn core_button(button: u32) -> Option<MouseButton> {
+    match button {
+        1 => Some(MouseButton::Left),
+        2 => Some(MouseButton::Middle),
+        3 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+fn side_button(button: u32) -> Option<MouseButton> {
+    match button {
+        8 => Some(MouseButton::Back),
+        9 => Some(MouseButton::Forward),
+        _ => None,
     }
 }
 
@@ -436,41 +1200,15 @@ fn pressed_buttons(mask: u16) -> HashSet<MouseButton> {
     set
 }
 
-fn detect_last_button(
-    prev: Option<&HashSet<MouseButton>>,
-    current: &HashSet<MouseButton>,
-) -> Option<PointerButton> {
-    let empty = HashSet::new();
-    let prev = prev.unwrap_or(&empty);
-
-    let mut newly_pressed: Vec<MouseButton> = current.difference(prev).copied().collect();
-    if let Some(btn) = prioritize_button(&mut newly_pressed) {
-        return Some(PointerButton {
-            button: Some(btn),
-            pressed: true,
-        });
-    }
-
-    let mut released: Vec<MouseButton> = prev.difference(current).copied().collect();
-    if let Some(btn) = prioritize_button(&mut released) {
-        return Some(PointerButton {
-            button: Some(btn),
-            pressed: false,
-        });
-    }
-
-    None
-}
-
-fn prioritize_button(buttons: &mut Vec<MouseButton>) -> Option<MouseButton> {
-    // Deterministic priority similar to common UX expectations.
-    let priority = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for p in priority {
-        if let Some(pos) = buttons.iter().position(|b| *b == p) {
-            return Some(buttons.swap_remove(pos));
-        }
+    #[test]
+    fn side_buttons_map_to_back_and_forward() {
+        assert_eq!(side_button(8), Some(MouseButton::Back));
+        assert_eq!(side_button(9), Some(MouseButton::Forward));
+        assert_eq!(side_button(4), None);
+        assert_eq!(core_button(8), None);
     }
-
-    buttons.pop()
 }
@@ -1,7 +1,9 @@
+use std::collections::HashMap;
+
 use bevy::{
     asset::RenderAssetUsages,
     log::{debug, error, warn},
-    prelude::{Assets, Handle, Image, Res, ResMut, Resource},
+    prelude::{Assets, Handle, Image, Res, ResMut, Resource, UVec2},
     render::{
         extract_resource::ExtractResource,
         render_asset::RenderAssets,
@@ -15,9 +17,16 @@ use wgpu::{
     SurfaceConfiguration, SurfaceTargetUnsafe, TextureAspect,
 };
 
-use crate::x11::surface::X11SurfaceHandles;
+use crate::{
+    WallpaperAlphaMode,
+    redraw::WallpaperDamage,
+    surface_format::SurfacePreferences,
+    surface_recovery::{SharedSurfaceRecovery, SurfaceBackoff},
+    x11::{X11SurfaceConfig, surface::X11SurfaceHandles},
+};
 
-pub const X11_SURFACE_FORMAT: TextureFormat = TextureFormat::Bgra8UnormSrgb;
+/// Format of the render target until a surface format has been negotiated.
+pub(crate) const DEFAULT_X11_SURFACE_FORMAT: TextureFormat = TextureFormat::Bgra8UnormSrgb;
 
 pub(crate) fn create_x11_image(images: &mut Assets<Image>) -> Handle<Image> {
     let size = Extent3d {
@@ -29,7 +38,7 @@ pub(crate) fn create_x11_image(images: &mut Assets<Image>) -> Handle<Image> {
         size,
         TextureDimension::D2,
         &[0, 0, 0, 255],
-        X11_SURFACE_FORMAT,
+        DEFAULT_X11_SURFACE_FORMAT,
         RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
     );
     image.texture_descriptor.usage =
@@ -39,22 +48,73 @@ pub(crate) fn create_x11_image(images: &mut Assets<Image>) -> Handle<Image> {
 
 #[derive(Resource, ExtractResource, Clone, Debug, Default)]
 pub(crate) struct X11SurfaceDescriptor {
-    pub handles: Option<X11SurfaceHandles>,
+    /// One entry per wallpaper window; empty while no window should be drawn.
+    pub surfaces: Vec<X11SurfaceEntry>,
+    pub generation: u64,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct X11SurfaceEntry {
+    pub window: u32,
+    pub handles: X11SurfaceHandles,
     pub width: u32,
     pub height: u32,
-    pub generation: u64,
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub refresh_hz: Option<f64>,
+    pub physical_size_mm: Option<UVec2>,
+    pub primary: bool,
+    pub name: String,
+}
+
+impl From<X11SurfaceConfig> for X11SurfaceEntry {
+    fn from(config: X11SurfaceConfig) -> Self {
+        Self {
+            window: config.window,
+            handles: config.handles,
+            width: config.width,
+            height: config.height,
+            offset_x: config.offset_x,
+            offset_y: config.offset_y,
+            refresh_hz: config.refresh_hz,
+            physical_size_mm: config.physical_size_mm,
+            primary: config.primary,
+            name: config.name,
+        }
+    }
 }
 
 impl X11SurfaceDescriptor {
     pub(crate) fn new() -> Self {
         Self {
-            handles: None,
-            width: 0,
-            height: 0,
+            surfaces: Vec::new(),
             generation: 0,
         }
     }
 
+    /// Bounding box of all windows on the X screen.
+    pub(crate) fn overall_bounds(&self) -> Option<(i32, i32, u32, u32)> {
+        let mut iter = self.surfaces.iter();
+        let first = iter.next()?;
+
+        let mut min_x = first.offset_x;
+        let mut min_y = first.offset_y;
+        let mut max_x = first.offset_x + first.width as i32;
+        let mut max_y = first.offset_y + first.height as i32;
+
+        for s in iter {
+            min_x = min_x.min(s.offset_x);
+            min_y = min_y.min(s.offset_y);
+            max_x = max_x.max(s.offset_x + s.width as i32);
+            max_y = max_y.max(s.offset_y + s.height as i32);
+        }
+
+        let width = (max_x - min_x).max(1) as u32;
+        let height = (max_y - min_y).max(1) as u32;
+
+        Some((min_x, min_y, width, height))
+    }
+
     pub(crate) fn bump_generation(&mut self) {
         self.generation = self.generation.wrapping_add(1);
     }
@@ -75,14 +135,23 @@ impl X11RenderTarget {
     }
 }
 
+/// wgpu surfaces keyed by X11 window id.
 #[derive(Resource, Default)]
 pub(crate) struct X11GpuSurfaceState {
+    pub surfaces: HashMap<u32, X11GpuPerSurface>,
+}
+
+#[derive(Default)]
+pub(crate) struct X11GpuPerSurface {
     pub surface: Option<wgpu::Surface<'static>>,
     pub config: Option<SurfaceConfiguration>,
     pub last_applied_generation: u64,
+    /// Present even if nothing changed, e.g. right after (re)configuring.
+    pub needs_present: bool,
+    pub backoff: SurfaceBackoff,
 }
 
-impl X11GpuSurfaceState {
+impl X11GpuPerSurface {
     pub(crate) fn mark_stale(&mut self) {
         self.surface = None;
         self.config = None;
@@ -93,171 +162,259 @@ impl X11GpuSurfaceState {
 pub(crate) fn prepare_x11_surface(
     descriptor: Res<X11SurfaceDescriptor>,
     mut state: ResMut<X11GpuSurfaceState>,
+    preferences: SurfacePreferences,
     render_instance: Res<RenderInstance>,
     render_adapter: Res<RenderAdapter>,
     render_device: Res<RenderDevice>,
+    wallpaper_alpha: Option<Res<WallpaperAlphaMode>>,
 ) {
-    if descriptor.handles.is_none() {
-        if state.surface.is_some() {
-            debug!("X11 surface handles dropped; tearing down wgpu surface");
-        }
-        state.mark_stale();
-        return;
+    let before = state.surfaces.len();
+    state
+        .surfaces
+        .retain(|window, _| descriptor.surfaces.iter().any(|s| s.window == *window));
+    if state.surfaces.len() != before {
+        debug!("X11 wallpaper windows dropped; tearing down their wgpu surfaces");
     }
 
-    if descriptor.width == 0 || descriptor.height == 0 {
-        return;
-    }
-
-    let needs_recreate =
-        state.surface.is_none() || state.last_applied_generation != descriptor.generation;
-
-    if needs_recreate {
-        let handles = descriptor.handles.expect("handles exist");
-        let raw_display_handle = handles.raw_display_handle();
-        let raw_window_handle = handles.raw_window_handle();
-        let instance = render_instance.0.as_ref();
-        let surface = unsafe {
-            instance
-                .create_surface_unsafe(SurfaceTargetUnsafe::RawHandle {
-                    raw_display_handle: Some(raw_display_handle),
-                    raw_window_handle,
-                })
-                .expect("failed to create X11 wgpu surface")
-        };
-        state.surface = Some(surface);
-    }
-
-    let Some(surface) = state.surface.as_ref() else {
-        return;
-    };
-
-    let width = descriptor.width.max(1);
-    let height = descriptor.height.max(1);
-
-    let needs_reconfigure = state
-        .config
-        .as_ref()
-        .map(|config| config.width != width || config.height != height)
-        .unwrap_or(true);
-
-    if needs_reconfigure || needs_recreate {
-        let capabilities = surface.get_capabilities(render_adapter.0.as_ref());
-        if capabilities.formats.is_empty() {
-            warn!("X11 surface reported no supported formats; retrying later");
-            state.mark_stale();
-            return;
+    for surf_desc in &descriptor.surfaces {
+        if surf_desc.width == 0 || surf_desc.height == 0 {
+            continue;
         }
 
-        let format = capabilities
-            .formats
-            .iter()
-            .copied()
-            .find(|fmt| *fmt == X11_SURFACE_FORMAT)
-            .or_else(|| capabilities.formats.first().copied())
-            .expect("X11 surface has no supported formats");
-
-        let present_mode = capabilities
-            .present_modes
-            .iter()
-            .copied()
-            .find(|mode| matches!(mode, PresentMode::Mailbox | PresentMode::Immediate))
-            .unwrap_or(PresentMode::Fifo);
-
-        let alpha_mode = capabilities
-            .alpha_modes
-            .iter()
-            .copied()
-            .find(|mode| matches!(mode, CompositeAlphaMode::Opaque))
-            .unwrap_or(capabilities.alpha_modes[0]);
+        let entry = state.surfaces.entry(surf_desc.window).or_default();
+        if entry.surface.is_none() && entry.backoff.waiting() {
+            continue;
+        }
 
-        let mut usage = TextureUsages::RENDER_ATTACHMENT;
-        if capabilities.usages.contains(TextureUsages::COPY_DST) {
-            usage |= TextureUsages::COPY_DST;
+        let needs_recreate =
+            entry.surface.is_none() || entry.last_applied_generation != descriptor.generation;
+
+        if needs_recreate {
+            let raw_display_handle = surf_desc.handles.raw_display_handle();
+            let raw_window_handle = surf_desc.handles.raw_window_handle();
+            let instance = render_instance.0.as_ref();
+            let surface = unsafe {
+                instance
+                    .create_surface_unsafe(SurfaceTargetUnsafe::RawHandle {
+                        raw_display_handle: Some(raw_display_handle),
+                        raw_window_handle,
+                    })
+                    .expect("failed to create X11 wgpu surface")
+            };
+            entry.surface = Some(surface);
         }
 
-        let config = SurfaceConfiguration {
-            usage,
-            format,
-            width,
-            height,
-            present_mode,
-            alpha_mode,
-            view_formats: vec![],
-            desired_maximum_frame_latency: 1,
+        let Some(surface) = entry.surface.as_ref() else {
+            continue;
         };
 
-        render_device.configure_surface(surface, &config);
+        let width = surf_desc.width.max(1);
+        let height = surf_desc.height.max(1);
+
+        let needs_reconfigure = entry
+            .config
+            .as_ref()
+            .map(|config| config.width != width || config.height != height)
+            .unwrap_or(true);
+
+        if needs_reconfigure || needs_recreate {
+            let capabilities = surface.get_capabilities(render_adapter.0.as_ref());
+            if capabilities.formats.is_empty() {
+                warn!("X11 surface reported no supported formats; retrying later");
+                entry.mark_stale();
+                continue;
+            }
+
+            let format = preferences
+                .choose_format(&capabilities.formats, DEFAULT_X11_SURFACE_FORMAT)
+                .expect("X11 surface has no supported formats");
+
+            let present_mode = preferences.present_mode(
+                &capabilities.present_modes,
+                &[PresentMode::Mailbox, PresentMode::Immediate],
+            );
+
+            // With a 32-bit visual, `Inherit` leaves the alpha channel to the
+            // X server, which passes it on to the compositor.
+            let preferred_alpha: &[CompositeAlphaMode] = match wallpaper_alpha.as_deref() {
+                Some(WallpaperAlphaMode::Transparent) => &[
+                    CompositeAlphaMode::PreMultiplied,
+                    CompositeAlphaMode::PostMultiplied,
+                    CompositeAlphaMode::Inherit,
+                ],
+                _ => &[CompositeAlphaMode::Opaque],
+            };
+            let alpha_mode = preferred_alpha
+                .iter()
+                .copied()
+                .find(|mode| capabilities.alpha_modes.contains(mode))
+                .unwrap_or(capabilities.alpha_modes[0]);
+
+            // Frames are copied into the surface texture on present.
+            if !capabilities.usages.contains(TextureUsages::COPY_DST) {
+                entry.backoff.record_failure();
+                if entry.backoff.info().consecutive_failures == 1 {
+                    warn!(
+                        "X11 surface for window {} doesn't support COPY_DST usage; \
+                         it can't be presented to",
+                        surf_desc.window
+                    );
+                }
+                entry.mark_stale();
+                continue;
+            }
+            let usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_DST;
+
+            let config = SurfaceConfiguration {
+                usage,
+                format,
+                width,
+                height,
+                present_mode,
+                alpha_mode,
+                view_formats: vec![],
+                desired_maximum_frame_latency: preferences.frame_latency(),
+            };
+
+            render_device.configure_surface(surface, &config);
+
+            entry.config = Some(config);
+            entry.needs_present = true;
+        }
 
-        state.config = Some(config);
+        entry.last_applied_generation = descriptor.generation;
     }
-
-    state.last_applied_generation = descriptor.generation;
 }
 
+/// Copies each window's sub-rectangle of the combined render target to it.
 pub(crate) fn present_x11_surface(
     mut state: ResMut<X11GpuSurfaceState>,
+    damage: Option<Res<WallpaperDamage>>,
     target: Option<Res<X11RenderTarget>>,
     images: Res<RenderAssets<GpuImage>>,
     render_device: Res<RenderDevice>,
     render_queue: Res<RenderQueue>,
+    descriptor: Res<X11SurfaceDescriptor>,
 ) {
     let Some(target) = target else {
         return;
     };
 
-    let Some(surface) = state.surface.as_ref() else {
-        return;
-    };
-
-    let Some(config) = state.config.as_ref() else {
+    let Some(gpu_image) = images.get(&target.image) else {
         return;
     };
 
-    let Some(gpu_image) = images.get(&target.image) else {
+    let Some((min_x, min_y, _, _)) = descriptor.overall_bounds() else {
         return;
     };
 
-    let extent = Extent3d {
-        width: config.width.min(gpu_image.texture_descriptor.size.width),
-        height: config.height.min(gpu_image.texture_descriptor.size.height),
-        depth_or_array_layers: 1,
-    };
+    let dirty = damage.is_none_or(|damage| damage.dirty);
 
-    let surface_texture = match surface.get_current_texture() {
-        CurrentSurfaceTexture::Success(texture) | CurrentSurfaceTexture::Suboptimal(texture) => {
-            texture
-        }
-        CurrentSurfaceTexture::Outdated | CurrentSurfaceTexture::Lost => {
-            warn!("X11 surface outdated/lost; scheduling recreate");
-            state.mark_stale();
-            return;
+    for (window, entry) in state.surfaces.iter_mut() {
+        if !dirty && !entry.needs_present {
+            continue;
         }
-        CurrentSurfaceTexture::Timeout | CurrentSurfaceTexture::Occluded => {
-            debug!("X11 surface acquire timeout");
-            return;
+        let Some(surface) = entry.surface.as_ref() else {
+            continue;
+        };
+        let Some(config) = entry.config.as_ref() else {
+            continue;
+        };
+        if config.format.remove_srgb_suffix()
+            != gpu_image.texture_descriptor.format.remove_srgb_suffix()
+        {
+            // The render target hasn't switched to the negotiated format yet.
+            continue;
         }
-        CurrentSurfaceTexture::Validation => {
-            error!("X11 surface validation failed");
-            return;
+        let Some(desc_entry) = descriptor.surfaces.iter().find(|s| s.window == *window) else {
+            continue;
+        };
+
+        let src_origin = Origin3d {
+            x: (desc_entry.offset_x - min_x).max(0) as u32,
+            y: (desc_entry.offset_y - min_y).max(0) as u32,
+            z: 0,
+        };
+        let image_size = gpu_image.texture_descriptor.size;
+        let extent = Extent3d {
+            width: config
+                .width
+                .min(image_size.width.saturating_sub(src_origin.x)),
+            height: config
+                .height
+                .min(image_size.height.saturating_sub(src_origin.y)),
+            depth_or_array_layers: 1,
+        };
+        if extent.width == 0 || extent.height == 0 {
+            // The render target hasn't been resized to cover this window yet.
+            continue;
         }
-    };
 
-    let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
-        label: Some("x11-surface-present"),
-    });
-
-    encoder.copy_texture_to_texture(
-        gpu_image.texture.as_image_copy(),
-        wgpu::TexelCopyTextureInfo {
-            texture: &surface_texture.texture,
-            mip_level: 0,
-            origin: Origin3d::ZERO,
-            aspect: TextureAspect::All,
-        },
-        extent,
-    );
+        let surface_texture = match surface.get_current_texture() {
+            CurrentSurfaceTexture::Success(texture)
+            | CurrentSurfaceTexture::Suboptimal(texture) => texture,
+            CurrentSurfaceTexture::Outdated | CurrentSurfaceTexture::Lost => {
+                entry.backoff.record_failure();
+                let info = entry.backoff.info();
+                if info.consecutive_failures == 1 {
+                    warn!(
+                        "X11 surface for window {} outdated/lost; scheduling recreate",
+                        window
+                    );
+                } else {
+                    debug!(
+                        "X11 surface for window {} outdated/lost {} times in a row; \
+                         recreating in {} frames",
+                        window, info.consecutive_failures, info.backoff_frames
+                    );
+                }
+                entry.mark_stale();
+                continue;
+            }
+            CurrentSurfaceTexture::Timeout | CurrentSurfaceTexture::Occluded => {
+                debug!("X11 surface acquire timeout (window {})", window);
+                continue;
+            }
+            CurrentSurfaceTexture::Validation => {
+                error!("X11 surface validation failed (window {})", window);
+                continue;
+            }
+        };
+
+        let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("x11-surface-present"),
+        });
+
+        let mut src = gpu_image.texture.as_image_copy();
+        src.origin = src_origin;
+
+        encoder.copy_texture_to_texture(
+            src,
+            wgpu::TexelCopyTextureInfo {
+                texture: &surface_texture.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            extent,
+        );
+
+        render_queue.submit(Some(encoder.finish()));
+        surface_texture.present();
+        entry.needs_present = false;
+        entry.backoff.reset();
+    }
+}
 
-    render_queue.submit(Some(encoder.finish()));
-    surface_texture.present();
+/// Publishes the recovery counters of each window's surface.
+pub(crate) fn publish_x11_surface_recovery(
+    state: Res<X11GpuSurfaceState>,
+    recovery: Res<SharedSurfaceRecovery>,
+) {
+    recovery.publish(
+        state
+            .surfaces
+            .iter()
+            .map(|(window, entry)| (*window, entry.backoff.info())),
+    );
 }